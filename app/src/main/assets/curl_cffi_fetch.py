@@ -1,21 +1,29 @@
 #!/usr/bin/env python3
 import json
+import os
 import sys
 
-if len(sys.argv) != 4:
-    raise SystemExit("expected args: <method> <url> <payload_json>")
+if len(sys.argv) not in (4, 5, 6):
+    raise SystemExit("expected args: <method> <url> <payload_json> [headers_json] [impersonate]")
 
 from curl_cffi import requests
 
 method = sys.argv[1].upper()
 url = sys.argv[2]
 payload = json.loads(sys.argv[3])
+headers = json.loads(sys.argv[4]) if len(sys.argv) >= 5 else {}
+impersonate = sys.argv[5] if len(sys.argv) == 6 else "chrome124"
 
 kwargs = {
     "url": url,
-    "impersonate": "chrome124",
+    "impersonate": impersonate,
     "timeout": 20,
 }
+if headers:
+    kwargs["headers"] = headers
+proxy_url = os.environ.get("WHIRLPOOL_PROXY_URL")
+if proxy_url:
+    kwargs["proxies"] = {"http": proxy_url, "https": proxy_url}
 if method == "GET":
     kwargs["params"] = payload
 else:
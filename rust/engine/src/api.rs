@@ -1,55 +1,162 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
 
+use chrono::{DateTime, Utc};
+use reqwest::header::{CONTENT_TYPE, RETRY_AFTER};
 use reqwest::StatusCode;
 use serde_json::json;
 use serde_json::Value;
 
-use crate::curl_cffi::fetch_with_curl_cffi;
+use crate::cancellation::{check_cancelled, CancellationToken};
+use crate::curl_cffi::CurlCffiBridge;
 use crate::errors::EngineError;
 use crate::models::{
-    ApiStatusChannel, ApiStatusResponse, ApiVideoRecord, EngineConfig,
-    FilterSelection, StatusChannel, StatusChoice, StatusFilterOption, StatusSummary, VideoItem,
+    ApiStatusChannel, ApiStatusChannelOption, ApiStatusChoice, ApiStatusResponse, ApiVideoRecord,
+    ChannelStatus, DiscoverPage, EngineConfig, FilterSelection, LinkPreview, NetworkStats,
+    ServerLatency, StatusChannel, StatusChoice, StatusFilterOption, StatusNotice, StatusSummary,
+    VideoItem,
 };
+use crate::preview::parse_link_preview;
+use crate::runtime;
 
 const DEFAULT_USER_AGENT: &str = "whirlpool-engine/0.1 (+android; uniffi)";
+const MAX_RATE_LIMIT_RETRIES: u32 = 2;
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(30);
 
 #[derive(Debug, Clone)]
 pub struct ApiClient {
     base_url: String,
-    python_executable: String,
-    curl_cffi_script_path: Option<String>,
+    fallback_base_urls: Vec<String>,
+    // Shared by clones so every copy of the engine's client follows the last base that answered.
+    preferred_base: Arc<Mutex<Option<String>>>,
+    network: Arc<NetworkCounters>,
+    curl_cffi: Option<CurlCffiBridge>,
+    accept_language: Option<String>,
+    proxy_url: Option<String>,
+    status_method: String,
+    videos_method: String,
+    strict_filters: bool,
+    max_response_bytes: Option<u64>,
+    timeouts: HttpTimeouts,
+    custom_headers: Vec<(String, String)>,
+    extra_params: Vec<(String, String)>,
 }
 
 impl ApiClient {
     pub fn new(config: &EngineConfig) -> Self {
+        let base_url = config.api_base_url.trim_end_matches('/').to_string();
+        let mut fallback_base_urls: Vec<String> = Vec::new();
+        for fallback in config.fallback_api_base_urls.iter().flatten() {
+            let fallback = fallback.trim().trim_end_matches('/');
+            if !fallback.is_empty()
+                && fallback != base_url
+                && !fallback_base_urls.iter().any(|known| known == fallback)
+            {
+                fallback_base_urls.push(fallback.to_string());
+            }
+        }
         Self {
-            base_url: config.api_base_url.trim_end_matches('/').to_string(),
-            python_executable: config.python_executable.clone(),
-            curl_cffi_script_path: config.curl_cffi_script_path.clone(),
+            base_url,
+            fallback_base_urls,
+            preferred_base: Arc::new(Mutex::new(None)),
+            network: Arc::new(NetworkCounters::default()),
+            curl_cffi: CurlCffiBridge::from_config(config),
+            accept_language: config
+                .accept_language
+                .as_deref()
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(ToOwned::to_owned),
+            proxy_url: configured_proxy(config).map(ToOwned::to_owned),
+            status_method: endpoint_method(config.status_method.as_deref()),
+            videos_method: endpoint_method(config.videos_method.as_deref()),
+            strict_filters: config.strict_filter_selections,
+            max_response_bytes: config.max_response_bytes,
+            timeouts: HttpTimeouts::from_config(config),
+            custom_headers: Vec::new(),
+            extra_params: Vec::new(),
+        }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub fn with_preferred_base(self, preferred: Option<String>) -> Self {
+        if let Ok(mut guard) = self.preferred_base.lock() {
+            *guard = preferred;
         }
+        self
+    }
+
+    pub fn preferred_base(&self) -> Option<String> {
+        self.preferred_base
+            .lock()
+            .ok()
+            .and_then(|guard| guard.clone())
+    }
+
+    pub fn with_network_counters(mut self, counters: Arc<NetworkCounters>) -> Self {
+        self.network = counters;
+        self
+    }
+
+    pub fn network_counters(&self) -> Arc<NetworkCounters> {
+        Arc::clone(&self.network)
+    }
+
+    pub fn with_custom_headers(mut self, headers: &HashMap<String, String>) -> Self {
+        let mut custom_headers: Vec<(String, String)> = headers
+            .iter()
+            .filter(|(name, _)| !name.trim().is_empty())
+            .map(|(name, value)| (name.trim().to_string(), value.clone()))
+            .collect();
+        custom_headers.sort();
+        self.custom_headers = custom_headers;
+        self
+    }
+
+    pub fn with_extra_params(mut self, params: &HashMap<String, String>) -> Self {
+        let mut extra_params: Vec<(String, String)> = params
+            .iter()
+            .filter(|(name, _)| !name.trim().is_empty())
+            .map(|(name, value)| (name.trim().to_string(), value.clone()))
+            .collect();
+        extra_params.sort();
+        self.extra_params = extra_params;
+        self
     }
 
     pub fn fetch_status(&self) -> Result<StatusSummary, EngineError> {
-        let parsed = self.fetch_status_payload()?;
-        let channels = parsed.channels.unwrap_or_default();
-        let channel_ids = channels.iter().map(|channel| channel.id.clone()).collect();
-        let channel_details = channels.into_iter().map(map_status_channel).collect();
-
-        Ok(StatusSummary {
-            name: parsed.name.unwrap_or_else(|| "unknown".to_string()),
-            api_version: parsed
-                .api_version
-                .or(parsed.id)
-                .unwrap_or_else(|| "unknown".to_string()),
-            icon_url: parsed.icon_url,
-            primary_color: parsed.primary_color.or(parsed.color),
-            secondary_color: parsed.secondary_color,
-            channels: channel_ids,
-            channel_details,
-            sources: parsed.sources.or(parsed.categories).unwrap_or_default(),
-            adblock_required: parsed.adblock_required.unwrap_or(false),
-            source_releases_url: parsed.source_releases_url,
-        })
+        let (base, parsed) = self.fetch_status_from(None)?;
+        Ok(map_status_summary(parsed, &base))
+    }
+
+    // Any failure to fetch or parse the status counts as unreachable; the elapsed time is still reported.
+    pub fn measure_latency(&self) -> ServerLatency {
+        let started = Instant::now();
+        let result = self.fetch_status_payload(None);
+        let latency_ms = started.elapsed().as_millis().try_into().unwrap_or(u64::MAX);
+        match result {
+            Ok(parsed) => ServerLatency {
+                reachable: true,
+                latency_ms,
+                api_version: trimmed_non_empty(parsed.api_version.or(parsed.id)),
+            },
+            Err(_) => ServerLatency {
+                reachable: false,
+                latency_ms,
+                api_version: None,
+            },
+        }
+    }
+
+    pub fn fetch_link_preview(&self, url: &str) -> Result<LinkPreview, EngineError> {
+        let response = self.fetch_text("GET", url, None, None)?;
+        Ok(parse_link_preview(&response.body, url))
     }
 
     pub fn discover_videos_with_filters(
@@ -59,8 +166,23 @@ impl ApiClient {
         limit: u32,
         channel_id: Option<&str>,
         selections: &[FilterSelection],
+        cancel: Option<&CancellationToken>,
     ) -> Result<Vec<VideoItem>, EngineError> {
-        let status = self.fetch_status_payload()?;
+        Ok(self
+            .discover_page_with_filters(query, page, limit, channel_id, selections, cancel)?
+            .videos)
+    }
+
+    pub fn discover_page_with_filters(
+        &self,
+        query: &str,
+        page: u32,
+        limit: u32,
+        channel_id: Option<&str>,
+        selections: &[FilterSelection],
+        cancel: Option<&CancellationToken>,
+    ) -> Result<DiscoverPage, EngineError> {
+        let status = self.fetch_status_payload(cancel)?;
         let selected_channel =
             select_channel_with_id_or_default(&status, channel_id).ok_or_else(|| {
                 EngineError::NotFound {
@@ -68,21 +190,119 @@ impl ApiClient {
                 }
             })?;
 
-        let payload =
-            build_videos_payload(selected_channel, query, page, limit, selections).to_string();
+        if self.strict_filters {
+            validate_selections(selected_channel, selections)?;
+        }
+        let mut payload = build_videos_payload(selected_channel, query, page, limit, selections);
+        merge_extra_params(&mut payload, selected_channel, &self.extra_params);
 
-        let primary = format!("{}/api/videos", self.base_url);
-        let body = self.fetch_text("POST", &primary, Some(&payload))?;
+        let (base, body) = if self.videos_method == "GET" {
+            self.fetch_from_bases("GET", "/api/videos", Some(&payload), None, cancel)?
+        } else {
+            let payload = payload.to_string();
+            self.fetch_from_bases(
+                &self.videos_method,
+                "/api/videos",
+                None,
+                Some(&payload),
+                cancel,
+            )?
+        };
+
+        parse_discover_page(&body, &selected_channel.id, &base)
+    }
+
+    pub fn default_selections(
+        &self,
+        channel_id: Option<&str>,
+    ) -> Result<Vec<FilterSelection>, EngineError> {
+        let status = self.fetch_status_payload(None)?;
+        let channel = select_channel_with_id_or_default(&status, channel_id).ok_or_else(|| {
+            EngineError::NotFound {
+                detail: "no active channel returned by /api/status".to_string(),
+            }
+        })?;
+        Ok(channel_default_selections(channel))
+    }
 
-        parse_videos(&body, &selected_channel.id)
+    fn fetch_status_payload(
+        &self,
+        cancel: Option<&CancellationToken>,
+    ) -> Result<ApiStatusResponse, EngineError> {
+        self.fetch_status_from(cancel).map(|(_, parsed)| parsed)
     }
 
-    fn fetch_status_payload(&self) -> Result<ApiStatusResponse, EngineError> {
-        let endpoint = format!("{}/api/status", self.base_url);
-        // Some upstream gateways reject POST requests without a Content-Length.
-        let body = self.fetch_text("POST", &endpoint, Some("{}"))?;
+    fn fetch_status_from(
+        &self,
+        cancel: Option<&CancellationToken>,
+    ) -> Result<(String, ApiStatusResponse), EngineError> {
+        let (base, body) = if self.status_method == "GET" {
+            self.fetch_from_bases("GET", "/api/status", None, None, cancel)?
+        } else {
+            // Some upstream gateways reject POST requests without a Content-Length.
+            self.fetch_from_bases(&self.status_method, "/api/status", None, Some("{}"), cancel)?
+        };
         let parsed = serde_json::from_str::<ApiStatusResponse>(&body)?;
-        Ok(parsed)
+        Ok((base, parsed))
+    }
+
+    // The preferred base goes first, then the primary and each fallback in order. Only network
+    // and HTTP failures move on to the next base; the base that answered is returned with the body.
+    fn fetch_from_bases(
+        &self,
+        method: &str,
+        path: &str,
+        query: Option<&Value>,
+        json_body: Option<&str>,
+        cancel: Option<&CancellationToken>,
+    ) -> Result<(String, String), EngineError> {
+        let mut last_error = None;
+        for base in self.candidate_bases() {
+            let mut url = format!("{base}{path}");
+            if let Some(query) = query {
+                url = url_with_query(&url, query)?;
+            }
+            match self
+                .fetch_text(method, &url, json_body, cancel)
+                .and_then(|response| expect_json(&url, response))
+            {
+                Ok(body) => {
+                    self.remember_base(&base);
+                    return Ok((base, body));
+                }
+                Err(err @ (EngineError::Network { .. } | EngineError::Http { .. })) => {
+                    last_error = Some(err);
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        Err(last_error.unwrap_or_else(|| EngineError::Network {
+            detail: "no api base url to try".to_string(),
+        }))
+    }
+
+    fn candidate_bases(&self) -> Vec<String> {
+        let known = std::iter::once(&self.base_url).chain(&self.fallback_base_urls);
+        let mut bases: Vec<String> = self
+            .preferred_base()
+            .filter(|preferred| known.clone().any(|base| base == preferred))
+            .into_iter()
+            .collect();
+        for base in known {
+            if !bases.contains(base) {
+                bases.push(base.clone());
+            }
+        }
+        bases
+    }
+
+    fn remember_base(&self, base: &str) {
+        if let Ok(mut preferred) = self.preferred_base.lock() {
+            // Without a preference the primary is implied, so there is nothing to record.
+            if preferred.is_some() || base != self.base_url {
+                *preferred = Some(base.to_string());
+            }
+        }
     }
 
     fn fetch_text(
@@ -90,75 +310,258 @@ impl ApiClient {
         method: &str,
         url: &str,
         json_body: Option<&str>,
-    ) -> Result<String, EngineError> {
-        let runtime = tokio::runtime::Builder::new_current_thread()
-            .enable_all()
-            .build()
-            .map_err(|err| EngineError::Network {
-                detail: format!("failed to build runtime: {err}"),
-            })?;
-
+        cancel: Option<&CancellationToken>,
+    ) -> Result<TextResponse, EngineError> {
+        check_cancelled(cancel)?;
         let request_method =
             reqwest::Method::from_bytes(method.as_bytes()).map_err(|err| EngineError::Network {
                 detail: format!("invalid request method {method}: {err}"),
             })?;
 
-        let request_result = runtime.block_on(async {
-            let client = reqwest::Client::builder()
-                .user_agent(DEFAULT_USER_AGENT)
-                .build()?;
+        let headers = self.request_headers();
+        let mut rate_limit_retries = 0;
+        let (status, content_type, body) = loop {
+            let request_result = runtime::block_on(async {
+                let client =
+                    http_client(DEFAULT_USER_AGENT, self.proxy_url.as_deref(), self.timeouts)?;
 
-            let mut request = client.request(request_method, url);
-            if let Some(body) = json_body {
-                request = request
-                    .header("Content-Type", "application/json")
-                    .body(body.to_owned());
-            }
+                let mut request = client.request(request_method.clone(), url);
+                for (name, value) in &headers {
+                    request = request.header(name.as_str(), value.as_str());
+                }
+                if let Some(body) = json_body {
+                    request = request
+                        .header("Content-Type", "application/json")
+                        .body(body.to_owned());
+                }
 
-            let response = request.send().await?;
-            let status = response.status();
-            let body = response.text().await?;
-            Ok::<(StatusCode, String), reqwest::Error>((status, body))
-        });
+                let response = request.send().await?;
+                let status = response.status();
+                let retry_after = response
+                    .headers()
+                    .get(RETRY_AFTER)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(parse_retry_after);
+                let content_type = response
+                    .headers()
+                    .get(CONTENT_TYPE)
+                    .and_then(|value| value.to_str().ok())
+                    .map(ToOwned::to_owned);
+                let body = read_capped_body(response, self.max_response_bytes).await?;
+                Ok::<_, reqwest::Error>((status, retry_after, content_type, body))
+            })?;
 
-        let (status, body) = match request_result {
-            Ok(result) => result,
-            Err(err) => {
-                if let Some(script_path) = &self.curl_cffi_script_path {
-                    return fetch_with_curl_cffi(
-                        &self.python_executable,
-                        script_path,
-                        method,
-                        url,
-                        json_body,
-                    );
+            let (status, retry_after, content_type, body) = match request_result {
+                Ok((status, retry_after, content_type, Some(body))) => {
+                    self.network.record(body.len());
+                    (status, retry_after, content_type, body)
                 }
-                return Err(EngineError::Network {
-                    detail: format!("network request failed: {err}"),
-                });
+                Ok((_, _, _, None)) => {
+                    return Err(EngineError::Network {
+                        detail: format!(
+                            "response from {} exceeded {} bytes",
+                            redact_query_tokens(url),
+                            self.max_response_bytes.unwrap_or_default()
+                        ),
+                    });
+                }
+                Err(err) => {
+                    check_cancelled(cancel)?;
+                    if let Some(bridge) = &self.curl_cffi {
+                        return bridge
+                            .fetch(
+                                method,
+                                url,
+                                json_body,
+                                &headers,
+                                self.proxy_url.as_deref(),
+                                self.max_response_bytes,
+                            )
+                            .map(|body| self.bridge_response(body));
+                    }
+                    return Err(EngineError::Network {
+                        detail: format!("network request failed: {err}"),
+                    });
+                }
+            };
+
+            // A 429 with Retry-After is retried in place before any curl-cffi escalation.
+            match retry_after {
+                Some(delay)
+                    if status == StatusCode::TOO_MANY_REQUESTS
+                        && rate_limit_retries < MAX_RATE_LIMIT_RETRIES =>
+                {
+                    rate_limit_retries += 1;
+                    thread::sleep(delay.min(MAX_RETRY_AFTER));
+                    check_cancelled(cancel)?;
+                }
+                _ => break (status, content_type, body),
             }
         };
 
         if status.is_success() {
-            return Ok(body);
+            return Ok(TextResponse { body, content_type });
         }
 
         if should_try_curl_cffi(status) {
-            if let Some(script_path) = &self.curl_cffi_script_path {
-                return fetch_with_curl_cffi(
-                    &self.python_executable,
-                    script_path,
-                    method,
-                    url,
-                    json_body,
-                );
+            check_cancelled(cancel)?;
+            if let Some(bridge) = &self.curl_cffi {
+                return bridge
+                    .fetch(
+                        method,
+                        url,
+                        json_body,
+                        &headers,
+                        self.proxy_url.as_deref(),
+                        self.max_response_bytes,
+                    )
+                    .map(|body| self.bridge_response(body));
             }
         }
 
-        Err(EngineError::Network {
-            detail: format!("request failed with status {status} at {url}: {body}"),
+        Err(EngineError::Http {
+            status: status.as_u16(),
+            url: redact_query_tokens(url),
+            body_snippet: payload_snippet(&body),
         })
     }
+
+    // The curl-cffi bridge only hands back the body.
+    fn bridge_response(&self, body: String) -> TextResponse {
+        self.network.record(body.len());
+        TextResponse {
+            body,
+            content_type: None,
+        }
+    }
+
+    fn request_headers(&self) -> Vec<(String, String)> {
+        let mut headers = Vec::new();
+        if let Some(accept_language) = &self.accept_language {
+            headers.push(("Accept-Language".to_string(), accept_language.clone()));
+        }
+        headers.extend(self.custom_headers.iter().cloned());
+        headers
+    }
+}
+
+pub fn configured_proxy(config: &EngineConfig) -> Option<&str> {
+    config
+        .proxy_url
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+}
+
+pub fn endpoint_method(method: Option<&str>) -> String {
+    method
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .unwrap_or("POST")
+        .to_ascii_uppercase()
+}
+
+// GET mirrors take the videos payload as query parameters; list values repeat their key.
+fn url_with_query(url: &str, payload: &Value) -> Result<String, EngineError> {
+    let mut url = reqwest::Url::parse(url).map_err(|err| EngineError::InvalidConfig {
+        detail: format!("invalid videos url {url}: {err}"),
+    })?;
+    if let Some(fields) = payload.as_object() {
+        let mut query = url.query_pairs_mut();
+        for (key, value) in fields {
+            match value {
+                Value::String(text) => {
+                    query.append_pair(key, text);
+                }
+                Value::Array(items) => {
+                    for item in items {
+                        query.append_pair(key, item.as_str().unwrap_or_default());
+                    }
+                }
+                other => {
+                    query.append_pair(key, &other.to_string());
+                }
+            }
+        }
+    }
+    Ok(url.into())
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HttpTimeouts {
+    pub connect: Option<Duration>,
+    pub total: Option<Duration>,
+}
+
+impl HttpTimeouts {
+    // `read_timeout_ms` bounds the whole request once connected; `request_timeout_ms` is the
+    // older single knob and only applies when no read timeout is set.
+    pub fn from_config(config: &EngineConfig) -> Self {
+        Self {
+            connect: config.connect_timeout_ms.map(Duration::from_millis),
+            total: config
+                .read_timeout_ms
+                .or(config.request_timeout_ms)
+                .map(Duration::from_millis),
+        }
+    }
+}
+
+pub fn http_client(
+    user_agent: &str,
+    proxy_url: Option<&str>,
+    timeouts: HttpTimeouts,
+) -> Result<reqwest::Client, reqwest::Error> {
+    let mut builder = reqwest::Client::builder().user_agent(user_agent);
+    if let Some(proxy_url) = proxy_url {
+        builder = builder.proxy(reqwest::Proxy::all(proxy_url)?);
+    }
+    if let Some(connect) = timeouts.connect {
+        builder = builder.connect_timeout(connect);
+    }
+    if let Some(total) = timeouts.total {
+        builder = builder.timeout(total);
+    }
+    builder.build()
+}
+
+// Retry-After is either delta-seconds or an HTTP-date; past dates mean "retry now".
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        (date.with_timezone(&Utc) - Utc::now())
+            .to_std()
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+// Reads the body chunk by chunk so an oversized response is dropped before it is fully buffered.
+// None means the cap was exceeded.
+async fn read_capped_body(
+    mut response: reqwest::Response,
+    max_bytes: Option<u64>,
+) -> Result<Option<String>, reqwest::Error> {
+    let Some(max_bytes) = max_bytes else {
+        return response.text().await.map(Some);
+    };
+    if response
+        .content_length()
+        .is_some_and(|length| length > max_bytes)
+    {
+        return Ok(None);
+    }
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        if body.len() as u64 + chunk.len() as u64 > max_bytes {
+            return Ok(None);
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(Some(String::from_utf8_lossy(&body).into_owned()))
 }
 
 fn should_try_curl_cffi(status: StatusCode) -> bool {
@@ -174,9 +577,10 @@ fn select_channel(status: &ApiStatusResponse) -> Option<&ApiStatusChannel> {
         .iter()
         .find(|channel| channel.default)
         .or_else(|| {
-            channels
-                .iter()
-                .find(|channel| channel.status.as_deref() == Some("active"))
+            channels.iter().find(|channel| {
+                channel.status.as_deref().and_then(ChannelStatus::parse)
+                    == Some(ChannelStatus::Active)
+            })
         })
         .or_else(|| channels.first())
 }
@@ -194,8 +598,71 @@ fn select_channel_with_id_or_default<'a>(
     select_channel(status)
 }
 
-fn map_status_channel(channel: ApiStatusChannel) -> StatusChannel {
+fn map_status_summary(parsed: ApiStatusResponse, base_url: &str) -> StatusSummary {
+    let channels = parsed.channels.unwrap_or_default();
+    let channel_ids = channels.iter().map(|channel| channel.id.clone()).collect();
+    let channel_details = channels
+        .into_iter()
+        .map(|channel| map_status_channel(channel, base_url))
+        .collect();
+
+    StatusSummary {
+        name: parsed.name.unwrap_or_else(|| "unknown".to_string()),
+        api_version: parsed
+            .api_version
+            .or(parsed.id)
+            .unwrap_or_else(|| "unknown".to_string()),
+        icon_url: trimmed_non_empty(parsed.icon_url).map(|url| resolve_relative_url(base_url, url)),
+        primary_color: parsed.primary_color.or(parsed.color),
+        secondary_color: parsed.secondary_color,
+        channels: channel_ids,
+        channel_details,
+        sources: parsed.sources.or(parsed.categories).unwrap_or_default(),
+        adblock_required: parsed.adblock_required.unwrap_or(false),
+        source_releases_url: parsed.source_releases_url,
+        subtitle: trimmed_non_empty(parsed.subtitle),
+        description: trimmed_non_empty(parsed.description),
+        message: trimmed_non_empty(parsed.message),
+        // A notice without a message has nothing to show in a banner.
+        notices: parsed
+            .notices
+            .into_iter()
+            .filter_map(|notice| {
+                Some(StatusNotice {
+                    level: trimmed_non_empty(notice.level),
+                    title: trimmed_non_empty(notice.title),
+                    message: trimmed_non_empty(notice.message)?,
+                })
+            })
+            .collect(),
+    }
+}
+
+fn resolve_relative_url(base_url: &str, value: String) -> String {
+    // Absolute URLs (any scheme) are kept verbatim; only scheme-less values are joined.
+    if value.trim().is_empty() || reqwest::Url::parse(&value).is_ok() {
+        return value;
+    }
+    reqwest::Url::parse(&format!("{}/", base_url.trim_end_matches('/')))
+        .and_then(|base| base.join(&value))
+        .map(|resolved| resolved.to_string())
+        .unwrap_or(value)
+}
+
+fn trimmed_non_empty(value: Option<String>) -> Option<String> {
+    value.and_then(|value| {
+        let trimmed = value.trim();
+        if trimmed.is_empty() {
+            None
+        } else {
+            Some(trimmed.to_string())
+        }
+    })
+}
+
+fn map_status_channel(channel: ApiStatusChannel, base_url: &str) -> StatusChannel {
     let title = channel.name.unwrap_or_else(|| channel.id.clone());
+    let status = channel.status.as_deref().and_then(ChannelStatus::parse);
     let description = channel.description.and_then(|value| {
         let trimmed = value.trim();
         if trimmed.is_empty() {
@@ -209,7 +676,7 @@ fn map_status_channel(channel: ApiStatusChannel) -> StatusChannel {
         if trimmed.is_empty() {
             None
         } else {
-            Some(trimmed.to_string())
+            Some(resolve_relative_url(base_url, trimmed.to_string()))
         }
     });
     let ytdlp_command = channel.ytdlp_command.and_then(|value| {
@@ -249,7 +716,55 @@ fn map_status_channel(channel: ApiStatusChannel) -> StatusChannel {
         favicon_url,
         ytdlp_command,
         options,
+        status,
+    }
+}
+
+// The source has no explicit default flag per choice; the first listed choice is the default.
+fn default_choice(option: &ApiStatusChannelOption) -> Option<&ApiStatusChoice> {
+    option.options.first()
+}
+
+fn channel_default_selections(channel: &ApiStatusChannel) -> Vec<FilterSelection> {
+    channel
+        .options
+        .iter()
+        .filter(|option| !option.id.trim().is_empty())
+        .filter_map(|option| {
+            default_choice(option).map(|choice| FilterSelection {
+                option_id: option.id.clone(),
+                choice_id: choice.id.clone(),
+            })
+        })
+        .collect()
+}
+
+// Strict mode: every selection must name an option and choice the channel actually offers,
+// instead of being dropped or replaced by the default choice.
+fn validate_selections(
+    channel: &ApiStatusChannel,
+    selections: &[FilterSelection],
+) -> Result<(), EngineError> {
+    for selection in selections {
+        let option_id = selection.option_id.trim();
+        let option = channel
+            .options
+            .iter()
+            .find(|option| option.id == option_id)
+            .ok_or_else(|| EngineError::InvalidConfig {
+                detail: format!(
+                    "unknown filter option {option_id:?} for channel {}",
+                    channel.id
+                ),
+            })?;
+        let choice_id = selection.choice_id.trim();
+        if !option.options.iter().any(|choice| choice.id == choice_id) {
+            return Err(EngineError::InvalidConfig {
+                detail: format!("unknown choice {choice_id:?} for filter option {option_id:?}"),
+            });
+        }
     }
+    Ok(())
 }
 
 fn build_videos_payload(
@@ -301,7 +816,7 @@ fn build_videos_payload(
                     })
                     .collect();
                 payload.insert(option.id.clone(), json!(selected_ids));
-            } else if let Some(default_choice) = option.options.first() {
+            } else if let Some(default_choice) = default_choice(option) {
                 payload.insert(option.id.clone(), json!(vec![default_choice.id.as_str()]));
             }
         } else {
@@ -315,7 +830,7 @@ fn build_videos_payload(
                             .map(|choice| choice.id.as_str())
                     })
                 })
-                .or_else(|| option.options.first().map(|choice| choice.id.as_str()));
+                .or_else(|| default_choice(option).map(|choice| choice.id.as_str()));
 
             if let Some(selected_id) = selected_id {
                 payload.insert(option.id.clone(), json!(selected_id));
@@ -326,38 +841,267 @@ fn build_videos_payload(
     serde_json::Value::Object(payload)
 }
 
-fn parse_videos(body: &str, default_channel_id: &str) -> Result<Vec<VideoItem>, EngineError> {
-    let root = serde_json::from_str::<Value>(body)?;
+// Pass-through knobs only fill keys the payload doesn't already own: the fixed fields and every
+// declared channel option stay under `build_videos_payload` (and strict validation) control.
+fn merge_extra_params(
+    payload: &mut serde_json::Value,
+    channel: &ApiStatusChannel,
+    extra_params: &[(String, String)],
+) {
+    let Some(payload) = payload.as_object_mut() else {
+        return;
+    };
+    for (name, value) in extra_params {
+        if payload.contains_key(name) || channel.options.iter().any(|option| option.id == *name) {
+            continue;
+        }
+        payload.insert(name.clone(), json!(value));
+    }
+}
+
+#[cfg(test)]
+fn parse_videos(
+    body: &str,
+    default_channel_id: &str,
+    base_url: &str,
+) -> Result<Vec<VideoItem>, EngineError> {
+    parse_discover_page(body, default_channel_id, base_url).map(|page| page.videos)
+}
+
+fn parse_discover_page(
+    body: &str,
+    default_channel_id: &str,
+    base_url: &str,
+) -> Result<DiscoverPage, EngineError> {
+    let root = serde_json::from_str::<Value>(body).map_err(|err| EngineError::Serialization {
+        detail: format!(
+            "videos response is not valid JSON: {err}; body: {}",
+            payload_snippet(body)
+        ),
+    })?;
+    let page = |videos: Vec<VideoItem>, page_info: Option<&Value>| DiscoverPage {
+        videos,
+        has_next_page: page_info
+            .and_then(|info| info.get("hasNextPage"))
+            .and_then(Value::as_bool)
+            .unwrap_or(false),
+        recommendations: page_info
+            .and_then(|info| info.get("recommendations"))
+            .and_then(Value::as_array)
+            .map(|items| parse_recommendations(items, default_channel_id, base_url))
+            .unwrap_or_default(),
+    };
     match root {
         Value::Object(obj) => {
-            if let Some(items) = obj.get("videos").and_then(Value::as_array) {
-                return parse_video_array(items, default_channel_id);
+            let page_info = obj.get("pageInfo");
+            // Some sources fill both lists (results in `videos`, extras in `items`). Both are
+            // merged videos-first, and the first occurrence of an id wins.
+            let arrays: Vec<&Vec<Value>> = [obj.get("videos"), obj.get("items")]
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_array)
+                .collect();
+            if !arrays.is_empty() {
+                let mut seen = HashSet::new();
+                let mut videos = Vec::new();
+                for items in arrays {
+                    for video in parse_video_array(items, default_channel_id, base_url)? {
+                        if seen.insert(video.id.clone()) {
+                            videos.push(video);
+                        }
+                    }
+                }
+                return Ok(page(videos, page_info));
             }
-            if let Some(items) = obj.get("items").and_then(Value::as_array) {
-                return parse_video_array(items, default_channel_id);
+            // A recognized envelope with a null list is a genuine "no results" page.
+            if [obj.get("videos"), obj.get("items")].contains(&Some(&Value::Null)) {
+                return Ok(page(Vec::new(), page_info));
             }
-            Err(EngineError::Serialization {
-                detail: "unexpected videos payload shape".to_string(),
-            })
+            Err(unexpected_videos_shape(body))
+        }
+        Value::Array(items) => Ok(page(
+            parse_video_array(&items, default_channel_id, base_url)?,
+            None,
+        )),
+        _ => Err(unexpected_videos_shape(body)),
+    }
+}
+
+// Recommendations are best-effort extras: malformed or url-less entries are dropped.
+fn parse_recommendations(
+    items: &[Value],
+    default_channel_id: &str,
+    base_url: &str,
+) -> Vec<VideoItem> {
+    items
+        .iter()
+        .filter_map(|raw| {
+            let record = serde_json::from_value::<ApiVideoRecord>(raw.clone()).ok()?;
+            if record
+                .url
+                .as_deref()
+                .map_or(true, |url| url.trim().is_empty())
+            {
+                return None;
+            }
+            Some(map_video_record(
+                record,
+                default_channel_id,
+                base_url,
+                serde_json::to_string_pretty(raw).ok(),
+            ))
+        })
+        .collect()
+}
+
+fn unexpected_videos_shape(body: &str) -> EngineError {
+    EngineError::Serialization {
+        detail: format!(
+            "unexpected videos payload shape (expected a videos/items envelope or an array); body: {}",
+            payload_snippet(body)
+        ),
+    }
+}
+
+const PAYLOAD_SNIPPET_CHARS: usize = 500;
+
+const REDACTED_QUERY_PARAMS: [&str; 10] = [
+    "token",
+    "access_token",
+    "auth",
+    "key",
+    "api_key",
+    "apikey",
+    "sig",
+    "signature",
+    "session",
+    "password",
+];
+
+// Shared by every client the engine builds; each response read by `fetch_text` counts once, retries
+// and failed statuses included, since they cost data too.
+#[derive(Debug, Default)]
+pub struct NetworkCounters {
+    requests: AtomicU64,
+    bytes_downloaded: AtomicU64,
+}
+
+impl NetworkCounters {
+    pub fn new(stats: NetworkStats) -> Self {
+        Self {
+            requests: AtomicU64::new(stats.requests),
+            bytes_downloaded: AtomicU64::new(stats.bytes_downloaded),
+        }
+    }
+
+    pub fn snapshot(&self) -> NetworkStats {
+        NetworkStats {
+            requests: self.requests.load(Ordering::Relaxed),
+            bytes_downloaded: self.bytes_downloaded.load(Ordering::Relaxed),
+        }
+    }
+
+    pub fn reset(&self) {
+        self.requests.store(0, Ordering::Relaxed);
+        self.bytes_downloaded.store(0, Ordering::Relaxed);
+    }
+
+    fn record(&self, bytes: usize) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        self.bytes_downloaded
+            .fetch_add(bytes.try_into().unwrap_or(u64::MAX), Ordering::Relaxed);
+    }
+}
+
+struct TextResponse {
+    body: String,
+    content_type: Option<String>,
+}
+
+// Captive portals and error pages answer API paths with HTML; flag that as a network problem
+// instead of letting serde report a confusing parse error. The type alone isn't trusted: plenty of
+// servers label real JSON as text/html, so the body must not look like JSON either.
+fn expect_json(url: &str, response: TextResponse) -> Result<String, EngineError> {
+    let Some(content_type) = response.content_type.as_deref() else {
+        return Ok(response.body);
+    };
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    let textual = mime.starts_with("text/") || mime == "application/xhtml+xml";
+    if textual && !response.body.trim_start().starts_with(['{', '[']) {
+        return Err(EngineError::Network {
+            detail: format!(
+                "expected JSON from {} but received {mime} (captive portal or error page?): {}",
+                redact_query_tokens(url),
+                payload_snippet(&response.body)
+            ),
+        });
+    }
+    Ok(response.body)
+}
+
+pub fn payload_snippet(body: &str) -> String {
+    let redacted = redact_query_tokens(body.trim());
+    match redacted.char_indices().nth(PAYLOAD_SNIPPET_CHARS) {
+        Some((cut, _)) => format!("{}...", &redacted[..cut]),
+        None => redacted,
+    }
+}
+
+fn redact_query_tokens(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(offset) = rest.find(['?', '&']) {
+        let (before, after) = rest.split_at(offset + 1);
+        out.push_str(before);
+        rest = after;
+
+        let name_len = rest
+            .find(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '_' || ch == '-'))
+            .unwrap_or(rest.len());
+        let (name, tail) = rest.split_at(name_len);
+        let sensitive = REDACTED_QUERY_PARAMS
+            .iter()
+            .any(|param| param.eq_ignore_ascii_case(name));
+        if !sensitive || !tail.starts_with('=') {
+            continue;
         }
-        Value::Array(items) => parse_video_array(&items, default_channel_id),
-        _ => Err(EngineError::Serialization {
-            detail: "unexpected videos payload shape".to_string(),
-        }),
+
+        let value_len = tail[1..]
+            .find(|ch: char| matches!(ch, '&' | '"' | '\'' | '#') || ch.is_whitespace())
+            .unwrap_or(tail.len() - 1);
+        out.push_str(name);
+        out.push_str("=REDACTED");
+        rest = &tail[1 + value_len..];
     }
+    out.push_str(rest);
+    out
 }
 
 fn parse_video_array(
     items: &[Value],
     default_channel_id: &str,
+    base_url: &str,
 ) -> Result<Vec<VideoItem>, EngineError> {
     items
         .iter()
         .map(|raw| {
-            let record = serde_json::from_value::<ApiVideoRecord>(raw.clone())?;
+            let record = serde_json::from_value::<ApiVideoRecord>(raw.clone()).map_err(|err| {
+                EngineError::Serialization {
+                    detail: format!(
+                        "invalid video record: {err}; item: {}",
+                        payload_snippet(&raw.to_string())
+                    ),
+                }
+            })?;
             Ok(map_video_record(
                 record,
                 default_channel_id,
+                base_url,
                 serde_json::to_string_pretty(raw).ok(),
             ))
         })
@@ -367,6 +1111,7 @@ fn parse_video_array(
 fn map_video_record(
     record: ApiVideoRecord,
     default_channel_id: &str,
+    base_url: &str,
     raw_json: Option<String>,
 ) -> VideoItem {
     let page_url = record.url.unwrap_or_default();
@@ -381,7 +1126,9 @@ fn map_video_record(
         title: record.title.unwrap_or_else(|| "Untitled".to_string()),
         page_url,
         duration_seconds: record.duration,
-        image_url: record.image,
+        image_url: record
+            .image
+            .map(|image| resolve_relative_url(base_url, image)),
         network: record
             .network
             .or_else(|| Some(default_channel_id.to_string())),
@@ -389,15 +1136,95 @@ fn map_video_record(
         extractor: record.extractor,
         view_count: record.view_count,
         raw_json,
+        aspect_ratio: record
+            .aspect_ratio
+            .filter(|ratio| ratio.is_finite() && *ratio > 0.0),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::{
+        test_config, MockResponse, MockServer, FIGLEAF_STATUS, STATUS_WITH_TWO_CHANNELS,
+    };
 
     #[test]
     fn parses_getfigleaf_status_with_channel_objects() {
+        let payload = FIGLEAF_STATUS;
+
+        let parsed: ApiStatusResponse = serde_json::from_str(payload).expect("parse status");
+        let channel = select_channel(&parsed).expect("select channel");
+        assert_eq!(channel.id, "catflix");
+        assert!(channel.default);
+        let payload = build_videos_payload(channel, "", 1, 10, &[]);
+        assert_eq!(
+            payload.get("sort").and_then(|value| value.as_str()),
+            Some("views")
+        );
+    }
+
+    #[test]
+    fn channel_status_is_parsed_once_and_drives_selection() {
+        let parsed: ApiStatusResponse = serde_json::from_value(serde_json::json!({
+            "channels": [
+                { "id": "first", "status": "Inactive" },
+                { "id": "second", "status": " ACTIVE " },
+                { "id": "third", "status": "maintenance" },
+                { "id": "fourth", "status": "  " },
+            ],
+        }))
+        .expect("parse status");
+        assert_eq!(select_channel(&parsed).expect("channel").id, "second");
+
+        let summary = map_status_summary(parsed, "https://example.com");
+        let statuses: Vec<_> = summary
+            .channel_details
+            .into_iter()
+            .map(|channel| channel.status)
+            .collect();
+        assert_eq!(
+            statuses,
+            [
+                Some(ChannelStatus::Inactive),
+                Some(ChannelStatus::Active),
+                Some(ChannelStatus::Unknown("maintenance".to_string())),
+                None,
+            ]
+        );
+
+        let with_default: ApiStatusResponse = serde_json::from_value(serde_json::json!({
+            "channels": [
+                { "id": "live", "status": "active" },
+                { "id": "preferred", "status": "inactive", "default": true },
+            ],
+        }))
+        .expect("parse status");
+        assert_eq!(
+            select_channel(&with_default).expect("channel").id,
+            "preferred"
+        );
+
+        let none_active: ApiStatusResponse = serde_json::from_value(serde_json::json!({
+            "channels": [{ "id": "only", "status": "inactive" }],
+        }))
+        .expect("parse status");
+        assert_eq!(select_channel(&none_active).expect("channel").id, "only");
+    }
+
+    #[test]
+    fn default_selections_use_first_choice_per_option() {
+        let parsed: ApiStatusResponse = serde_json::from_str(FIGLEAF_STATUS).expect("parse status");
+        let channel = select_channel(&parsed).expect("select channel");
+
+        let defaults = channel_default_selections(channel);
+        assert_eq!(defaults.len(), 1);
+        assert_eq!(defaults[0].option_id, "sort");
+        assert_eq!(defaults[0].choice_id, "views");
+    }
+
+    #[test]
+    fn status_summary_keeps_subtitle_description_and_message() {
         let payload = r##"{
             "id": "figleaf",
             "name": "Fig Leaf",
@@ -406,45 +1233,22 @@ mod tests {
             "iconUrl": "https://cdn.hottubapp.io/assets/channels/figleaf.png",
             "color": "#478003",
             "status": "normal",
-            "notices": [],
-            "channels": [
-                {
-                    "id": "catflix",
-                    "name": "Catflix",
-                    "description": "All cats, all the time.",
-                    "color": "indigo",
-                    "status": "active",
-                    "default": true,
-                    "options": [
-                        {
-                            "id": "sort",
-                            "title": "Sort",
-                            "options": [
-                                { "id": "views", "title": "Views" },
-                                { "id": "likes", "title": "Likes" },
-                                { "id": "latest", "title": "Latest" }
-                            ]
-                        }
-                    ],
-                    "categories": ["Funny Cats", "Kittens"],
-                    "ytdlpCommand": "--format best[ext=mp4]"
-                }
-            ],
-            "subscription": { "status": "inactive" },
-            "nsfw": false,
+            "channels": [{ "id": "catflix", "name": "Catflix", "default": true }],
             "categories": ["Cute", "Funny"],
             "message": "New tutorials available."
         }"##;
 
         let parsed: ApiStatusResponse = serde_json::from_str(payload).expect("parse status");
-        let channel = select_channel(&parsed).expect("select channel");
-        assert_eq!(channel.id, "catflix");
-        assert!(channel.default);
-        let payload = build_videos_payload(channel, "", 1, 10, &[]);
+        let summary = map_status_summary(parsed, "https://getfigleaf.com");
+        assert_eq!(summary.name, "Fig Leaf");
+        assert_eq!(summary.subtitle.as_deref(), Some("Watch it!"));
         assert_eq!(
-            payload.get("sort").and_then(|value| value.as_str()),
-            Some("views")
+            summary.description.as_deref(),
+            Some("A source for all your fig leaf needs.")
         );
+        assert_eq!(summary.message.as_deref(), Some("New tutorials available."));
+        assert_eq!(summary.primary_color.as_deref(), Some("#478003"));
+        assert_eq!(summary.channels, vec!["catflix".to_string()]);
     }
 
     #[test]
@@ -465,7 +1269,7 @@ mod tests {
 
         let channel: ApiStatusChannel =
             serde_json::from_str(payload).expect("parse api status channel");
-        let mapped = map_status_channel(channel);
+        let mapped = map_status_channel(channel, "https://cdn.example.com");
 
         assert_eq!(mapped.id, "catflix");
         assert_eq!(mapped.title, "Catflix");
@@ -516,7 +1320,8 @@ mod tests {
             ]
         }"#;
 
-        let videos = parse_videos(payload, "catflix").expect("parse items envelope");
+        let videos = parse_videos(payload, "catflix", "https://getfigleaf.com")
+            .expect("parse items envelope");
         assert_eq!(videos.len(), 2);
         assert_eq!(
             videos[0].id,
@@ -556,7 +1361,8 @@ mod tests {
             }]
         }"#;
 
-        let videos = parse_videos(payload, "catflix").expect("parse items envelope");
+        let videos = parse_videos(payload, "catflix", "https://getfigleaf.com")
+            .expect("parse items envelope");
         assert_eq!(videos.len(), 1);
         assert_eq!(videos[0].id, "abc");
         assert_eq!(videos[0].network.as_deref(), Some("catflix"));
@@ -571,6 +1377,129 @@ mod tests {
             .is_some_and(|payload| payload.contains("\"channel\": \"catflix\"")));
     }
 
+    #[test]
+    fn resolves_relative_thumbnails_and_favicons_against_base_url() {
+        let payload = r#"{
+            "items": [
+                { "id": "a", "url": "https://example.com/a", "thumb": "/assets/a.jpg" },
+                { "id": "b", "url": "https://example.com/b", "thumb": "thumbs/b.jpg" },
+                { "id": "c", "url": "https://example.com/c", "thumb": "//cdn.example.com/c.jpg" },
+                { "id": "d", "url": "https://example.com/d", "thumb": "https://img.example.com/d.jpg" }
+            ]
+        }"#;
+
+        let videos =
+            parse_videos(payload, "catflix", "https://getfigleaf.com/").expect("parse videos");
+        let images: Vec<Option<&str>> = videos
+            .iter()
+            .map(|video| video.image_url.as_deref())
+            .collect();
+        assert_eq!(
+            images,
+            vec![
+                Some("https://getfigleaf.com/assets/a.jpg"),
+                Some("https://getfigleaf.com/thumbs/b.jpg"),
+                Some("https://cdn.example.com/c.jpg"),
+                Some("https://img.example.com/d.jpg"),
+            ]
+        );
+
+        let status: ApiStatusResponse = serde_json::from_str(
+            r#"{
+                "iconUrl": "/icon.png",
+                "channels": [{ "id": "catflix", "favicon": "/favicons/catflix.png" }]
+            }"#,
+        )
+        .expect("parse status");
+        let summary = map_status_summary(status, "https://getfigleaf.com");
+        assert_eq!(
+            summary.icon_url.as_deref(),
+            Some("https://getfigleaf.com/icon.png")
+        );
+        assert_eq!(
+            summary.channel_details[0].favicon_url.as_deref(),
+            Some("https://getfigleaf.com/favicons/catflix.png")
+        );
+    }
+
+    #[test]
+    fn merges_videos_and_items_videos_first_without_duplicates() {
+        let payload = r#"{
+            "videos": [
+                { "id": "a", "title": "A", "url": "https://example.com/a" },
+                { "id": "b", "title": "B", "url": "https://example.com/b" }
+            ],
+            "items": [
+                { "id": "b", "title": "B again", "url": "https://example.com/b2" },
+                { "id": "c", "title": "C", "url": "https://example.com/c" }
+            ]
+        }"#;
+
+        let videos =
+            parse_videos(payload, "catflix", "https://getfigleaf.com").expect("parse videos");
+
+        let ids: Vec<&str> = videos.iter().map(|video| video.id.as_str()).collect();
+        assert_eq!(ids, ["a", "b", "c"]);
+        assert_eq!(videos[1].title, "B");
+
+        let empty_videos =
+            r#"{ "videos": [], "items": [{ "id": "c", "url": "https://example.com/c" }] }"#;
+        let videos =
+            parse_videos(empty_videos, "catflix", "https://getfigleaf.com").expect("items only");
+        assert_eq!(videos.len(), 1);
+    }
+
+    #[test]
+    fn empty_items_envelope_is_ok_and_distinct_from_shape_mismatch() {
+        let empty = r#"{ "pageInfo": { "hasNextPage": false }, "items": [] }"#;
+        assert!(parse_videos(empty, "catflix", "https://getfigleaf.com")
+            .expect("empty items envelope")
+            .is_empty());
+
+        let null_items = r#"{ "pageInfo": { "hasNextPage": false }, "items": null }"#;
+        assert!(
+            parse_videos(null_items, "catflix", "https://getfigleaf.com")
+                .expect("null items envelope")
+                .is_empty()
+        );
+
+        let mismatch = r#"{ "pageInfo": { "hasNextPage": false } }"#;
+        assert!(matches!(
+            parse_videos(mismatch, "catflix", "https://getfigleaf.com"),
+            Err(EngineError::Serialization { .. })
+        ));
+    }
+
+    #[test]
+    fn unexpected_videos_shape_includes_redacted_snippet() {
+        let payload = r#"{ "results": [], "next": "https://example.com/api/videos?page=2&token=s3cr3t&x=1" }"#;
+
+        let err =
+            parse_videos(payload, "catflix", "https://getfigleaf.com").expect_err("shape mismatch");
+        let EngineError::Serialization { detail } = err else {
+            panic!("expected serialization error, got {err:?}");
+        };
+        assert!(detail.contains("unexpected videos payload shape"));
+        assert!(detail.contains("\"results\": []"));
+        assert!(detail.contains("token=REDACTED&x=1"));
+        assert!(!detail.contains("s3cr3t"));
+    }
+
+    #[test]
+    fn invalid_videos_json_is_reported_separately_and_truncated() {
+        let payload = format!("<html>{}</html>", "x".repeat(2_000));
+
+        let err =
+            parse_videos(&payload, "catflix", "https://getfigleaf.com").expect_err("invalid json");
+        let EngineError::Serialization { detail } = err else {
+            panic!("expected serialization error, got {err:?}");
+        };
+        assert!(detail.starts_with("videos response is not valid JSON"));
+        assert!(detail.contains("body: <html>xxx"));
+        assert!(detail.ends_with("..."));
+        assert!(detail.len() < 700);
+    }
+
     #[test]
     fn selects_default_channel_and_latest_sort() {
         let status: ApiStatusResponse = serde_json::from_str(
@@ -663,6 +1592,110 @@ mod tests {
         );
     }
 
+    #[test]
+    fn extra_params_fill_payload_without_clobbering_declared_options() {
+        let status: ApiStatusResponse = serde_json::from_str(
+            r#"{
+                "channels": [{
+                    "id": "catflix",
+                    "default": true,
+                    "options": [{
+                        "id": "sort",
+                        "options": [{ "id": "new" }, { "id": "latest" }]
+                    }, {
+                        "id": "hidden",
+                        "options": []
+                    }]
+                }]
+            }"#,
+        )
+        .expect("parse status");
+        let channel = select_channel(&status).expect("default channel");
+        let client = ApiClient::new(&test_config("http://127.0.0.1:9", "unused.db"))
+            .with_extra_params(&HashMap::from([
+                ("region".to_string(), "eu".to_string()),
+                ("sort".to_string(), "views".to_string()),
+                ("hidden".to_string(), "1".to_string()),
+                ("query".to_string(), "dogs".to_string()),
+                (" ".to_string(), "blank".to_string()),
+            ]));
+
+        let mut payload = build_videos_payload(channel, "kittens", 1, 10, &[]);
+        merge_extra_params(&mut payload, channel, &client.extra_params);
+
+        assert_eq!(
+            payload.get("region").and_then(|value| value.as_str()),
+            Some("eu")
+        );
+        assert_eq!(
+            payload.get("sort").and_then(|value| value.as_str()),
+            Some("new")
+        );
+        assert_eq!(
+            payload.get("query").and_then(|value| value.as_str()),
+            Some("kittens")
+        );
+        assert!(payload.get("hidden").is_none());
+        assert_eq!(payload.as_object().map(|object| object.len()), Some(6));
+    }
+
+    #[test]
+    fn strict_mode_rejects_unknown_filter_selections() {
+        let status = r#"{
+            "channels": [{
+                "id": "catflix",
+                "default": true,
+                "options": [{
+                    "id": "sort",
+                    "options": [{ "id": "new" }, { "id": "latest" }]
+                }]
+            }]
+        }"#;
+        let server = MockServer::start(vec![
+            ("/api/status", MockResponse::json(status)),
+            ("/api/videos", MockResponse::json(r#"{ "items": [] }"#)),
+        ]);
+        let selection = |option_id: &str, choice_id: &str| FilterSelection {
+            option_id: option_id.to_string(),
+            choice_id: choice_id.to_string(),
+        };
+        let mut config = test_config(server.base_url(), "/tmp/unused.sqlite");
+
+        let lenient = ApiClient::new(&config);
+        lenient
+            .discover_videos_with_filters("", 1, 10, None, &[selection("sort", "oldest")], None)
+            .expect("lenient unknown choice");
+        lenient
+            .discover_videos_with_filters("", 1, 10, None, &[selection("color", "red")], None)
+            .expect("lenient unknown option");
+        assert_eq!(server.requests_to("/api/videos").len(), 2);
+
+        config.strict_filter_selections = true;
+        let strict = ApiClient::new(&config);
+        strict
+            .discover_videos_with_filters("", 1, 10, None, &[selection("sort", "latest")], None)
+            .expect("strict known choice");
+        for (option_id, choice_id, expected) in [
+            ("sort", "oldest", "unknown choice"),
+            ("color", "red", "unknown filter option"),
+        ] {
+            let err = strict
+                .discover_videos_with_filters(
+                    "",
+                    1,
+                    10,
+                    None,
+                    &[selection(option_id, choice_id)],
+                    None,
+                )
+                .expect_err("strict rejects unknown selection");
+            assert!(
+                matches!(err, EngineError::InvalidConfig { ref detail } if detail.contains(expected))
+            );
+        }
+        assert_eq!(server.requests_to("/api/videos").len(), 3);
+    }
+
     #[test]
     fn payload_supports_multi_select_options() {
         let status: ApiStatusResponse = serde_json::from_str(
@@ -754,6 +1787,348 @@ mod tests {
         assert!(selected.is_empty(), "deselect all should serialize as empty array");
     }
 
+    #[test]
+    fn sends_accept_language_only_when_configured() {
+        let server = MockServer::start(vec![(
+            "/api/status",
+            MockResponse::json(STATUS_WITH_TWO_CHANNELS),
+        )]);
+
+        let mut config = test_config(server.base_url(), "/tmp/unused.sqlite");
+        ApiClient::new(&config)
+            .fetch_status()
+            .expect("status without language");
+        config.accept_language = Some("de-DE,de;q=0.9".to_string());
+        ApiClient::new(&config)
+            .fetch_status()
+            .expect("status with language");
+
+        let requests = server.requests_to("/api/status");
+        assert_eq!(requests.len(), 2);
+        assert!(!requests[0].headers.contains_key("accept-language"));
+        assert_eq!(
+            requests[1]
+                .headers
+                .get("accept-language")
+                .map(String::as_str),
+            Some("de-DE,de;q=0.9")
+        );
+    }
+
+    #[test]
+    fn maps_status_notices() {
+        let payload = r#"{
+            "id": "figleaf",
+            "name": "Fig Leaf",
+            "message": "Scheduled maintenance tonight",
+            "notices": [
+                { "level": "warning", "title": "Maintenance", "message": "Down 02:00-03:00 UTC" },
+                { "message": "  Content policy updated  " },
+                { "level": "info", "title": "Empty" }
+            ]
+        }"#;
+        let parsed: ApiStatusResponse = serde_json::from_str(payload).expect("parse status");
+
+        let summary = map_status_summary(parsed, "https://getfigleaf.com");
+
+        assert_eq!(
+            summary.notices,
+            [
+                StatusNotice {
+                    level: Some("warning".to_string()),
+                    title: Some("Maintenance".to_string()),
+                    message: "Down 02:00-03:00 UTC".to_string(),
+                },
+                StatusNotice {
+                    level: None,
+                    title: None,
+                    message: "Content policy updated".to_string(),
+                },
+            ]
+        );
+        assert_eq!(
+            summary.message.as_deref(),
+            Some("Scheduled maintenance tonight")
+        );
+    }
+
+    #[test]
+    fn parses_page_info_recommendations() {
+        let payload = r#"{
+            "pageInfo": {
+                "hasNextPage": true,
+                "recommendations": [
+                    { "id": "rec-1", "title": "First", "url": "https://example.com/r/1" },
+                    { "id": "rec-2", "title": "Second", "url": "https://example.com/r/2", "thumb": "/t/2.jpg" },
+                    { "id": "rec-3", "title": "No url" }
+                ]
+            },
+            "items": [{ "id": "clip", "url": "https://example.com/v/1" }]
+        }"#;
+
+        let page = parse_discover_page(payload, "catflix", "https://getfigleaf.com")
+            .expect("parse discover page");
+
+        assert!(page.has_next_page);
+        assert_eq!(page.videos.len(), 1);
+        let ids: Vec<&str> = page
+            .recommendations
+            .iter()
+            .map(|video| video.id.as_str())
+            .collect();
+        assert_eq!(ids, ["rec-1", "rec-2"]);
+        assert_eq!(
+            page.recommendations[1].image_url.as_deref(),
+            Some("https://getfigleaf.com/t/2.jpg")
+        );
+        assert_eq!(page.recommendations[0].network.as_deref(), Some("catflix"));
+    }
+
+    #[test]
+    fn sequential_requests_share_one_runtime() {
+        let mut routes = Vec::new();
+        for page in 1..=3 {
+            routes.push(("/api/status", MockResponse::json(STATUS_WITH_TWO_CHANNELS)));
+            routes.push((
+                "/api/videos",
+                MockResponse::json(&format!(
+                    r#"{{ "items": [{{ "id": "clip-{page}", "title": "Clip {page}", "url": "https://example.com/{page}" }}] }}"#
+                )),
+            ));
+        }
+        let server = MockServer::start(routes);
+        let client = ApiClient::new(&test_config(server.base_url(), "/tmp/unused.sqlite"));
+
+        for page in 1..=3 {
+            let videos = client
+                .discover_videos_with_filters("", page, 10, None, &[], None)
+                .expect("discover page");
+            assert_eq!(videos.len(), 1);
+            assert_eq!(videos[0].id, format!("clip-{page}"));
+        }
+        assert_eq!(server.requests_to("/api/status").len(), 3);
+        assert_eq!(server.requests_to("/api/videos").len(), 3);
+    }
+
+    #[test]
+    fn html_response_reports_non_json_body() {
+        let portal = MockResponse {
+            status: 200,
+            headers: vec![(
+                "Content-Type".to_string(),
+                "text/html; charset=utf-8".to_string(),
+            )],
+            body: "<html><body>Sign in to the hotel wifi</body></html>".to_string(),
+        };
+        let mislabeled = MockResponse {
+            status: 200,
+            headers: vec![("Content-Type".to_string(), "text/html".to_string())],
+            body: STATUS_WITH_TWO_CHANNELS.to_string(),
+        };
+        let server = MockServer::start(vec![("/api/status", portal), ("/api/status", mislabeled)]);
+        let client = ApiClient::new(&test_config(server.base_url(), "/tmp/unused.sqlite"));
+
+        let err = client.fetch_status().expect_err("captive portal");
+        match err {
+            EngineError::Network { detail } => {
+                assert!(detail.contains("expected JSON"), "{detail}");
+                assert!(detail.contains("text/html"), "{detail}");
+                assert!(detail.contains("hotel wifi"), "{detail}");
+            }
+            other => panic!("expected network error, got {other:?}"),
+        }
+
+        let status = client.fetch_status().expect("json labelled as html");
+        assert_eq!(status.name, "Fig Leaf");
+    }
+
+    #[test]
+    fn non_success_status_maps_to_http_error() {
+        let server = MockServer::start(vec![(
+            "/api/status",
+            MockResponse {
+                status: 418,
+                headers: Vec::new(),
+                body: "short and stout".to_string(),
+            },
+        )]);
+        let config = test_config(server.base_url(), "/tmp/unused.sqlite");
+
+        let err = ApiClient::new(&config)
+            .fetch_status()
+            .expect_err("teapot status");
+        match err {
+            EngineError::Http {
+                status,
+                url,
+                body_snippet,
+            } => {
+                assert_eq!(status, 418);
+                assert_eq!(url, format!("{}/api/status", server.base_url()));
+                assert_eq!(body_snippet, "short and stout");
+            }
+            other => panic!("expected http error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn configured_get_methods_send_no_body() {
+        let server = MockServer::start(vec![
+            ("/api/status", MockResponse::json(STATUS_WITH_TWO_CHANNELS)),
+            ("/api/videos", MockResponse::json(r#"{ "items": [] }"#)),
+        ]);
+        let mut config = test_config(server.base_url(), "/tmp/unused.sqlite");
+        config.status_method = Some("get".to_string());
+        config.videos_method = Some("GET".to_string());
+        let client = ApiClient::new(&config);
+
+        client.fetch_status().expect("status via GET");
+        client
+            .discover_videos_with_filters("cats", 2, 10, Some("dogflix"), &[], None)
+            .expect("videos via GET");
+
+        for request in server.requests() {
+            assert_eq!(request.method, "GET");
+            assert!(request.body.is_empty());
+            assert!(!request.headers.contains_key("content-type"));
+        }
+        let videos = server.requests_to("/api/videos");
+        assert_eq!(videos.len(), 1);
+        let query = &videos[0].query;
+        assert!(query.contains("channel=dogflix"));
+        assert!(query.contains("query=cats"));
+        assert!(query.contains("page=2"));
+        assert!(query.contains("perPage=10"));
+    }
+
+    #[test]
+    fn retries_rate_limited_request_after_retry_after_delay() {
+        let server = MockServer::start(vec![
+            (
+                "/api/status",
+                MockResponse {
+                    status: 429,
+                    headers: vec![("Retry-After".to_string(), "2".to_string())],
+                    body: "slow down".to_string(),
+                },
+            ),
+            ("/api/status", MockResponse::json(STATUS_WITH_TWO_CHANNELS)),
+        ]);
+        let config = test_config(server.base_url(), "/tmp/unused.sqlite");
+
+        let started = Instant::now();
+        let status = ApiClient::new(&config)
+            .fetch_status()
+            .expect("status after retry");
+
+        assert!(started.elapsed() >= Duration::from_secs(2));
+        assert_eq!(status.channels, ["catflix", "dogflix"]);
+        assert_eq!(server.requests_to("/api/status").len(), 2);
+    }
+
+    #[test]
+    fn rejects_response_bodies_over_configured_cap() {
+        let server = MockServer::start(vec![(
+            "/api/status",
+            MockResponse::json(STATUS_WITH_TWO_CHANNELS),
+        )]);
+        let mut config = test_config(server.base_url(), "/tmp/unused.sqlite");
+        config.max_response_bytes = Some(64);
+
+        let err = ApiClient::new(&config)
+            .fetch_status()
+            .expect_err("oversized body");
+
+        match err {
+            EngineError::Network { detail } => assert!(detail.contains("exceeded 64 bytes")),
+            other => panic!("expected network error, got {other:?}"),
+        }
+
+        config.max_response_bytes = Some(STATUS_WITH_TWO_CHANNELS.len() as u64);
+        let status = ApiClient::new(&config)
+            .fetch_status()
+            .expect("body at the cap");
+        assert_eq!(status.channels, ["catflix", "dogflix"]);
+    }
+
+    #[test]
+    fn unresponsive_server_measures_as_unreachable_after_timeout() {
+        // Accepted by the kernel backlog but never answered.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind listener");
+        let base_url = format!("http://{}", listener.local_addr().expect("local addr"));
+        let mut config = test_config(&base_url, "/tmp/unused.sqlite");
+        config.request_timeout_ms = Some(200);
+
+        let latency = ApiClient::new(&config).measure_latency();
+
+        assert!(!latency.reachable);
+        assert!(latency.latency_ms >= 200 && latency.latency_ms < 5_000);
+        assert_eq!(latency.api_version, None);
+        drop(listener);
+    }
+
+    #[test]
+    fn read_timeout_overrides_request_timeout_and_connect_timeout_stays_separate() {
+        let mut config = test_config("http://127.0.0.1:9", "/tmp/unused.sqlite");
+        config.request_timeout_ms = Some(5_000);
+        let timeouts = HttpTimeouts::from_config(&config);
+        assert_eq!(timeouts.connect, None);
+        assert_eq!(timeouts.total, Some(Duration::from_millis(5_000)));
+
+        // Accepted by the kernel backlog but never answered: connecting is instant, reading stalls.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind listener");
+        let base_url = format!("http://{}", listener.local_addr().expect("local addr"));
+        let mut config = test_config(&base_url, "/tmp/unused.sqlite");
+        config.request_timeout_ms = Some(5_000);
+        config.connect_timeout_ms = Some(50);
+        config.read_timeout_ms = Some(300);
+
+        let latency = ApiClient::new(&config).measure_latency();
+
+        assert!(!latency.reachable);
+        assert!(latency.latency_ms >= 300 && latency.latency_ms < 5_000);
+        drop(listener);
+
+        // Never accepted and with a full backlog, the kernel drops further SYNs: connecting
+        // stalls, so the connect timeout has to fire well before the read timeout.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind listener");
+        let addr = listener.local_addr().expect("local addr");
+        let mut backlog = Vec::new();
+        while let Ok(stream) =
+            std::net::TcpStream::connect_timeout(&addr, Duration::from_millis(100))
+        {
+            backlog.push(stream);
+            assert!(backlog.len() < 10_000, "listener backlog never filled");
+        }
+        let mut config = test_config(&format!("http://{addr}"), "/tmp/unused.sqlite");
+        config.connect_timeout_ms = Some(200);
+        config.read_timeout_ms = Some(5_000);
+
+        let latency = ApiClient::new(&config).measure_latency();
+
+        assert!(!latency.reachable);
+        assert!(
+            latency.latency_ms >= 200 && latency.latency_ms < 1_500,
+            "{}ms",
+            latency.latency_ms
+        );
+        drop(backlog);
+        drop(listener);
+    }
+
+    #[test]
+    fn parses_retry_after_seconds_and_dates() {
+        assert_eq!(parse_retry_after(" 7 "), Some(Duration::from_secs(7)));
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"),
+            Some(Duration::ZERO)
+        );
+        let ahead = (Utc::now() + chrono::Duration::seconds(120)).to_rfc2822();
+        let delay = parse_retry_after(&ahead).expect("future date");
+        assert!(delay > Duration::from_secs(100) && delay <= Duration::from_secs(120));
+        assert_eq!(parse_retry_after("soon"), None);
+    }
+
     #[test]
     #[ignore = "live network test against getfigleaf.com"]
     fn fetches_and_parses_live_getfigleaf_videos() {
@@ -764,6 +2139,26 @@ mod tests {
             python_executable: "python3".to_string(),
             curl_cffi_script_path: None,
             yt_dlp_repo_api: None,
+            backup_before_import: true,
+            accept_language: None,
+            offline: false,
+            min_discover_interval_ms: None,
+            yt_dlp_expected_sha256: None,
+            proxy_url: None,
+            status_method: None,
+            videos_method: None,
+            db_busy_timeout_ms: 5000,
+            db_encryption_key: None,
+            yt_dlp_extra_args: None,
+            strict_filter_selections: false,
+            max_response_bytes: None,
+            request_timeout_ms: None,
+            connect_timeout_ms: None,
+            read_timeout_ms: None,
+            fallback_api_base_urls: None,
+            curl_cffi_env: None,
+            curl_cffi_impersonate: None,
+            allowed_resolve_hosts: None,
         });
 
         let status = client.fetch_status().expect("fetch status");
@@ -773,7 +2168,7 @@ mod tests {
         );
 
         let videos = client
-            .discover_videos_with_filters("", 1, 10, None, &[])
+            .discover_videos_with_filters("", 1, 10, None, &[], None)
             .expect("fetch and parse videos");
         assert!(!videos.is_empty(), "videos response should not be empty");
         assert!(
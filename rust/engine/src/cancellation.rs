@@ -0,0 +1,34 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::errors::EngineError;
+
+#[derive(Debug, Clone, Default, uniffi::Object)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+#[uniffi::export]
+impl CancellationToken {
+    #[uniffi::constructor]
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+pub fn check_cancelled(token: Option<&CancellationToken>) -> Result<(), EngineError> {
+    match token {
+        Some(token) if token.is_cancelled() => Err(EngineError::Cancelled {
+            detail: "operation was cancelled".to_string(),
+        }),
+        _ => Ok(()),
+    }
+}
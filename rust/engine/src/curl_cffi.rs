@@ -1,34 +1,231 @@
-use std::process::Command;
+use std::collections::HashMap;
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::thread;
 
 use crate::errors::EngineError;
+use crate::models::EngineConfig;
 
-pub fn fetch_with_curl_cffi(
-    python_executable: &str,
-    script_path: &str,
-    method: &str,
-    url: &str,
-    json_body: Option<&str>,
-) -> Result<String, EngineError> {
-    let payload = json_body.unwrap_or("{}");
-
-    let output = Command::new(python_executable)
-        .arg(script_path)
-        .arg(method)
-        .arg(url)
-        .arg(payload)
-        .output()
+const PROXY_ENV_VAR: &str = "WHIRLPOOL_PROXY_URL";
+
+#[derive(Debug, Clone)]
+pub struct CurlCffiBridge {
+    python_executable: String,
+    script_path: String,
+    env: Vec<(String, String)>,
+    impersonate: Option<String>,
+}
+
+impl CurlCffiBridge {
+    // None when no bridge script is configured, in which case failed requests are not escalated.
+    pub fn from_config(config: &EngineConfig) -> Option<Self> {
+        let script_path = config.curl_cffi_script_path.clone()?;
+        let mut env: Vec<(String, String)> = config
+            .curl_cffi_env
+            .iter()
+            .flatten()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        env.sort();
+        Some(Self {
+            python_executable: config.python_executable.clone(),
+            script_path,
+            env,
+            impersonate: config
+                .curl_cffi_impersonate
+                .as_deref()
+                .map(str::trim)
+                .filter(|profile| !profile.is_empty())
+                .map(ToOwned::to_owned),
+        })
+    }
+
+    pub fn fetch(
+        &self,
+        method: &str,
+        url: &str,
+        json_body: Option<&str>,
+        headers: &[(String, String)],
+        proxy_url: Option<&str>,
+        max_bytes: Option<u64>,
+    ) -> Result<String, EngineError> {
+        let payload = json_body.unwrap_or("{}");
+
+        let mut command = Command::new(&self.python_executable);
+        command
+            .arg(&self.script_path)
+            .arg(method)
+            .arg(url)
+            .arg(payload);
+        // The profile is positional after the headers, so headers are always sent alongside it.
+        if !headers.is_empty() || self.impersonate.is_some() {
+            let header_map: serde_json::Map<String, serde_json::Value> = headers
+                .iter()
+                .map(|(name, value)| (name.clone(), serde_json::Value::String(value.clone())))
+                .collect();
+            command.arg(serde_json::Value::Object(header_map).to_string());
+        }
+        if let Some(profile) = &self.impersonate {
+            command.arg(profile);
+        }
+        command.envs(self.env.iter().map(|(name, value)| (name, value)));
+        if let Some(proxy_url) = proxy_url {
+            command.env(PROXY_ENV_VAR, proxy_url);
+        }
+        run_bridge(command, max_bytes)
+    }
+}
+
+// Validated up front so a bad name surfaces as a config error rather than a spawn failure.
+pub fn validate_bridge_env(env: &HashMap<String, String>) -> Result<(), EngineError> {
+    for (name, value) in env {
+        if name.is_empty() || name.contains(['=', '\0']) || value.contains('\0') {
+            return Err(EngineError::InvalidConfig {
+                detail: format!("invalid curl_cffi_env entry {name:?}"),
+            });
+        }
+    }
+    Ok(())
+}
+
+fn run_bridge(mut command: Command, max_bytes: Option<u64>) -> Result<String, EngineError> {
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
         .map_err(|err| EngineError::Process {
             detail: format!("failed to execute curl-cffi bridge: {err}"),
         })?;
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
+    // stderr drains on its own thread so a chatty bridge cannot block on a full pipe.
+    let stderr_reader = child.stderr.take().map(|mut stderr| {
+        thread::spawn(move || {
+            let mut buffer = Vec::new();
+            let _ = stderr.read_to_end(&mut buffer);
+            buffer
+        })
+    });
+
+    let mut stdout = Vec::new();
+    let read_result = match (child.stdout.take(), max_bytes) {
+        (Some(pipe), Some(max_bytes)) => pipe.take(max_bytes + 1).read_to_end(&mut stdout),
+        (Some(mut pipe), None) => pipe.read_to_end(&mut stdout),
+        (None, _) => Ok(0),
+    };
+    let exceeded = max_bytes.is_some_and(|max_bytes| stdout.len() as u64 > max_bytes);
+    if exceeded {
+        let _ = child.kill();
+    }
+    let status = child.wait().map_err(|err| EngineError::Process {
+        detail: format!("failed to wait for curl-cffi bridge: {err}"),
+    })?;
+    let stderr = stderr_reader
+        .and_then(|reader| reader.join().ok())
+        .unwrap_or_default();
+
+    if exceeded {
+        return Err(EngineError::Network {
+            detail: format!(
+                "curl-cffi response exceeded {} bytes",
+                max_bytes.unwrap_or_default()
+            ),
+        });
+    }
+    read_result.map_err(|err| EngineError::Process {
+        detail: format!("failed reading curl-cffi bridge output: {err}"),
+    })?;
+
+    if !status.success() {
+        let stderr = String::from_utf8_lossy(&stderr);
         return Err(EngineError::Process {
             detail: format!("curl-cffi bridge failed: {stderr}"),
         });
     }
 
-    String::from_utf8(output.stdout).map_err(|err| EngineError::Process {
+    String::from_utf8(stdout).map_err(|err| EngineError::Process {
         detail: format!("curl-cffi bridge output was not utf8: {err}"),
     })
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn script_bridge(name: &str, body: &str) -> (std::path::PathBuf, CurlCffiBridge) {
+        let dir =
+            std::env::temp_dir().join(format!("whirlpool-cffi-{name}-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("script dir");
+        let script = dir.join("bridge.sh");
+        std::fs::write(&script, body).expect("write script");
+        let bridge = CurlCffiBridge {
+            python_executable: "sh".to_string(),
+            script_path: script.to_string_lossy().into_owned(),
+            env: Vec::new(),
+            impersonate: None,
+        };
+        (dir, bridge)
+    }
+
+    #[test]
+    fn caps_bridge_output() {
+        let (dir, bridge) = script_bridge("cap", "printf '%0200d' 0\n");
+
+        let err = bridge
+            .fetch("GET", "https://x", None, &[], None, Some(100))
+            .expect_err("oversized output");
+        assert!(matches!(err, EngineError::Network { .. }));
+
+        let body = bridge
+            .fetch("GET", "https://x", None, &[], None, Some(200))
+            .expect("output at the cap");
+        assert_eq!(body.len(), 200);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn passes_configured_env_and_impersonation_profile() {
+        let (dir, _) = script_bridge("env", "echo \"argc=$#\"\necho \"profile=$5\"\nenv\n");
+        let mut config = crate::test_support::test_config("http://127.0.0.1:9", "unused.db");
+        config.python_executable = "sh".to_string();
+        config.curl_cffi_script_path = Some(dir.join("bridge.sh").to_string_lossy().into_owned());
+        config.curl_cffi_env = Some(HashMap::from([
+            (
+                "CURL_CA_BUNDLE".to_string(),
+                "/etc/ssl/bundle.pem".to_string(),
+            ),
+            ("WHIRLPOOL_TEST_FLAG".to_string(), "on".to_string()),
+        ]));
+        config.curl_cffi_impersonate = Some(" safari17_0 ".to_string());
+        let bridge = CurlCffiBridge::from_config(&config).expect("bridge configured");
+
+        let output = bridge
+            .fetch(
+                "GET",
+                "https://x",
+                None,
+                &[],
+                Some("http://proxy:8080"),
+                None,
+            )
+            .expect("bridge output");
+        let lines: Vec<&str> = output.lines().collect();
+        assert!(lines.contains(&"argc=5"), "{output}");
+        assert!(lines.contains(&"profile=safari17_0"), "{output}");
+        assert!(
+            lines.contains(&"CURL_CA_BUNDLE=/etc/ssl/bundle.pem"),
+            "{output}"
+        );
+        assert!(lines.contains(&"WHIRLPOOL_TEST_FLAG=on"), "{output}");
+        assert!(
+            lines.contains(&"WHIRLPOOL_PROXY_URL=http://proxy:8080"),
+            "{output}"
+        );
+
+        assert!(
+            validate_bridge_env(&HashMap::from([("A=B".to_string(), "x".to_string())])).is_err()
+        );
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
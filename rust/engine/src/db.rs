@@ -1,39 +1,150 @@
+use std::collections::HashMap;
 use std::fs;
+use std::ops::{Deref, DerefMut};
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, TryLockError};
+use std::thread;
+use std::time::{Duration, Instant};
 
 use chrono::{DateTime, NaiveDateTime, SecondsFormat, TimeZone, Utc};
-use rusqlite::{params, Connection, OptionalExtension};
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension};
 
+use crate::clock::{system_clock, Clock};
 use crate::errors::EngineError;
-use crate::models::{FavoriteItem, ResolvedVideo, SourceServer, VideoItem};
+use crate::models::{
+    ActivityEntry, Dashboard, DatabaseBackup, FavoriteItem, ImportReport, MergeReport,
+    MigrationStats, NetworkCount, ResetOptions, ResetReport, ResolvedVideo, SourceServer,
+    VideoItem,
+};
+
+const TEMPLATE_TABLES: [&str; 5] = [
+    "user_preferences",
+    "server_preferences",
+    "video_details",
+    "categories",
+    "searches",
+];
+
+const VIDEO_ITEM_COLUMNS: &str = r#""id", COALESCE("title", ''), COALESCE("url", ''), "duration", "thumb", "network", "uploader", "views", "rawData", "aspectRatio""#;
+
+const SCHEMA_VERSION: u32 = 1;
+
+const DEFAULT_BUSY_TIMEOUT: Duration = Duration::from_millis(5000);
+
+// How often `conn_within` retries a connection held by another engine operation.
+const CONN_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+const MIGRATION_STATS_KEY: &str = "last_migration_stats";
+
+// Bump when `VideoItem` changes shape so `migrate_cached_payloads` rewrites stored rawData.
+const PAYLOAD_VERSION: u32 = 2;
+
+const PAYLOAD_VERSION_KEY: &str = "cached_payload_version";
+
+// Oldest activity rows beyond this are trimmed on every insert.
+const ACTIVITY_LOG_LIMIT: u32 = 500;
+
+// SQLite's name for a private in-memory database; the pooled connection is its only copy.
+const IN_MEMORY_PATH: &str = ":memory:";
+
+// `staging_path` labels; see `remove_stale_staging_files`.
+const STAGING_LABELS: [&str; 2] = ["import", "encrypt"];
+
+const LEGACY_TABLES: [&str; 4] = ["engine_meta", "video_cache", "favorites", "resolved_cache"];
+
+// (index, column) pairs on video_details backing the favorites, history, network and url lookups.
+const VIDEO_DETAILS_INDEXES: [(&str, &str); 4] = [
+    ("idx_video_details_favorite_date", "favoriteDate"),
+    ("idx_video_details_last_watch_date", "lastWatchDate"),
+    ("idx_video_details_network", "network"),
+    ("idx_video_details_url", "url"),
+];
 
 #[derive(Debug, Clone)]
 pub struct Database {
     path: PathBuf,
+    conn: Arc<Mutex<Option<Connection>>>,
+    clock: Arc<dyn Clock>,
+    busy_timeout: Duration,
+    encryption_key: Option<String>,
+}
+
+struct PooledConnection<'a> {
+    guard: MutexGuard<'a, Option<Connection>>,
+}
+
+impl Deref for PooledConnection<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.guard.as_ref().expect("pooled connection is open")
+    }
+}
+
+impl DerefMut for PooledConnection<'_> {
+    fn deref_mut(&mut self) -> &mut Connection {
+        self.guard.as_mut().expect("pooled connection is open")
+    }
 }
 
 impl Database {
     pub fn new(path: impl Into<PathBuf>) -> Self {
-        Self { path: path.into() }
+        Self {
+            path: path.into(),
+            conn: Arc::new(Mutex::new(None)),
+            clock: system_clock(),
+            busy_timeout: DEFAULT_BUSY_TIMEOUT,
+            encryption_key: None,
+        }
+    }
+
+    pub fn with_encryption_key(mut self, encryption_key: Option<String>) -> Self {
+        self.encryption_key = encryption_key;
+        self
+    }
+
+    pub fn with_busy_timeout(mut self, busy_timeout: Duration) -> Self {
+        self.busy_timeout = busy_timeout;
+        self
+    }
+
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
     }
 
     pub fn path(&self) -> &Path {
         &self.path
     }
 
-    pub fn init(&self) -> Result<(), EngineError> {
-        if let Some(parent) = self.path.parent() {
+    pub fn is_in_memory(&self) -> bool {
+        self.path.as_os_str() == IN_MEMORY_PATH
+    }
+
+    fn require_file_backed(&self, action: &str) -> Result<(), EngineError> {
+        if self.is_in_memory() {
+            return Err(EngineError::InvalidConfig {
+                detail: format!("cannot {action} an in-memory database"),
+            });
+        }
+        Ok(())
+    }
+
+    fn now_iso(&self) -> String {
+        to_iso(self.clock.now())
+    }
+
+    pub fn init(&self) -> Result<MigrationStats, EngineError> {
+        if let Some(parent) = self.path.parent().filter(|_| !self.is_in_memory()) {
             fs::create_dir_all(parent).map_err(|err| EngineError::Database {
                 detail: format!("failed creating db parent directory: {err}"),
             })?;
+            self.remove_stale_staging_files();
         }
 
         let mut conn = self.conn()?;
         conn.execute_batch(
             r#"
-            PRAGMA journal_mode = WAL;
-            PRAGMA foreign_keys = ON;
-
             CREATE TABLE IF NOT EXISTS "user_preferences" (
                 "id" TEXT PRIMARY KEY NOT NULL,
                 "preferenceValue" TEXT
@@ -83,15 +194,79 @@ impl Database {
                 "timestamp" TEXT NOT NULL,
                 "frequency" INTEGER NOT NULL DEFAULT (1)
             );
+
+            CREATE TABLE IF NOT EXISTS "activity_log" (
+                "id" INTEGER PRIMARY KEY AUTOINCREMENT,
+                "ts" TEXT NOT NULL,
+                "kind" TEXT NOT NULL,
+                "detail" TEXT NOT NULL
+            );
             "#,
         )?;
 
-        Self::migrate_legacy_schema(&mut conn)?;
-        Ok(())
+        if !Self::column_exists(&conn, "video_details", "sortPosition")? {
+            conn.execute_batch(
+                r#"ALTER TABLE "video_details" ADD COLUMN "sortPosition" INTEGER;"#,
+            )?;
+        }
+
+        // ANALYZE only when an index is new, so later opens don't rescan the whole table.
+        let mut created_index = false;
+        for (index, column) in VIDEO_DETAILS_INDEXES {
+            if !Self::index_exists(&conn, index)? {
+                conn.execute_batch(&format!(
+                    r#"CREATE INDEX IF NOT EXISTS "{index}" ON "video_details" ("{column}");"#
+                ))?;
+                created_index = true;
+            }
+        }
+        if created_index {
+            conn.execute_batch("ANALYZE;")?;
+        }
+
+        let version = Self::read_schema_version(&conn)?;
+        if version >= SCHEMA_VERSION {
+            return Ok(MigrationStats {
+                meta: 0,
+                videos: 0,
+                favorites: 0,
+                resolved: 0,
+            });
+        }
+        // Version 1 is the video_details template layout; older files may still hold legacy tables.
+        let stats = Self::migrate_legacy_schema(&mut conn)?;
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        Ok(stats)
+    }
+
+    // `init` trusts user_version, so a stamped file that still carries legacy tables (an old backup
+    // merged in by hand, say) is skipped there. This sweeps them regardless and is safe to repeat.
+    pub fn ensure_migrated(&self) -> Result<MigrationStats, EngineError> {
+        let stats = self.init()?;
+        let mut conn = self.conn()?;
+        let mut has_legacy_tables = false;
+        for table in LEGACY_TABLES {
+            has_legacy_tables |= Self::table_exists(&conn, table)?;
+        }
+        if !has_legacy_tables {
+            return Ok(stats);
+        }
+        let swept = Self::migrate_legacy_schema(&mut conn)?;
+        Ok(stats.merged(swept))
+    }
+
+    pub fn schema_version(&self) -> Result<u32, EngineError> {
+        let conn = self.conn()?;
+        Self::read_schema_version(&conn)
+    }
+
+    fn read_schema_version(conn: &Connection) -> Result<u32, EngineError> {
+        let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        Ok(u32::try_from(version).unwrap_or(0))
     }
 
     pub fn cache_videos(&self, videos: &[VideoItem]) -> Result<(), EngineError> {
-        let now_iso = now_iso();
+        let now_iso = self.now_iso();
         let mut conn = self.conn()?;
         let tx = conn.transaction()?;
         {
@@ -99,11 +274,11 @@ impl Database {
                 r#"
                 INSERT INTO "video_details" (
                     "id", "url", "title", "thumb", "dateAdded", "views", "duration",
-                    "uploader", "network", "lastUpdated", "rawData", "cacheDate"
+                    "uploader", "network", "lastUpdated", "rawData", "cacheDate", "aspectRatio"
                 )
-                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
                 ON CONFLICT("id") DO UPDATE SET
-                    "url" = excluded."url",
+                    "url" = COALESCE(NULLIF(excluded."url", ''), "url"),
                     "title" = excluded."title",
                     "thumb" = excluded."thumb",
                     "views" = excluded."views",
@@ -112,7 +287,8 @@ impl Database {
                     "network" = excluded."network",
                     "lastUpdated" = excluded."lastUpdated",
                     "rawData" = excluded."rawData",
-                    "cacheDate" = excluded."cacheDate"
+                    "cacheDate" = excluded."cacheDate",
+                    "aspectRatio" = COALESCE(excluded."aspectRatio", "aspectRatio")
                 "#,
             )?;
 
@@ -132,7 +308,8 @@ impl Database {
                     video.network,
                     now_iso,
                     payload,
-                    now_iso
+                    now_iso,
+                    video.aspect_ratio
                 ])?;
             }
         }
@@ -140,14 +317,67 @@ impl Database {
         Ok(())
     }
 
+    #[cfg(test)]
     pub fn cache_resolved_video(
         &self,
         page_url: &str,
         video: &ResolvedVideo,
     ) -> Result<(), EngineError> {
-        let payload = serde_json::to_string(video)?;
-        let now_iso = now_iso();
         let conn = self.conn()?;
+        self.write_resolved_video(&conn, page_url, video)
+    }
+
+    // Caches a fresh resolve and logs it in one transaction, waiting at most `wait` in total for
+    // the engine connection and for another connection's write lock. On failure a
+    // "resolve_cache_failed" entry is written with whatever is left of `wait`; if the database
+    // is still locked by then, the returned error is the only trace.
+    pub fn cache_resolved_video_within(
+        &self,
+        page_url: &str,
+        video: &ResolvedVideo,
+        wait: Duration,
+    ) -> Result<(), EngineError> {
+        let started = Instant::now();
+        let Some(mut conn) = self.conn_within(wait)? else {
+            return Err(EngineError::Database {
+                detail: format!("connection busy; {page_url} was not cached"),
+            });
+        };
+        conn.busy_timeout(wait.saturating_sub(started.elapsed()))?;
+        let result = conn
+            .transaction()
+            .map_err(EngineError::from)
+            .and_then(|tx| {
+                self.write_resolved_video(&tx, page_url, video)?;
+                self.insert_activity(&tx, "resolve", page_url, ACTIVITY_LOG_LIMIT)?;
+                tx.commit()?;
+                Ok(())
+            });
+        if result.is_err() {
+            let _ = conn
+                .busy_timeout(wait.saturating_sub(started.elapsed()))
+                .map_err(EngineError::from)
+                .and_then(|()| {
+                    self.insert_activity(
+                        &conn,
+                        "resolve_cache_failed",
+                        page_url,
+                        ACTIVITY_LOG_LIMIT,
+                    )
+                });
+        }
+        conn.busy_timeout(self.busy_timeout)?;
+        result
+    }
+
+    fn write_resolved_video(
+        &self,
+        conn: &Connection,
+        page_url: &str,
+        video: &ResolvedVideo,
+    ) -> Result<(), EngineError> {
+        let payload = serde_json::to_string(video)?;
+        let now_iso = self.now_iso();
 
         let updated = conn.execute(
             r#"
@@ -195,6 +425,8 @@ impl Database {
                 ],
             )?;
         }
+
+        Self::prune_stale_resolved_rows(conn, page_url)?;
         Ok(())
     }
 
@@ -204,6 +436,28 @@ impl Database {
         max_age_seconds: i64,
     ) -> Result<Option<ResolvedVideo>, EngineError> {
         let conn = self.conn()?;
+        self.read_cached_resolved_video(&conn, page_url, max_age_seconds)
+    }
+
+    // A connection still held by another operation after `wait` counts as a cache miss.
+    pub fn get_cached_resolved_video_within(
+        &self,
+        page_url: &str,
+        max_age_seconds: i64,
+        wait: Duration,
+    ) -> Result<Option<ResolvedVideo>, EngineError> {
+        match self.conn_within(wait)? {
+            Some(conn) => self.read_cached_resolved_video(&conn, page_url, max_age_seconds),
+            None => Ok(None),
+        }
+    }
+
+    fn read_cached_resolved_video(
+        &self,
+        conn: &Connection,
+        page_url: &str,
+        max_age_seconds: i64,
+    ) -> Result<Option<ResolvedVideo>, EngineError> {
         let row: Option<(String, String)> = conn
             .query_row(
                 r#"
@@ -220,25 +474,104 @@ impl Database {
             )
             .optional()?;
 
-        let Some((payload, cache_date)) = row else {
-            return Ok(None);
-        };
+        let now = self.clock.now().timestamp();
+        Ok(row.and_then(|(payload, cache_date)| {
+            fresh_resolved_payload(&payload, &cache_date, max_age_seconds, now)
+        }))
+    }
 
-        let Some(updated_at_epoch) = parse_timestamp_to_epoch_seconds(&cache_date) else {
-            return Ok(None);
-        };
+    // Same freshness rule as `get_cached_resolved_video`, without loading the payload.
+    pub fn is_resolved_cached(
+        &self,
+        page_url: &str,
+        max_age_seconds: i64,
+    ) -> Result<bool, EngineError> {
+        let conn = self.conn()?;
+        let cache_date: Option<String> = conn
+            .query_row(
+                r#"
+                SELECT "cacheDate"
+                FROM "video_details"
+                WHERE "url" = ?1
+                  AND "allFormats" IS NOT NULL
+                  AND TRIM("allFormats") <> ''
+                ORDER BY "cacheDate" DESC
+                LIMIT 1
+                "#,
+                params![page_url],
+                |row| row.get(0),
+            )
+            .optional()?
+            .flatten();
+        let now = self.clock.now().timestamp();
+        Ok(cache_date.is_some_and(|cache_date| is_fresh(&cache_date, max_age_seconds, now)))
+    }
 
-        let age = Utc::now().timestamp() - updated_at_epoch;
-        if age > max_age_seconds {
-            return Ok(None);
-        }
+    pub fn get_cached_resolved_by_id(
+        &self,
+        video_id: &str,
+        max_age_seconds: i64,
+    ) -> Result<Option<ResolvedVideo>, EngineError> {
+        let conn = self.conn()?;
+        let row: Option<(String, String)> = conn
+            .query_row(
+                r#"
+                SELECT "allFormats", COALESCE("cacheDate", '')
+                FROM "video_details"
+                WHERE "id" = ?1
+                  AND "allFormats" IS NOT NULL
+                  AND TRIM("allFormats") <> ''
+                "#,
+                params![video_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
 
-        Ok(serde_json::from_str::<ResolvedVideo>(&payload).ok())
+        let now = self.clock.now().timestamp();
+        Ok(row.and_then(|(payload, cache_date)| {
+            fresh_resolved_payload(&payload, &cache_date, max_age_seconds, now)
+        }))
+    }
+
+    pub fn evict_resolved(&self, page_url: &str) -> Result<bool, EngineError> {
+        let conn = self.conn()?;
+        let rows = conn.execute(
+            r#"
+            UPDATE "video_details"
+            SET "allFormats" = NULL, "cacheDate" = NULL
+            WHERE "url" = ?1
+              AND "allFormats" IS NOT NULL
+            "#,
+            params![page_url],
+        )?;
+        Ok(rows > 0)
     }
 
     pub fn add_favorite(&self, video: &VideoItem) -> Result<FavoriteItem, EngineError> {
-        let now = Utc::now().timestamp();
-        let now_iso = now_iso();
+        let conn = self.conn()?;
+        self.upsert_favorite(&conn, video)
+    }
+
+    // All or nothing: a failing item rolls back the ones before it.
+    pub fn add_favorites(&self, videos: &[VideoItem]) -> Result<u64, EngineError> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        let mut added = 0;
+        for video in videos {
+            self.upsert_favorite(&tx, video)?;
+            added += 1;
+        }
+        tx.commit()?;
+        Ok(added)
+    }
+
+    fn upsert_favorite(
+        &self,
+        conn: &Connection,
+        video: &VideoItem,
+    ) -> Result<FavoriteItem, EngineError> {
+        let now = self.clock.now().timestamp();
+        let now_iso = self.now_iso();
         let payload = serde_json::to_string(video)?;
         let favorite = FavoriteItem {
             video_id: video.id.clone(),
@@ -248,16 +581,15 @@ impl Database {
             added_at_epoch: now,
         };
 
-        let conn = self.conn()?;
         conn.execute(
             r#"
             INSERT INTO "video_details" (
                 "id", "url", "title", "thumb", "dateAdded", "views", "duration",
-                "uploader", "network", "lastUpdated", "favoriteDate", "rawData"
+                "uploader", "network", "lastUpdated", "favoriteDate", "rawData", "aspectRatio"
             )
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
             ON CONFLICT("id") DO UPDATE SET
-                "url" = excluded."url",
+                "url" = COALESCE(NULLIF(excluded."url", ''), "url"),
                 "title" = excluded."title",
                 "thumb" = excluded."thumb",
                 "views" = excluded."views",
@@ -266,7 +598,8 @@ impl Database {
                 "network" = excluded."network",
                 "lastUpdated" = excluded."lastUpdated",
                 "favoriteDate" = excluded."favoriteDate",
-                "rawData" = excluded."rawData"
+                "rawData" = excluded."rawData",
+                "aspectRatio" = COALESCE(excluded."aspectRatio", "aspectRatio")
             "#,
             params![
                 favorite.video_id,
@@ -281,153 +614,292 @@ impl Database {
                 now_iso,
                 now_iso,
                 payload,
+                video.aspect_ratio,
             ],
         )?;
 
         Ok(favorite)
     }
 
-    pub fn remove_favorite(&self, video_id: &str) -> Result<bool, EngineError> {
+    // Merges the newest resolved payload for the favorite's URL into its stored VideoItem, filling
+    // only what the list item lacked. Stream freshness doesn't matter here, so expired entries count.
+    pub fn enrich_favorite(&self, video_id: &str) -> Result<bool, EngineError> {
+        let Some(mut video) = self.get_video(video_id)? else {
+            return Ok(false);
+        };
         let conn = self.conn()?;
-        let rows = conn.execute(
+        let resolved: Option<String> = conn
+            .query_row(
+                r#"
+                SELECT "allFormats"
+                FROM "video_details"
+                WHERE "url" = ?1
+                  AND "allFormats" IS NOT NULL
+                  AND TRIM("allFormats") <> ''
+                  AND EXISTS (
+                    SELECT 1 FROM "video_details"
+                    WHERE "id" = ?2 AND "favoriteDate" IS NOT NULL AND TRIM("favoriteDate") <> ''
+                  )
+                ORDER BY "cacheDate" DESC
+                LIMIT 1
+                "#,
+                params![video.page_url, video_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let Some(resolved) = resolved
+            .as_deref()
+            .and_then(|payload| serde_json::from_str::<ResolvedVideo>(payload).ok())
+        else {
+            return Ok(false);
+        };
+        if !merge_resolved_into_item(&mut video, &resolved) {
+            return Ok(false);
+        }
+
+        conn.execute(
             r#"
             UPDATE "video_details"
-            SET "favoriteDate" = NULL
+            SET "title" = ?2, "thumb" = ?3, "views" = ?4, "duration" = ?5, "uploader" = ?6,
+                "rawData" = ?7, "lastUpdated" = ?8
             WHERE "id" = ?1
             "#,
-            params![video_id],
+            params![
+                video.id,
+                video.title,
+                video.image_url,
+                video.view_count.and_then(|count| i64::try_from(count).ok()),
+                video.duration_seconds.map(i64::from),
+                video.author_name,
+                serde_json::to_string(&video)?,
+                self.now_iso(),
+            ],
         )?;
-        Ok(rows > 0)
+        Ok(true)
     }
 
-    pub fn list_favorites(&self) -> Result<Vec<FavoriteItem>, EngineError> {
+    pub fn set_favorite_position(
+        &self,
+        video_id: &str,
+        position: i64,
+    ) -> Result<bool, EngineError> {
         let conn = self.conn()?;
-        let mut stmt = conn.prepare(
+        let rows = conn.execute(
             r#"
-            SELECT "id", COALESCE("title", ''), "thumb", "network", "favoriteDate"
-            FROM "video_details"
-            WHERE "favoriteDate" IS NOT NULL
-              AND TRIM("favoriteDate") <> ''
-            ORDER BY "favoriteDate" DESC
+            UPDATE "video_details"
+            SET "sortPosition" = ?2
+            WHERE "id" = ?1
+              AND "favoriteDate" IS NOT NULL
             "#,
+            params![video_id, position],
         )?;
-
-        let rows = stmt.query_map([], |row| {
-            let video_id: String = row.get(0)?;
-            let title: String = row.get(1)?;
-            let favorite_date: String = row.get(4)?;
-            Ok(FavoriteItem {
-                video_id: video_id.clone(),
-                title: if title.trim().is_empty() {
-                    video_id
-                } else {
-                    title
-                },
-                image_url: row.get(2)?,
-                network: row.get(3)?,
-                added_at_epoch: parse_timestamp_to_epoch_seconds(&favorite_date)
-                    .unwrap_or_else(|| Utc::now().timestamp()),
-            })
-        })?;
-
-        let mut out = Vec::new();
-        for row in rows {
-            out.push(row?);
-        }
-        Ok(out)
+        Ok(rows > 0)
     }
 
-    pub fn list_favorite_videos(&self) -> Result<Vec<VideoItem>, EngineError> {
+    pub fn remove_favorite(&self, video_id: &str) -> Result<bool, EngineError> {
         let conn = self.conn()?;
-        let mut stmt = conn.prepare(
+        let rows = conn.execute(
             r#"
-            SELECT
-                "id",
-                COALESCE("title", ''),
-                COALESCE("url", ''),
-                "duration",
-                "thumb",
-                "network",
-                "uploader",
-                "views",
-                "rawData"
-            FROM "video_details"
-            WHERE "favoriteDate" IS NOT NULL
-              AND TRIM("favoriteDate") <> ''
-            ORDER BY "favoriteDate" DESC
+            UPDATE "video_details"
+            SET "favoriteDate" = NULL, "sortPosition" = NULL
+            WHERE "id" = ?1
             "#,
+            params![video_id],
         )?;
+        Ok(rows > 0)
+    }
 
-        let rows = stmt.query_map([], |row| {
-            let video_id: String = row.get(0)?;
-            let title: String = row.get(1)?;
-            let page_url: String = row.get(2)?;
-            let raw_data: Option<String> = row.get(8)?;
-            let parsed_video = raw_data
-                .as_deref()
-                .and_then(|payload| serde_json::from_str::<VideoItem>(payload).ok());
-            let extractor = parsed_video.as_ref().and_then(|video| video.extractor.clone());
-            let raw_json = parsed_video.as_ref().and_then(|video| video.raw_json.clone());
-
-            Ok(VideoItem {
-                id: video_id.clone(),
-                title: if title.trim().is_empty() {
-                    video_id.clone()
-                } else {
-                    title
-                },
-                page_url: non_empty_str(&page_url)
-                    .map(ToOwned::to_owned)
-                    .unwrap_or_else(|| fallback_url(&video_id)),
-                duration_seconds: row
-                    .get::<_, Option<i64>>(3)?
-                    .and_then(|seconds| u32::try_from(seconds).ok()),
-                image_url: row.get(4)?,
-                network: row.get(5)?,
-                author_name: row.get(6)?,
-                extractor,
-                view_count: row
-                    .get::<_, Option<i64>>(7)?
-                    .and_then(|views| u64::try_from(views).ok()),
-                raw_json,
-            })
-        })?;
-
-        let mut out = Vec::new();
-        for row in rows {
-            out.push(row?);
+    pub fn remove_favorites(&self, video_ids: &[String]) -> Result<u64, EngineError> {
+        if video_ids.is_empty() {
+            return Ok(0);
         }
-        Ok(out)
-    }
 
-    pub fn set_meta(&self, key: &str, value: &str) -> Result<(), EngineError> {
-        let conn = self.conn()?;
-        conn.execute(
-            r#"
-            INSERT INTO "user_preferences" ("id", "preferenceValue")
-            VALUES (?1, ?2)
-            ON CONFLICT("id") DO UPDATE SET "preferenceValue" = excluded."preferenceValue"
-            "#,
-            params![key, value],
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        let mut removed = 0u64;
+        {
+            let mut stmt = tx.prepare(
+                r#"
+                UPDATE "video_details"
+                SET "favoriteDate" = NULL, "sortPosition" = NULL
+                WHERE "id" = ?1
+                  AND "favoriteDate" IS NOT NULL
+                  AND TRIM("favoriteDate") <> ''
+                "#,
+            )?;
+            for video_id in video_ids {
+                removed += stmt.execute(params![video_id])? as u64;
+            }
+        }
+        tx.commit()?;
+        Ok(removed)
+    }
+
+    pub fn list_favorites(&self) -> Result<Vec<FavoriteItem>, EngineError> {
+        let now = self.clock.now().timestamp();
+        Ok(self
+            .favorite_rows()?
+            .into_iter()
+            .map(|(mut favorite, favorited_at)| {
+                favorite.added_at_epoch = favorited_at.unwrap_or(now);
+                favorite
+            })
+            .collect())
+    }
+
+    pub fn list_favorites_by_tag(&self, tag: &str) -> Result<Vec<FavoriteItem>, EngineError> {
+        let Some(tag) = normalize_tags(&[tag.to_string()]).pop() else {
+            return Ok(Vec::new());
+        };
+        let tagged = {
+            let conn = self.conn()?;
+            let mut stmt = conn.prepare(
+                r#"
+                SELECT "id"
+                FROM "video_details"
+                WHERE "favoriteDate" IS NOT NULL
+                  AND json_valid("tags")
+                  AND EXISTS (SELECT 1 FROM json_each("tags") WHERE value = ?1)
+                "#,
+            )?;
+            let ids = stmt
+                .query_map(params![tag], |row| row.get::<_, String>(0))?
+                .collect::<Result<Vec<_>, _>>()?;
+            ids
+        };
+        Ok(self
+            .list_favorites()?
+            .into_iter()
+            .filter(|favorite| tagged.contains(&favorite.video_id))
+            .collect())
+    }
+
+    pub fn set_rating(&self, video_id: &str, rating: f64) -> Result<bool, EngineError> {
+        if !(0.0..=5.0).contains(&rating) {
+            return Err(EngineError::InvalidConfig {
+                detail: format!("rating must be between 0 and 5, got {rating}"),
+            });
+        }
+        let conn = self.conn()?;
+        let rows = conn.execute(
+            r#"UPDATE "video_details" SET "rating" = ?2 WHERE "id" = ?1"#,
+            params![video_id, rating],
         )?;
-        Ok(())
+        Ok(rows > 0)
     }
 
-    pub fn list_meta_with_prefix(&self, prefix: &str) -> Result<Vec<(String, String)>, EngineError> {
+    // Unrated favorites go last; ties keep the regular favorites order.
+    pub fn list_favorites_by_rating(&self) -> Result<Vec<FavoriteItem>, EngineError> {
+        let ratings: HashMap<String, f64> = {
+            let conn = self.conn()?;
+            let mut stmt = conn.prepare(
+                r#"
+                SELECT "id", "rating"
+                FROM "video_details"
+                WHERE "favoriteDate" IS NOT NULL
+                  AND "rating" IS NOT NULL
+                "#,
+            )?;
+            let rows = stmt
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<Result<_, _>>()?;
+            rows
+        };
+        let mut favorites = self.list_favorites()?;
+        favorites.sort_by(|left, right| {
+            let left = ratings
+                .get(&left.video_id)
+                .copied()
+                .unwrap_or(f64::NEG_INFINITY);
+            let right = ratings
+                .get(&right.video_id)
+                .copied()
+                .unwrap_or(f64::NEG_INFINITY);
+            right.total_cmp(&left)
+        });
+        Ok(favorites)
+    }
+
+    pub fn set_video_tags(&self, video_id: &str, tags: &[String]) -> Result<bool, EngineError> {
+        let tags = normalize_tags(tags);
+        let payload = if tags.is_empty() {
+            None
+        } else {
+            Some(serde_json::to_string(&tags)?)
+        };
+        let conn = self.conn()?;
+        let rows = conn.execute(
+            r#"UPDATE "video_details" SET "tags" = ?2 WHERE "id" = ?1"#,
+            params![video_id, payload],
+        )?;
+        Ok(rows > 0)
+    }
+
+    pub fn get_video_tags(&self, video_id: &str) -> Result<Vec<String>, EngineError> {
+        let conn = self.conn()?;
+        let tags: Option<String> = conn
+            .query_row(
+                r#"SELECT "tags" FROM "video_details" WHERE "id" = ?1"#,
+                params![video_id],
+                |row| row.get(0),
+            )
+            .optional()?
+            .flatten();
+        // Template databases may carry non-JSON tags from other clients; those read as untagged.
+        Ok(tags
+            .and_then(|tags| serde_json::from_str::<Vec<String>>(&tags).ok())
+            .unwrap_or_default())
+    }
+
+    pub fn favorites_changed_since(
+        &self,
+        epoch_seconds: i64,
+    ) -> Result<Vec<FavoriteItem>, EngineError> {
+        // Stored dates mix ISO strings and raw epochs, so the cursor is applied after parsing.
+        let mut out: Vec<FavoriteItem> = self
+            .favorite_rows()?
+            .into_iter()
+            .filter_map(|(mut favorite, favorited_at)| {
+                favorite.added_at_epoch = favorited_at.filter(|epoch| *epoch > epoch_seconds)?;
+                Some(favorite)
+            })
+            .collect();
+        out.sort_by(|left, right| {
+            left.added_at_epoch
+                .cmp(&right.added_at_epoch)
+                .then_with(|| left.video_id.cmp(&right.video_id))
+        });
+        Ok(out)
+    }
+
+    fn favorite_rows(&self) -> Result<Vec<(FavoriteItem, Option<i64>)>, EngineError> {
         let conn = self.conn()?;
         let mut stmt = conn.prepare(
             r#"
-            SELECT "id", COALESCE("preferenceValue", '')
-            FROM "user_preferences"
-            WHERE "id" LIKE ?1
-            ORDER BY "id" ASC
+            SELECT "id", COALESCE("title", ''), "thumb", "network", "favoriteDate"
+            FROM "video_details"
+            WHERE "favoriteDate" IS NOT NULL
+              AND TRIM("favoriteDate") <> ''
+            ORDER BY "sortPosition" ASC NULLS LAST, "favoriteDate" DESC
             "#,
         )?;
-        let pattern = format!("{prefix}%");
-        let rows = stmt.query_map(params![pattern], |row| {
-            let key: String = row.get(0)?;
-            let value: String = row.get(1)?;
-            Ok((key, value))
+
+        let rows = stmt.query_map([], |row| {
+            let video_id: String = row.get(0)?;
+            let title: String = row.get(1)?;
+            let favorite_date: String = row.get(4)?;
+            let favorite = FavoriteItem {
+                video_id: video_id.clone(),
+                title: if title.trim().is_empty() {
+                    video_id
+                } else {
+                    title
+                },
+                image_url: row.get(2)?,
+                network: row.get(3)?,
+                added_at_epoch: 0,
+            };
+            Ok((favorite, parse_timestamp_to_epoch_seconds(&favorite_date)))
         })?;
 
         let mut out = Vec::new();
@@ -437,781 +909,3949 @@ impl Database {
         Ok(out)
     }
 
-    pub fn get_meta(&self, key: &str) -> Result<Option<String>, EngineError> {
+    pub fn list_favorite_videos(&self) -> Result<Vec<VideoItem>, EngineError> {
+        self.query_favorite_videos(video_item_from_row)
+    }
+
+    // Rebuilt from rawData where it parses, so fields only the payload carries survive an export.
+    pub fn list_stored_favorite_videos(&self) -> Result<Vec<VideoItem>, EngineError> {
+        self.query_favorite_videos(stored_video_from_row)
+    }
+
+    fn query_favorite_videos(
+        &self,
+        map_row: fn(&rusqlite::Row<'_>) -> rusqlite::Result<VideoItem>,
+    ) -> Result<Vec<VideoItem>, EngineError> {
         let conn = self.conn()?;
-        let val = conn
+        let mut stmt = conn.prepare(&format!(
+            r#"
+            SELECT {VIDEO_ITEM_COLUMNS}
+            FROM "video_details"
+            WHERE "favoriteDate" IS NOT NULL
+              AND TRIM("favoriteDate") <> ''
+            ORDER BY "sortPosition" ASC NULLS LAST, "favoriteDate" DESC
+            "#
+        ))?;
+
+        let rows = stmt.query_map([], map_row)?;
+
+        let mut out = Vec::new();
+        for row in rows {
+            out.push(row?);
+        }
+        Ok(out)
+    }
+
+    pub fn get_video(&self, video_id: &str) -> Result<Option<VideoItem>, EngineError> {
+        let conn = self.conn()?;
+        let video = conn
             .query_row(
-                r#"SELECT "preferenceValue" FROM "user_preferences" WHERE "id" = ?1"#,
-                params![key],
-                |row| row.get::<_, Option<String>>(0),
+                &format!(r#"SELECT {VIDEO_ITEM_COLUMNS} FROM "video_details" WHERE "id" = ?1"#),
+                params![video_id],
+                stored_video_from_row,
             )
             .optional()?;
-        Ok(val.flatten())
+        Ok(video)
     }
 
-    pub fn upsert_server(&self, server: &SourceServer) -> Result<(), EngineError> {
-        let base_url = server.base_url.trim();
-        if base_url.is_empty() {
-            return Ok(());
-        }
-
-        let payload = serde_json::to_string(server)?;
+    // One UPDATE so concurrent watches of the same video never lose an increment.
+    pub fn record_watch(&self, video_id: &str) -> Result<bool, EngineError> {
+        let now_iso = self.now_iso();
         let conn = self.conn()?;
-        conn.execute(
+        let rows = conn.execute(
             r#"
-            INSERT INTO "server_preferences" ("id", "preferenceValue")
-            VALUES (?1, ?2)
-            ON CONFLICT("id") DO UPDATE SET "preferenceValue" = excluded."preferenceValue"
+            UPDATE "video_details"
+            SET "lastWatchDate" = ?2, "userViews" = COALESCE("userViews", 0) + 1
+            WHERE "id" = ?1
             "#,
-            params![base_url, payload],
+            params![video_id, now_iso],
         )?;
-        Ok(())
+        Ok(rows > 0)
     }
 
-    pub fn remove_server(&self, base_url: &str) -> Result<bool, EngineError> {
+    pub fn user_views(&self, video_id: &str) -> Result<u64, EngineError> {
         let conn = self.conn()?;
-        let removed = conn.execute(
-            r#"DELETE FROM "server_preferences" WHERE "id" = ?1"#,
-            params![base_url.trim()],
-        )?;
-        Ok(removed > 0)
+        let views: Option<i64> = conn
+            .query_row(
+                r#"SELECT "userViews" FROM "video_details" WHERE "id" = ?1"#,
+                params![video_id],
+                |row| row.get(0),
+            )
+            .optional()?
+            .flatten();
+        Ok(views
+            .and_then(|views| u64::try_from(views).ok())
+            .unwrap_or(0))
     }
 
-    pub fn list_servers(&self) -> Result<Vec<SourceServer>, EngineError> {
+    pub fn most_rewatched(&self, limit: u32) -> Result<Vec<VideoItem>, EngineError> {
         let conn = self.conn()?;
-        let mut stmt = conn.prepare(
+        let mut stmt = conn.prepare(&format!(
             r#"
-            SELECT "id", COALESCE("preferenceValue", '')
-            FROM "server_preferences"
-            ORDER BY "id" ASC
-            "#,
-        )?;
-        let rows = stmt.query_map([], |row| {
-            let id: String = row.get(0)?;
-            let payload: String = row.get(1)?;
-            Ok((id, payload))
-        })?;
+            SELECT {VIDEO_ITEM_COLUMNS}
+            FROM "video_details"
+            WHERE "userViews" > 0
+            ORDER BY "userViews" DESC, "lastWatchDate" DESC
+            LIMIT ?1
+            "#
+        ))?;
+
+        let rows = stmt.query_map(params![limit], video_item_from_row)?;
 
         let mut out = Vec::new();
         for row in rows {
-            let (id, payload) = row?;
-            let parsed = serde_json::from_str::<SourceServer>(&payload).ok();
-            let fallback_title = id
-                .trim()
-                .trim_start_matches("https://")
-                .trim_start_matches("http://")
-                .to_string();
-            out.push(parsed.unwrap_or(SourceServer {
-                base_url: id,
-                title: if fallback_title.is_empty() {
-                    "Source".to_string()
-                } else {
-                    fallback_title
-                },
-                color: None,
-                icon_url: None,
-            }));
+            out.push(row?);
         }
         Ok(out)
     }
 
-    pub fn clear_cache_data(&self) -> Result<u64, EngineError> {
+    pub fn search_cached_videos(
+        &self,
+        query: &str,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<VideoItem>, EngineError> {
+        let pattern = format!("%{}%", query.trim());
         let conn = self.conn()?;
-        let rows = conn.execute(
+        let mut stmt = conn.prepare(&format!(
             r#"
-            DELETE FROM "video_details"
-            WHERE "favoriteDate" IS NULL OR TRIM("favoriteDate") = ''
-            "#,
-            [],
-        )?;
-        Ok(rows as u64)
-    }
+            SELECT {VIDEO_ITEM_COLUMNS}
+            FROM "video_details"
+            WHERE "cacheDate" IS NOT NULL
+              AND (COALESCE("title", '') LIKE ?1 OR COALESCE("uploader", '') LIKE ?1)
+            ORDER BY "cacheDate" DESC, "id" ASC
+            LIMIT ?2 OFFSET ?3
+            "#
+        ))?;
 
-    pub fn clear_watch_history(&self) -> Result<u64, EngineError> {
-        let conn = self.conn()?;
-        let rows = conn.execute(
-            r#"
-            UPDATE "video_details"
-            SET "lastWatchDate" = NULL
-            WHERE "lastWatchDate" IS NOT NULL AND TRIM("lastWatchDate") <> ''
-            "#,
-            [],
-        )?;
-        Ok(rows as u64)
+        let rows = stmt.query_map(params![pattern, limit, offset], video_item_from_row)?;
+
+        let mut out = Vec::new();
+        for row in rows {
+            out.push(row?);
+        }
+        Ok(out)
     }
 
-    pub fn clear_favorites(&self) -> Result<u64, EngineError> {
+    // A blank network lists every cached video.
+    pub fn list_cached_by_network(
+        &self,
+        network: &str,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<VideoItem>, EngineError> {
         let conn = self.conn()?;
-        let rows = conn.execute(
+        let mut stmt = conn.prepare(&format!(
             r#"
-            UPDATE "video_details"
-            SET "favoriteDate" = NULL
-            WHERE "favoriteDate" IS NOT NULL AND TRIM("favoriteDate") <> ''
-            "#,
-            [],
+            SELECT {VIDEO_ITEM_COLUMNS}
+            FROM "video_details"
+            WHERE "cacheDate" IS NOT NULL
+              AND (?1 = '' OR lower(TRIM("network")) = lower(?1))
+            ORDER BY "lastUpdated" DESC, "id" ASC
+            LIMIT ?2 OFFSET ?3
+            "#
+        ))?;
+
+        let rows = stmt.query_map(
+            params![network.trim(), limit, offset],
+            stored_video_from_row,
         )?;
-        Ok(rows as u64)
+
+        let mut out = Vec::new();
+        for row in rows {
+            out.push(row?);
+        }
+        Ok(out)
     }
 
-    pub fn clear_achievements(&self) -> Result<u64, EngineError> {
+    pub fn list_networks(&self) -> Result<Vec<NetworkCount>, EngineError> {
         let conn = self.conn()?;
-        let rows = conn.execute(
+        let mut stmt = conn.prepare(
             r#"
-            DELETE FROM "user_preferences"
-            WHERE "id" LIKE 'achievement.%'
-               OR "id" LIKE 'stats.%'
-               OR "id" LIKE 'badges.%'
+            SELECT
+                TRIM("network") AS "name",
+                COUNT(*) AS "total",
+                SUM(CASE WHEN "favoriteDate" IS NOT NULL AND TRIM("favoriteDate") <> '' THEN 1 ELSE 0 END)
+            FROM "video_details"
+            WHERE "network" IS NOT NULL
+              AND TRIM("network") <> ''
+            GROUP BY "name"
+            ORDER BY "total" DESC, "name" ASC
             "#,
-            [],
         )?;
-        Ok(rows as u64)
+
+        let rows = stmt.query_map([], |row| {
+            let total: i64 = row.get(1)?;
+            let favorites: i64 = row.get(2)?;
+            Ok(NetworkCount {
+                network: row.get(0)?,
+                total: u32::try_from(total).unwrap_or(u32::MAX),
+                favorites: u32::try_from(favorites).unwrap_or(u32::MAX),
+            })
+        })?;
+
+        let mut out = Vec::new();
+        for row in rows {
+            out.push(row?);
+        }
+        Ok(out)
     }
 
-    pub fn reset_all_data(&self) -> Result<(), EngineError> {
-        let mut conn = self.conn()?;
-        let tx = conn.transaction()?;
-        tx.execute(r#"DELETE FROM "video_details""#, [])?;
-        tx.execute(r#"DELETE FROM "searches""#, [])?;
-        tx.execute(r#"DELETE FROM "categories""#, [])?;
-        tx.execute(r#"DELETE FROM "user_preferences""#, [])?;
-        tx.execute(r#"DELETE FROM "server_preferences""#, [])?;
-        tx.commit()?;
-        Ok(())
+    pub fn get_raw_video(&self, video_id: &str) -> Result<Option<String>, EngineError> {
+        let conn = self.conn()?;
+        let raw = conn
+            .query_row(
+                r#"SELECT "rawData" FROM "video_details" WHERE "id" = ?1"#,
+                params![video_id],
+                |row| row.get::<_, Option<String>>(0),
+            )
+            .optional()?;
+        Ok(raw.flatten())
     }
 
-    pub fn sync_categories(&self, categories: &[String]) -> Result<(), EngineError> {
-        let mut conn = self.conn()?;
-        let tx = conn.transaction()?;
-        {
-            let mut stmt = tx.prepare(
+    pub fn dashboard(&self) -> Result<Dashboard, EngineError> {
+        let conn = self.conn()?;
+        let last_search = conn
+            .query_row(
+                r#"SELECT "query" FROM "searches" ORDER BY "timestamp" DESC LIMIT 1"#,
+                [],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()?;
+        let update_available = conn
+            .query_row(
+                r#"SELECT "preferenceValue" FROM "user_preferences" WHERE "id" = 'yt_dlp_update_available'"#,
+                [],
+                |row| row.get::<_, Option<String>>(0),
+            )
+            .optional()?
+            .flatten();
+
+        Ok(Dashboard {
+            favorite_count: Self::count_rows(
+                &conn,
                 r#"
-                INSERT INTO "categories" ("id", "name")
-                VALUES (?1, ?2)
-                ON CONFLICT("id") DO UPDATE SET "name" = excluded."name"
+                SELECT COUNT(*) FROM "video_details"
+                WHERE "favoriteDate" IS NOT NULL AND TRIM("favoriteDate") <> ''
                 "#,
-            )?;
+            )?,
+            watched_count: Self::count_rows(
+                &conn,
+                r#"
+                SELECT COUNT(*) FROM "video_details"
+                WHERE "lastWatchDate" IS NOT NULL AND TRIM("lastWatchDate") <> ''
+                "#,
+            )?,
+            cached_count: Self::count_rows(
+                &conn,
+                r#"SELECT COUNT(*) FROM "video_details" WHERE "cacheDate" IS NOT NULL"#,
+            )?,
+            last_search,
+            yt_dlp_update_available: update_available.as_deref() == Some("true"),
+        })
+    }
+
+    pub fn migrate_cached_payloads(&self) -> Result<u64, EngineError> {
+        let migrated_version = self
+            .get_meta(PAYLOAD_VERSION_KEY)?
+            .and_then(|version| version.parse::<u32>().ok())
+            .unwrap_or(0);
+        if migrated_version >= PAYLOAD_VERSION {
+            return Ok(0);
+        }
 
-            for category in categories {
-                let trimmed = category.trim();
-                if trimmed.is_empty() {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        let mut migrated = 0u64;
+        {
+            let rows = tx
+                .prepare(
+                    r#"
+                    SELECT "id", "rawData"
+                    FROM "video_details"
+                    WHERE "rawData" IS NOT NULL AND TRIM("rawData") <> ''
+                    "#,
+                )?
+                .query_map([], |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+            let mut update =
+                tx.prepare(r#"UPDATE "video_details" SET "rawData" = ?2 WHERE "id" = ?1"#)?;
+            for (id, raw_data) in rows {
+                // Other clients store their own JSON in rawData; only rewrite our own payloads.
+                let Ok(video) = serde_json::from_str::<VideoItem>(&raw_data) else {
+                    continue;
+                };
+                if video.id != id {
                     continue;
                 }
-                stmt.execute(params![trimmed, trimmed])?;
+                let upgraded = serde_json::to_string(&video)?;
+                if upgraded != raw_data {
+                    update.execute(params![id, upgraded])?;
+                    migrated += 1;
+                }
             }
         }
-        tx.commit()?;
-        Ok(())
-    }
-
-    pub fn record_search(&self, query: &str) -> Result<(), EngineError> {
-        let conn = self.conn()?;
-        let timestamp = now_iso();
-        conn.execute(
+        tx.execute(
             r#"
-            INSERT INTO "searches" ("query", "timestamp", "frequency")
-            VALUES (?1, ?2, 1)
-            ON CONFLICT("query") DO UPDATE SET
-                "timestamp" = excluded."timestamp",
-                "frequency" = "searches"."frequency" + 1
+            INSERT INTO "user_preferences" ("id", "preferenceValue")
+            VALUES (?1, ?2)
+            ON CONFLICT("id") DO UPDATE SET "preferenceValue" = excluded."preferenceValue"
             "#,
-            params![query, timestamp],
+            params![PAYLOAD_VERSION_KEY, PAYLOAD_VERSION.to_string()],
         )?;
+        tx.commit()?;
+        Ok(migrated)
+    }
 
+    pub fn set_meta(&self, key: &str, value: &str) -> Result<(), EngineError> {
+        let conn = self.conn()?;
         conn.execute(
             r#"
-            UPDATE "categories"
-            SET "clicks" = "clicks" + 1
-            WHERE lower("name") = lower(?1) OR lower("id") = lower(?1)
+            INSERT INTO "user_preferences" ("id", "preferenceValue")
+            VALUES (?1, ?2)
+            ON CONFLICT("id") DO UPDATE SET "preferenceValue" = excluded."preferenceValue"
             "#,
-            params![query],
+            params![key, value],
         )?;
-
         Ok(())
     }
 
-    pub fn export_to(&self, export_path: &str) -> Result<bool, EngineError> {
-        let export = PathBuf::from(export_path);
-        if let Some(parent) = export.parent() {
-            fs::create_dir_all(parent).map_err(|err| EngineError::Database {
-                detail: format!("failed creating export directory: {err}"),
-            })?;
-        }
-
-        fs::copy(&self.path, export).map_err(|err| EngineError::Database {
-            detail: format!("failed to export database: {err}"),
-        })?;
+    pub fn last_migration_stats(&self) -> Result<Option<MigrationStats>, EngineError> {
+        Ok(self
+            .get_meta(MIGRATION_STATS_KEY)?
+            .and_then(|payload| serde_json::from_str::<MigrationStats>(&payload).ok()))
+    }
 
-        Ok(true)
+    pub fn remove_meta(&self, key: &str) -> Result<bool, EngineError> {
+        let conn = self.conn()?;
+        let removed = conn.execute(
+            r#"DELETE FROM "user_preferences" WHERE "id" = ?1"#,
+            params![key],
+        )?;
+        Ok(removed > 0)
     }
 
-    pub fn import_from(&self, import_path: &str) -> Result<bool, EngineError> {
-        let import = PathBuf::from(import_path);
-        if !import.exists() {
-            return Err(EngineError::NotFound {
-                detail: format!("import file does not exist: {}", import.display()),
-            });
-        }
+    pub fn list_meta_with_prefix(&self, prefix: &str) -> Result<Vec<(String, String)>, EngineError> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT "id", COALESCE("preferenceValue", '')
+            FROM "user_preferences"
+            WHERE "id" LIKE ?1
+            ORDER BY "id" ASC
+            "#,
+        )?;
+        let pattern = format!("{prefix}%");
+        let rows = stmt.query_map(params![pattern], |row| {
+            let key: String = row.get(0)?;
+            let value: String = row.get(1)?;
+            Ok((key, value))
+        })?;
 
-        if let Some(parent) = self.path.parent() {
-            fs::create_dir_all(parent).map_err(|err| EngineError::Database {
-                detail: format!("failed creating database directory: {err}"),
-            })?;
+        let mut out = Vec::new();
+        for row in rows {
+            out.push(row?);
         }
+        Ok(out)
+    }
 
-        fs::copy(import, &self.path).map_err(|err| EngineError::Database {
-            detail: format!("failed to import database: {err}"),
+    pub fn list_all_meta(&self) -> Result<Vec<(String, String)>, EngineError> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT "id", COALESCE("preferenceValue", '')
+            FROM "user_preferences"
+            ORDER BY "id" ASC
+            "#,
+        )?;
+        let rows = stmt.query_map([], |row| {
+            let key: String = row.get(0)?;
+            let value: String = row.get(1)?;
+            Ok((key, value))
         })?;
 
-        self.init()?;
-        Ok(true)
+        let mut out = Vec::new();
+        for row in rows {
+            out.push(row?);
+        }
+        Ok(out)
     }
 
-    fn conn(&self) -> Result<Connection, EngineError> {
-        Connection::open(&self.path).map_err(EngineError::from)
+    pub fn get_meta(&self, key: &str) -> Result<Option<String>, EngineError> {
+        let conn = self.conn()?;
+        let val = conn
+            .query_row(
+                r#"SELECT "preferenceValue" FROM "user_preferences" WHERE "id" = ?1"#,
+                params![key],
+                |row| row.get::<_, Option<String>>(0),
+            )
+            .optional()?;
+        Ok(val.flatten())
     }
 
-    fn migrate_legacy_schema(conn: &mut Connection) -> Result<(), EngineError> {
-        if Self::table_exists(conn, "engine_meta")? {
-            Self::migrate_legacy_meta(conn)?;
-        }
-        if Self::table_exists(conn, "video_cache")? {
-            Self::migrate_legacy_video_cache(conn)?;
-        }
-        if Self::table_exists(conn, "favorites")? {
-            Self::migrate_legacy_favorites(conn)?;
-        }
-        if Self::table_exists(conn, "resolved_cache")? {
-            Self::migrate_legacy_resolved_cache(conn)?;
+    pub fn upsert_server(&self, server: &SourceServer) -> Result<(), EngineError> {
+        let base_url = server.base_url.trim();
+        if base_url.is_empty() {
+            return Ok(());
         }
+
+        let payload = serde_json::to_string(server)?;
+        let conn = self.conn()?;
+        conn.execute(
+            r#"
+            INSERT INTO "server_preferences" ("id", "preferenceValue")
+            VALUES (?1, ?2)
+            ON CONFLICT("id") DO UPDATE SET "preferenceValue" = excluded."preferenceValue"
+            "#,
+            params![base_url, payload],
+        )?;
         Ok(())
     }
 
-    fn table_exists(conn: &Connection, table_name: &str) -> Result<bool, EngineError> {
-        let exists = conn
-            .query_row(
-                "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1 LIMIT 1",
-                params![table_name],
-                |_| Ok(()),
-            )
-            .optional()?
-            .is_some();
-        Ok(exists)
+    pub fn remove_server(&self, base_url: &str) -> Result<bool, EngineError> {
+        let conn = self.conn()?;
+        let removed = conn.execute(
+            r#"DELETE FROM "server_preferences" WHERE "id" = ?1"#,
+            params![base_url.trim()],
+        )?;
+        Ok(removed > 0)
     }
 
-    fn migrate_legacy_meta(conn: &Connection) -> Result<(), EngineError> {
-        let mut stmt = conn.prepare("SELECT key, value FROM engine_meta")?;
+    pub fn list_servers(&self) -> Result<Vec<SourceServer>, EngineError> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT "id", COALESCE("preferenceValue", '')
+            FROM "server_preferences"
+            ORDER BY "id" ASC
+            "#,
+        )?;
         let rows = stmt.query_map([], |row| {
-            let key: String = row.get(0)?;
-            let value: String = row.get(1)?;
-            Ok((key, value))
+            let id: String = row.get(0)?;
+            let payload: String = row.get(1)?;
+            Ok((id, payload))
         })?;
 
+        let mut out = Vec::new();
         for row in rows {
-            let (key, value) = row?;
-            conn.execute(
-                r#"
-                INSERT INTO "user_preferences" ("id", "preferenceValue")
-                VALUES (?1, ?2)
-                ON CONFLICT("id") DO UPDATE SET "preferenceValue" = excluded."preferenceValue"
-                "#,
-                params![key, value],
-            )?;
+            let (id, payload) = row?;
+            let parsed = serde_json::from_str::<SourceServer>(&payload).ok();
+            let fallback_title = id
+                .trim()
+                .trim_start_matches("https://")
+                .trim_start_matches("http://")
+                .to_string();
+            out.push(parsed.unwrap_or(SourceServer {
+                base_url: id,
+                title: if fallback_title.is_empty() {
+                    "Source".to_string()
+                } else {
+                    fallback_title
+                },
+                color: None,
+                icon_url: None,
+                headers: None,
+            }));
         }
-        Ok(())
+        Ok(out)
     }
 
-    fn migrate_legacy_video_cache(conn: &Connection) -> Result<(), EngineError> {
-        let mut stmt =
-            conn.prepare("SELECT video_id, payload_json, updated_at FROM video_cache")?;
-        let rows = stmt.query_map([], |row| {
-            let video_id: String = row.get(0)?;
-            let payload_json: String = row.get(1)?;
-            let updated_at: i64 = row.get(2)?;
-            Ok((video_id, payload_json, updated_at))
-        })?;
+    pub fn get_server(&self, base_url: &str) -> Result<Option<SourceServer>, EngineError> {
+        let wanted = base_url.trim().trim_end_matches('/');
+        Ok(self
+            .list_servers()?
+            .into_iter()
+            .find(|server| server.base_url.trim().trim_end_matches('/') == wanted))
+    }
 
-        for row in rows {
-            let (video_id, payload_json, updated_at) = row?;
-            let updated_iso = epoch_seconds_to_iso(updated_at);
-            let parsed = serde_json::from_str::<VideoItem>(&payload_json).ok();
+    pub fn clear_cache_data(&self) -> Result<u64, EngineError> {
+        let conn = self.conn()?;
+        Self::clear_cache_rows(&conn)
+    }
 
-            let (url, title, thumb, views, duration, uploader, network) =
-                if let Some(video) = parsed {
-                    (
-                        video.page_url,
-                        video.title,
-                        video.image_url,
-                        video.view_count.and_then(|count| i64::try_from(count).ok()),
-                        video.duration_seconds.map(i64::from),
-                        video.author_name,
-                        video.network,
-                    )
-                } else {
-                    (
-                        fallback_url(&video_id),
-                        video_id.clone(),
-                        None,
-                        None,
-                        None,
-                        None,
-                        None,
-                    )
+    fn clear_cache_rows(conn: &Connection) -> Result<u64, EngineError> {
+        let rows = conn.execute(
+            r#"
+            DELETE FROM "video_details"
+            WHERE "favoriteDate" IS NULL OR TRIM("favoriteDate") = ''
+            "#,
+            [],
+        )?;
+        Ok(rows as u64)
+    }
+
+    pub fn clear_watch_history(&self) -> Result<u64, EngineError> {
+        let conn = self.conn()?;
+        Self::clear_watch_dates(&conn)
+    }
+
+    fn clear_watch_dates(conn: &Connection) -> Result<u64, EngineError> {
+        let rows = conn.execute(
+            r#"
+            UPDATE "video_details"
+            SET "lastWatchDate" = NULL
+            WHERE "lastWatchDate" IS NOT NULL AND TRIM("lastWatchDate") <> ''
+            "#,
+            [],
+        )?;
+        Ok(rows as u64)
+    }
+
+    pub fn clear_favorites(&self) -> Result<u64, EngineError> {
+        let conn = self.conn()?;
+        Self::clear_favorite_dates(&conn)
+    }
+
+    fn clear_favorite_dates(conn: &Connection) -> Result<u64, EngineError> {
+        let rows = conn.execute(
+            r#"
+            UPDATE "video_details"
+            SET "favoriteDate" = NULL, "sortPosition" = NULL
+            WHERE "favoriteDate" IS NOT NULL AND TRIM("favoriteDate") <> ''
+            "#,
+            [],
+        )?;
+        Ok(rows as u64)
+    }
+
+    pub fn clear_favorites_for_network(&self, network: &str) -> Result<u64, EngineError> {
+        let network = network.trim();
+        if network.is_empty() {
+            return Err(EngineError::InvalidConfig {
+                detail: "network cannot be empty".to_string(),
+            });
+        }
+        let conn = self.conn()?;
+        let rows = conn.execute(
+            r#"
+            UPDATE "video_details"
+            SET "favoriteDate" = NULL, "sortPosition" = NULL
+            WHERE "favoriteDate" IS NOT NULL AND TRIM("favoriteDate") <> ''
+              AND lower(TRIM("network")) = lower(?1)
+            "#,
+            params![network],
+        )?;
+        Ok(rows as u64)
+    }
+
+    // Fills blank title/thumb/network on favorites from the row's own payloads: rawData (a
+    // VideoItem) first, then the cached resolve in allFormats. Filled columns are never overwritten,
+    // so running it again is a no-op.
+    pub fn backfill_favorite_metadata(&self) -> Result<u64, EngineError> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        let mut updated = 0u64;
+        {
+            let rows = tx
+                .prepare(
+                    r#"
+                    SELECT "id", "title", "thumb", "network", "rawData", "allFormats"
+                    FROM "video_details"
+                    WHERE "favoriteDate" IS NOT NULL AND TRIM("favoriteDate") <> ''
+                      AND (COALESCE(TRIM("title"), '') = ''
+                        OR COALESCE(TRIM("thumb"), '') = ''
+                        OR COALESCE(TRIM("network"), '') = '')
+                    "#,
+                )?
+                .query_map([], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, Option<String>>(1)?,
+                        row.get::<_, Option<String>>(2)?,
+                        row.get::<_, Option<String>>(3)?,
+                        row.get::<_, Option<String>>(4)?,
+                        row.get::<_, Option<String>>(5)?,
+                    ))
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+            let mut update = tx.prepare(
+                r#"UPDATE "video_details" SET "title" = ?2, "thumb" = ?3, "network" = ?4 WHERE "id" = ?1"#,
+            )?;
+            for (id, title, thumb, network, raw_data, all_formats) in rows {
+                let video = raw_data
+                    .as_deref()
+                    .and_then(|payload| serde_json::from_str::<VideoItem>(payload).ok())
+                    .filter(|video| video.id == id);
+                let resolved = all_formats
+                    .as_deref()
+                    .and_then(|payload| serde_json::from_str::<ResolvedVideo>(payload).ok());
+
+                let present =
+                    |value: Option<String>| value.filter(|value| !value.trim().is_empty());
+                let mut changed = false;
+                let mut fill = |current: Option<String>, candidates: Vec<Option<String>>| {
+                    if present(current.clone()).is_some() {
+                        return current;
+                    }
+                    match candidates.into_iter().find_map(present) {
+                        Some(value) => {
+                            changed = true;
+                            Some(value)
+                        }
+                        None => current,
+                    }
                 };
+                let title = fill(
+                    title,
+                    vec![
+                        video.as_ref().map(|video| video.title.clone()),
+                        resolved
+                            .as_ref()
+                            .map(|resolved| resolved.title.clone())
+                            .filter(|title| title != "Untitled"),
+                    ],
+                );
+                let thumb = fill(
+                    thumb,
+                    vec![
+                        video.as_ref().and_then(|video| video.image_url.clone()),
+                        resolved
+                            .as_ref()
+                            .and_then(|resolved| resolved.thumbnail_url.clone()),
+                    ],
+                );
+                let network = fill(
+                    network,
+                    vec![video.as_ref().and_then(|video| video.network.clone())],
+                );
+
+                if changed {
+                    update.execute(params![id, title, thumb, network])?;
+                    updated += 1;
+                }
+            }
+        }
+        tx.commit()?;
+        Ok(updated)
+    }
+
+    pub fn clear_achievements(&self) -> Result<u64, EngineError> {
+        let conn = self.conn()?;
+        let rows = conn.execute(
+            r#"
+            DELETE FROM "user_preferences"
+            WHERE "id" LIKE 'achievement.%'
+               OR "id" LIKE 'stats.%'
+               OR "id" LIKE 'badges.%'
+            "#,
+            [],
+        )?;
+        Ok(rows as u64)
+    }
+
+    pub fn reset_all_data(&self) -> Result<(), EngineError> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        tx.execute(r#"DELETE FROM "video_details""#, [])?;
+        tx.execute(r#"DELETE FROM "searches""#, [])?;
+        tx.execute(r#"DELETE FROM "categories""#, [])?;
+        tx.execute(r#"DELETE FROM "user_preferences""#, [])?;
+        tx.execute(r#"DELETE FROM "server_preferences""#, [])?;
+        tx.execute(r#"DELETE FROM "activity_log""#, [])?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    // Steps run history, favorites, cache, so clearing favorites and cache together leaves no rows.
+    // Each count is what that step touched; any failure rolls the whole reset back.
+    pub fn reset_selective(&self, options: &ResetOptions) -> Result<ResetReport, EngineError> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        let mut report = ResetReport::default();
+        if options.history {
+            report.history = Self::clear_watch_dates(&tx)?;
+        }
+        if options.favorites {
+            report.favorites = Self::clear_favorite_dates(&tx)?;
+        }
+        if options.cache {
+            report.cache = Self::clear_cache_rows(&tx)?;
+        }
+        if options.searches {
+            report.searches = tx.execute(r#"DELETE FROM "searches""#, [])? as u64;
+        }
+        if options.servers {
+            report.servers = tx.execute(r#"DELETE FROM "server_preferences""#, [])? as u64;
+        }
+        if options.preferences {
+            report.preferences = tx.execute(r#"DELETE FROM "user_preferences""#, [])? as u64;
+        }
+        tx.commit()?;
+        Ok(report)
+    }
+
+    // The incoming list is authoritative: missing categories are pruned, survivors keep their clicks.
+    // An empty list is treated as "no update" so a bad status response cannot wipe everything.
+    pub fn sync_categories(&self, categories: &[String]) -> Result<(), EngineError> {
+        let incoming: Vec<&str> = categories
+            .iter()
+            .map(|category| category.trim())
+            .filter(|category| !category.is_empty())
+            .collect();
+        if incoming.is_empty() {
+            return Ok(());
+        }
+
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        {
+            let mut stmt = tx.prepare(
+                r#"
+                INSERT INTO "categories" ("id", "name")
+                VALUES (?1, ?2)
+                ON CONFLICT("id") DO UPDATE SET "name" = excluded."name"
+                "#,
+            )?;
+
+            for category in &incoming {
+                stmt.execute(params![category, category])?;
+            }
+
+            let existing = tx
+                .prepare(r#"SELECT "id" FROM "categories""#)?
+                .query_map([], |row| row.get::<_, String>(0))?
+                .collect::<Result<Vec<_>, _>>()?;
+            let mut delete = tx.prepare(r#"DELETE FROM "categories" WHERE "id" = ?1"#)?;
+            for id in existing {
+                if !incoming.contains(&id.as_str()) {
+                    delete.execute(params![id])?;
+                }
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    // Onboarding seed: only adds rows that are missing, so existing clicks and search frequencies
+    // survive and nothing is pruned.
+    pub fn seed_data(&self, categories: &[String], searches: &[String]) -> Result<(), EngineError> {
+        let timestamp = self.now_iso();
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        {
+            let mut category_stmt = tx.prepare(
+                r#"
+                INSERT INTO "categories" ("id", "name")
+                VALUES (?1, ?1)
+                ON CONFLICT("id") DO NOTHING
+                "#,
+            )?;
+            for category in categories.iter().filter_map(|value| non_empty_str(value)) {
+                category_stmt.execute(params![category])?;
+            }
+
+            let mut search_stmt = tx.prepare(
+                r#"
+                INSERT INTO "searches" ("query", "timestamp", "frequency")
+                VALUES (?1, ?2, 1)
+                ON CONFLICT("query") DO NOTHING
+                "#,
+            )?;
+            for query in searches.iter().filter_map(|value| non_empty_str(value)) {
+                search_stmt.execute(params![query, timestamp])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn decay_category_clicks(&self, factor: f64) -> Result<(), EngineError> {
+        if !(factor > 0.0 && factor < 1.0) {
+            return Err(EngineError::InvalidConfig {
+                detail: format!("decay factor must be between 0 and 1, got {factor}"),
+            });
+        }
+        let conn = self.conn()?;
+        conn.execute(
+            r#"UPDATE "categories" SET "clicks" = CAST("clicks" * ?1 AS INTEGER)"#,
+            params![factor],
+        )?;
+        Ok(())
+    }
+
+    pub fn reset_category_clicks(&self) -> Result<(), EngineError> {
+        let conn = self.conn()?;
+        conn.execute(r#"UPDATE "categories" SET "clicks" = 0"#, [])?;
+        Ok(())
+    }
+
+    pub fn record_search(&self, query: &str) -> Result<(), EngineError> {
+        let conn = self.conn()?;
+        let timestamp = self.now_iso();
+        conn.execute(
+            r#"
+            INSERT INTO "searches" ("query", "timestamp", "frequency")
+            VALUES (?1, ?2, 1)
+            ON CONFLICT("query") DO UPDATE SET
+                "timestamp" = excluded."timestamp",
+                "frequency" = "searches"."frequency" + 1
+            "#,
+            params![query, timestamp],
+        )?;
+
+        conn.execute(
+            r#"
+            UPDATE "categories"
+            SET "clicks" = "clicks" + 1
+            WHERE lower("name") = lower(?1) OR lower("id") = lower(?1)
+            "#,
+            params![query],
+        )?;
+
+        Ok(())
+    }
+
+    pub fn record_activity(&self, kind: &str, detail: &str) -> Result<(), EngineError> {
+        self.append_activity(kind, detail, ACTIVITY_LOG_LIMIT)
+    }
+
+    fn append_activity(&self, kind: &str, detail: &str, keep: u32) -> Result<(), EngineError> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        self.insert_activity(&tx, kind, detail, keep)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn insert_activity(
+        &self,
+        conn: &Connection,
+        kind: &str,
+        detail: &str,
+        keep: u32,
+    ) -> Result<(), EngineError> {
+        conn.execute(
+            r#"INSERT INTO "activity_log" ("ts", "kind", "detail") VALUES (?1, ?2, ?3)"#,
+            params![self.now_iso(), kind, detail],
+        )?;
+        conn.execute(
+            r#"
+            DELETE FROM "activity_log"
+            WHERE "id" NOT IN (
+                SELECT "id" FROM "activity_log" ORDER BY "id" DESC LIMIT ?1
+            )
+            "#,
+            params![keep],
+        )?;
+        Ok(())
+    }
+
+    pub fn recent_activity(&self, limit: u32) -> Result<Vec<ActivityEntry>, EngineError> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT "ts", "kind", "detail" FROM "activity_log"
+            ORDER BY "id" DESC
+            LIMIT ?1
+            "#,
+        )?;
+        let rows = stmt.query_map(params![limit], |row| {
+            let ts: String = row.get(0)?;
+            Ok(ActivityEntry {
+                kind: row.get(1)?,
+                detail: row.get(2)?,
+                recorded_at_epoch: parse_timestamp_to_epoch_seconds(&ts).unwrap_or(0),
+            })
+        })?;
+        Ok(rows.collect::<Result<Vec<_>, _>>()?)
+    }
+
+    pub fn clear_activity_log(&self) -> Result<u64, EngineError> {
+        let conn = self.conn()?;
+        let rows = conn.execute(r#"DELETE FROM "activity_log""#, [])?;
+        Ok(rows as u64)
+    }
+
+    pub fn export_to(&self, export_path: &str) -> Result<bool, EngineError> {
+        self.require_file_backed("export")?;
+        let export = PathBuf::from(export_path);
+        if let Some(parent) = export.parent() {
+            fs::create_dir_all(parent).map_err(|err| EngineError::Database {
+                detail: format!("failed creating export directory: {err}"),
+            })?;
+        }
+
+        // The shared connection keeps the WAL open, so fold it into the main file first.
+        self.conn()?
+            .execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")?;
+
+        fs::copy(&self.path, export).map_err(|err| EngineError::Database {
+            detail: format!("failed to export database: {err}"),
+        })?;
+
+        Ok(true)
+    }
+
+    // Folds the WAL into the main file and closes the shared connection, which removes `-wal`.
+    pub fn flush(&self) -> Result<(), EngineError> {
+        // Closing the only connection to an in-memory database would discard it.
+        if self.is_in_memory() {
+            return Ok(());
+        }
+        let mut guard = self.lock()?;
+        if let Some(conn) = guard.take() {
+            conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")?;
+            conn.close().map_err(|(_, err)| EngineError::from(err))?;
+        }
+        Ok(())
+    }
+
+    pub fn export_bytes(&self) -> Result<Vec<u8>, EngineError> {
+        self.require_file_backed("export")?;
+        // Hold the connection so no write lands between the checkpoint and the read.
+        let conn = self.conn()?;
+        conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")?;
+        fs::read(&self.path).map_err(|err| EngineError::Database {
+            detail: format!("failed to read database for export: {err}"),
+        })
+    }
+
+    pub fn validate_import(&self, import_path: &str) -> Result<ImportReport, EngineError> {
+        let import = PathBuf::from(import_path);
+        if !import.exists() {
+            return Err(EngineError::NotFound {
+                detail: format!("import file does not exist: {}", import.display()),
+            });
+        }
+
+        let mut report = ImportReport {
+            valid: false,
+            integrity_ok: false,
+            missing_tables: Vec::new(),
+            video_count: 0,
+            favorite_count: 0,
+            preference_count: 0,
+            server_count: 0,
+            issues: Vec::new(),
+        };
+
+        let conn = match self.open_import(&import) {
+            Ok(conn) => conn,
+            Err(err) => {
+                report
+                    .issues
+                    .push(format!("failed to open import file: {err}"));
+                return Ok(report);
+            }
+        };
+
+        match conn.query_row("PRAGMA integrity_check", [], |row| row.get::<_, String>(0)) {
+            Ok(result) if result.eq_ignore_ascii_case("ok") => report.integrity_ok = true,
+            Ok(result) => report
+                .issues
+                .push(format!("integrity check failed: {result}")),
+            Err(err) => {
+                report
+                    .issues
+                    .push(format!("not a readable sqlite database: {err}"));
+                return Ok(report);
+            }
+        }
+
+        for table in TEMPLATE_TABLES {
+            if !Self::table_exists(&conn, table)? {
+                report.missing_tables.push(table.to_string());
+            }
+        }
+        let mut has_legacy_tables = false;
+        for table in LEGACY_TABLES {
+            has_legacy_tables |= Self::table_exists(&conn, table)?;
+        }
+
+        if Self::table_exists(&conn, "video_details")? {
+            report.video_count =
+                Self::count_rows(&conn, r#"SELECT COUNT(*) FROM "video_details""#)?;
+            report.favorite_count = Self::count_rows(
+                &conn,
+                r#"
+                SELECT COUNT(*) FROM "video_details"
+                WHERE "favoriteDate" IS NOT NULL AND TRIM("favoriteDate") <> ''
+                "#,
+            )?;
+        }
+        if Self::table_exists(&conn, "user_preferences")? {
+            report.preference_count =
+                Self::count_rows(&conn, r#"SELECT COUNT(*) FROM "user_preferences""#)?;
+        }
+        if Self::table_exists(&conn, "server_preferences")? {
+            report.server_count =
+                Self::count_rows(&conn, r#"SELECT COUNT(*) FROM "server_preferences""#)?;
+        }
+
+        // Legacy databases lack the template tables but are upgraded by `init` after import.
+        if !report.missing_tables.is_empty() && !has_legacy_tables {
+            report.issues.push(format!(
+                "missing expected tables: {}",
+                report.missing_tables.join(", ")
+            ));
+        }
+
+        report.valid = report.integrity_ok && report.issues.is_empty();
+        Ok(report)
+    }
+
+    pub fn import_from(
+        &self,
+        import_path: &str,
+        validate: bool,
+        backup: bool,
+    ) -> Result<bool, EngineError> {
+        self.require_file_backed("import into")?;
+        let import = PathBuf::from(import_path);
+        if !import.exists() {
+            return Err(EngineError::NotFound {
+                detail: format!("import file does not exist: {}", import.display()),
+            });
+        }
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(|err| EngineError::Database {
+                detail: format!("failed creating database directory: {err}"),
+            })?;
+        }
+
+        // Copying straight over the live file would leave it half-written if the app dies
+        // mid-copy; the staged copy is only renamed into place once it is complete.
+        let staged = self.staging_path("import");
+        if let Err(err) = fs::copy(&import, &staged) {
+            let _ = fs::remove_file(&staged);
+            return Err(EngineError::Database {
+                detail: format!("failed to stage imported database: {err}"),
+            });
+        }
+        self.import_staged(staged, validate, backup)
+    }
+
+    pub fn import_bytes(&self, bytes: &[u8], backup: bool) -> Result<bool, EngineError> {
+        self.require_file_backed("import into")?;
+        let staged = self.staging_path("import");
+        if let Err(err) = fs::write(&staged, bytes) {
+            let _ = fs::remove_file(&staged);
+            return Err(EngineError::Database {
+                detail: format!("failed to stage imported database: {err}"),
+            });
+        }
+        self.import_staged(staged, true, backup)
+    }
+
+    // Takes ownership of a complete staged copy next to the live file: it is converted for an
+    // encrypted engine if needed, optionally validated, then renamed over the live path (atomic on
+    // the same filesystem). The staged file is removed on any failure.
+    fn import_staged(
+        &self,
+        mut staged: PathBuf,
+        validate: bool,
+        backup: bool,
+    ) -> Result<bool, EngineError> {
+        match self.encrypted_copy(&staged) {
+            Ok(Some(converted)) => {
+                remove_staged(&staged);
+                staged = converted;
+            }
+            Ok(None) => {}
+            Err(err) => {
+                remove_staged(&staged);
+                return Err(err);
+            }
+        }
+
+        // The live file is only touched once the staged copy validates.
+        let report = if validate {
+            self.validate_import(&staged.to_string_lossy()).map(Some)
+        } else {
+            Ok(None)
+        };
+        let swapped = match report {
+            Ok(Some(report)) if !report.valid => Err(EngineError::Database {
+                detail: format!("import validation failed: {}", report.issues.join("; ")),
+            }),
+            Ok(_) => self.swap_in(backup, |target| fs::rename(&staged, target)),
+            Err(err) => Err(err),
+        };
+        // After a successful rename only the sidecars validation opened are left to remove.
+        remove_staged(&staged);
+        swapped?;
+        self.init()?;
+        Ok(true)
+    }
+
+    // Staged imports left behind by a killed process are never complete enough to trust.
+    fn remove_stale_staging_files(&self) {
+        let Some(file_name) = self.path.file_name().and_then(|name| name.to_str()) else {
+            return;
+        };
+        let dir = match self.path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let Ok(entries) = fs::read_dir(&dir) else {
+            return;
+        };
+        let prefixes = STAGING_LABELS.map(|label| format!("{file_name}.{label}-"));
+        for entry in entries.flatten() {
+            let name = entry.file_name();
+            let Some(name) = name.to_str() else {
+                continue;
+            };
+            let staged = [".tmp", ".tmp-wal", ".tmp-shm"]
+                .iter()
+                .any(|suffix| name.ends_with(suffix));
+            if staged && prefixes.iter().any(|prefix| name.starts_with(prefix)) {
+                let _ = fs::remove_file(entry.path());
+            }
+        }
+    }
+
+    fn staging_path(&self, label: &str) -> PathBuf {
+        let mut staged = self.path.clone().into_os_string();
+        staged.push(format!(
+            ".{label}-{}.tmp",
+            self.clock.now().timestamp_millis()
+        ));
+        PathBuf::from(staged)
+    }
+
+    // Import files are either plaintext or encrypted with this engine's key.
+    fn open_import(&self, path: &Path) -> Result<Connection, EngineError> {
+        let conn = Connection::open_with_flags(
+            path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+        if let Some(key) = &self.encryption_key {
+            if !is_plaintext_sqlite(path) {
+                conn.pragma_update(None, "key", key)?;
+            }
+        }
+        Ok(conn)
+    }
+
+    // An encrypted engine cannot open plaintext files, so plaintext imports are converted
+    // into a sibling file encrypted with the engine key before they are swapped in.
+    fn encrypted_copy(&self, source: &Path) -> Result<Option<PathBuf>, EngineError> {
+        let Some(key) = &self.encryption_key else {
+            return Ok(None);
+        };
+        if !is_plaintext_sqlite(source) {
+            return Ok(None);
+        }
+
+        let target = self.staging_path("encrypt");
+        let converted = Connection::open(source).and_then(|plain| {
+            plain.execute(
+                "ATTACH DATABASE ?1 AS encrypted KEY ?2",
+                params![target.to_string_lossy(), key],
+            )?;
+            plain.query_row("SELECT sqlcipher_export('encrypted')", [], |_| Ok(()))?;
+            plain.execute_batch("DETACH DATABASE encrypted;")
+        });
+        if let Err(err) = converted {
+            let _ = fs::remove_file(&target);
+            return Err(EngineError::Database {
+                detail: format!("failed to encrypt plaintext import: {err}"),
+            });
+        }
+        Ok(Some(target))
+    }
+
+    fn swap_in(
+        &self,
+        backup: bool,
+        replace: impl FnOnce(&Path) -> std::io::Result<()>,
+    ) -> Result<(), EngineError> {
+        // Close the shared connection so the swap does not race an open WAL.
+        let mut guard = self.lock()?;
+        guard.take();
+        if backup && self.path.exists() {
+            let backup_path = self.backup_path(self.clock.now().timestamp());
+            fs::copy(&self.path, &backup_path).map_err(|err| EngineError::Database {
+                detail: format!("failed to back up database before import: {err}"),
+            })?;
+        }
+        replace(&self.path).map_err(|err| EngineError::Database {
+            detail: format!("failed to import database: {err}"),
+        })
+    }
+
+    pub fn merge_from(&self, import_path: &str) -> Result<MergeReport, EngineError> {
+        let report = self.validate_import(import_path)?;
+        if !report.valid || !report.missing_tables.is_empty() {
+            let mut issues = report.issues;
+            if !report.missing_tables.is_empty() {
+                issues.push(format!(
+                    "missing expected tables: {}",
+                    report.missing_tables.join(", ")
+                ));
+            }
+            return Err(EngineError::Database {
+                detail: format!("merge validation failed: {}", issues.join("; ")),
+            });
+        }
+
+        let import = self.open_import(Path::new(import_path))?;
+        let mut merged = MergeReport::default();
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        Self::merge_favorites(&import, &tx, &mut merged)?;
+        // Preferences and servers carry no timestamps, so live values always win.
+        for table in ["user_preferences", "server_preferences"] {
+            Self::merge_preferences(&import, &tx, table, &mut merged)?;
+        }
+        tx.commit()?;
+        Ok(merged)
+    }
+
+    pub fn list_backups(&self) -> Result<Vec<DatabaseBackup>, EngineError> {
+        if self.is_in_memory() {
+            return Ok(Vec::new());
+        }
+        let Some(file_name) = self.path.file_name().and_then(|name| name.to_str()) else {
+            return Ok(Vec::new());
+        };
+        let prefix = format!("{file_name}.bak-");
+        let dir = match self.path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(_) => return Ok(Vec::new()),
+        };
+
+        let mut out = Vec::new();
+        for entry in entries.flatten() {
+            let name = entry.file_name();
+            let Some(epoch) = name
+                .to_str()
+                .and_then(|name| name.strip_prefix(&prefix))
+                .and_then(|suffix| suffix.parse::<i64>().ok())
+            else {
+                continue;
+            };
+            let size_bytes = entry.metadata().map(|meta| meta.len()).unwrap_or(0);
+            out.push(DatabaseBackup {
+                path: entry.path().to_string_lossy().into_owned(),
+                created_at_epoch: epoch,
+                size_bytes,
+            });
+        }
+        out.sort_by_key(|backup| std::cmp::Reverse(backup.created_at_epoch));
+        Ok(out)
+    }
+
+    pub fn restore_backup(&self, backup_path: &str, backup: bool) -> Result<bool, EngineError> {
+        let is_known_backup = self
+            .list_backups()?
+            .iter()
+            .any(|candidate| Path::new(&candidate.path) == Path::new(backup_path));
+        if !is_known_backup {
+            return Err(EngineError::NotFound {
+                detail: format!("not a backup of this database: {backup_path}"),
+            });
+        }
+        self.import_from(backup_path, true, backup)
+    }
+
+    fn backup_path(&self, epoch_seconds: i64) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".bak-{epoch_seconds}"));
+        PathBuf::from(path)
+    }
+
+    fn conn(&self) -> Result<PooledConnection<'_>, EngineError> {
+        self.pooled(self.lock()?)
+    }
+
+    // Like `conn`, but gives up once `wait` has passed while another operation (a merge, an
+    // import, a long migration) still holds the connection; `None` means the deadline passed.
+    fn conn_within(&self, wait: Duration) -> Result<Option<PooledConnection<'_>>, EngineError> {
+        let deadline = Instant::now() + wait;
+        let guard = loop {
+            match self.conn.try_lock() {
+                Ok(guard) => break guard,
+                Err(TryLockError::Poisoned(_)) => {
+                    return Err(EngineError::Database {
+                        detail: "database connection lock poisoned".to_string(),
+                    })
+                }
+                Err(TryLockError::WouldBlock) if Instant::now() >= deadline => return Ok(None),
+                Err(TryLockError::WouldBlock) => thread::sleep(CONN_POLL_INTERVAL),
+            }
+        };
+        self.pooled(guard).map(Some)
+    }
+
+    fn pooled<'a>(
+        &'a self,
+        mut guard: MutexGuard<'a, Option<Connection>>,
+    ) -> Result<PooledConnection<'a>, EngineError> {
+        if guard.is_none() {
+            *guard = Some(Self::open_connection(
+                &self.path,
+                self.busy_timeout,
+                self.encryption_key.as_deref(),
+            )?);
+        }
+        Ok(PooledConnection { guard })
+    }
+
+    // Lets engine tests stand in for a long operation that keeps the connection.
+    #[cfg(test)]
+    pub fn hold_connection(&self) -> MutexGuard<'_, Option<Connection>> {
+        self.lock().expect("connection lock")
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, Option<Connection>>, EngineError> {
+        self.conn.lock().map_err(|_| EngineError::Database {
+            detail: "database connection lock poisoned".to_string(),
+        })
+    }
+
+    fn open_connection(
+        path: &Path,
+        busy_timeout: Duration,
+        encryption_key: Option<&str>,
+    ) -> Result<Connection, EngineError> {
+        let conn = Connection::open(path)?;
+        if let Some(key) = encryption_key {
+            conn.pragma_update(None, "key", key)?;
+            // SQLCipher only checks the key on first read, so fail here instead of mid-query.
+            conn.query_row("SELECT COUNT(*) FROM sqlite_master", [], |_| Ok(()))
+                .map_err(|err| EngineError::Database {
+                    detail: format!("failed to unlock encrypted database: {err}"),
+                })?;
+        }
+        // Other connections (merge, import, the host app) can hold the lock; wait instead of failing.
+        conn.busy_timeout(busy_timeout)?;
+        conn.execute_batch(
+            r#"
+            PRAGMA journal_mode = WAL;
+            PRAGMA foreign_keys = ON;
+            "#,
+        )?;
+        Ok(conn)
+    }
+
+    fn migrate_legacy_schema(conn: &mut Connection) -> Result<MigrationStats, EngineError> {
+        let mut stats = MigrationStats {
+            meta: 0,
+            videos: 0,
+            favorites: 0,
+            resolved: 0,
+        };
+        let tx = conn.transaction()?;
+        // Each legacy table is dropped only after its rows were copied; a failure rolls back both.
+        if Self::table_exists(&tx, "engine_meta")? {
+            stats.meta = Self::migrate_legacy_meta(&tx)?;
+            tx.execute_batch("DROP TABLE engine_meta;")?;
+        }
+        if Self::table_exists(&tx, "video_cache")? {
+            stats.videos = Self::migrate_legacy_video_cache(&tx)?;
+            tx.execute_batch("DROP TABLE video_cache;")?;
+        }
+        if Self::table_exists(&tx, "favorites")? {
+            stats.favorites = Self::migrate_legacy_favorites(&tx)?;
+            tx.execute_batch("DROP TABLE favorites;")?;
+        }
+        if Self::table_exists(&tx, "resolved_cache")? {
+            stats.resolved = Self::migrate_legacy_resolved_cache(&tx)?;
+            tx.execute_batch("DROP TABLE resolved_cache;")?;
+        }
+
+        // Empty legacy tables leave the previously recorded stats untouched.
+        if stats.total() > 0 {
+            tx.execute(
+                r#"
+                INSERT INTO "user_preferences" ("id", "preferenceValue")
+                VALUES (?1, ?2)
+                ON CONFLICT("id") DO UPDATE SET "preferenceValue" = excluded."preferenceValue"
+                "#,
+                params![MIGRATION_STATS_KEY, serde_json::to_string(&stats)?],
+            )?;
+        }
+        tx.commit()?;
+        Ok(stats)
+    }
+
+    fn prune_stale_resolved_rows(conn: &Connection, page_url: &str) -> Result<u64, EngineError> {
+        // Favorited rows are user data and are never pruned, only plain cache duplicates.
+        let removed = conn.execute(
+            r#"
+            DELETE FROM "video_details"
+            WHERE "url" = ?1
+              AND "allFormats" IS NOT NULL
+              AND TRIM("allFormats") <> ''
+              AND ("favoriteDate" IS NULL OR TRIM("favoriteDate") = '')
+              AND rowid <> (
+                  SELECT rowid
+                  FROM "video_details"
+                  WHERE "url" = ?1
+                    AND "allFormats" IS NOT NULL
+                    AND TRIM("allFormats") <> ''
+                  ORDER BY
+                      ("favoriteDate" IS NOT NULL AND TRIM("favoriteDate") <> '') DESC,
+                      "cacheDate" DESC,
+                      rowid DESC
+                  LIMIT 1
+              )
+            "#,
+            params![page_url],
+        )?;
+        Ok(removed as u64)
+    }
+
+    fn merge_favorites(
+        import: &Connection,
+        tx: &Connection,
+        merged: &mut MergeReport,
+    ) -> Result<(), EngineError> {
+        let mut stmt = import.prepare(
+            r#"
+            SELECT
+                "id", "url", "title", "thumb", "dateAdded", "views", "duration",
+                "uploader", "network", "lastUpdated", "favoriteDate", "rawData"
+            FROM "video_details"
+            WHERE "favoriteDate" IS NOT NULL
+              AND TRIM("favoriteDate") <> ''
+            "#,
+        )?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            let video_id: String = row.get(0)?;
+            let favorite_date: String = row.get(10)?;
+            let last_updated: Option<String> = row.get(9)?;
+            let existing: Option<(Option<String>, Option<String>)> = tx
+                .query_row(
+                    r#"SELECT "favoriteDate", "lastUpdated" FROM "video_details" WHERE "id" = ?1"#,
+                    params![video_id],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .optional()?;
+
+            if let Some((live_favorite, live_updated)) = &existing {
+                let imported_at =
+                    latest_timestamp(&[Some(favorite_date.as_str()), last_updated.as_deref()]);
+                let live_at =
+                    latest_timestamp(&[live_favorite.as_deref(), live_updated.as_deref()]);
+                if imported_at <= live_at {
+                    merged.skipped += 1;
+                    continue;
+                }
+            }
+
+            tx.execute(
+                r#"
+                INSERT INTO "video_details" (
+                    "id", "url", "title", "thumb", "dateAdded", "views", "duration",
+                    "uploader", "network", "lastUpdated", "favoriteDate", "rawData"
+                )
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+                ON CONFLICT("id") DO UPDATE SET
+                    "url" = COALESCE(NULLIF(excluded."url", ''), "url"),
+                    "title" = excluded."title",
+                    "thumb" = excluded."thumb",
+                    "views" = excluded."views",
+                    "duration" = excluded."duration",
+                    "uploader" = excluded."uploader",
+                    "network" = excluded."network",
+                    "lastUpdated" = excluded."lastUpdated",
+                    "favoriteDate" = excluded."favoriteDate",
+                    "rawData" = excluded."rawData"
+                "#,
+                params![
+                    video_id,
+                    row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                    row.get::<_, Option<String>>(4)?,
+                    row.get::<_, Option<i64>>(5)?,
+                    row.get::<_, Option<i64>>(6)?,
+                    row.get::<_, Option<String>>(7)?,
+                    row.get::<_, Option<String>>(8)?,
+                    last_updated,
+                    favorite_date,
+                    row.get::<_, Option<String>>(11)?,
+                ],
+            )?;
+            if existing.is_some() {
+                merged.updated += 1;
+            } else {
+                merged.added += 1;
+            }
+        }
+        Ok(())
+    }
+
+    fn merge_preferences(
+        import: &Connection,
+        tx: &Connection,
+        table: &str,
+        merged: &mut MergeReport,
+    ) -> Result<(), EngineError> {
+        let mut stmt =
+            import.prepare(&format!(r#"SELECT "id", "preferenceValue" FROM "{table}""#))?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            let id: String = row.get(0)?;
+            let value: Option<String> = row.get(1)?;
+            let inserted = tx.execute(
+                &format!(
+                    r#"INSERT INTO "{table}" ("id", "preferenceValue") VALUES (?1, ?2) ON CONFLICT("id") DO NOTHING"#
+                ),
+                params![id, value],
+            )?;
+            if inserted > 0 {
+                merged.added += 1;
+            } else {
+                merged.skipped += 1;
+            }
+        }
+        Ok(())
+    }
+
+    fn count_rows(conn: &Connection, sql: &str) -> Result<u64, EngineError> {
+        let count: i64 = conn.query_row(sql, [], |row| row.get(0))?;
+        Ok(u64::try_from(count).unwrap_or(0))
+    }
+
+    fn table_exists(conn: &Connection, table_name: &str) -> Result<bool, EngineError> {
+        let exists = conn
+            .query_row(
+                "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1 LIMIT 1",
+                params![table_name],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some();
+        Ok(exists)
+    }
+
+    fn index_exists(conn: &Connection, index_name: &str) -> Result<bool, EngineError> {
+        let exists = conn
+            .query_row(
+                "SELECT 1 FROM sqlite_master WHERE type = 'index' AND name = ?1 LIMIT 1",
+                params![index_name],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some();
+        Ok(exists)
+    }
+
+    fn column_exists(
+        conn: &Connection,
+        table_name: &str,
+        column_name: &str,
+    ) -> Result<bool, EngineError> {
+        let exists = conn
+            .query_row(
+                "SELECT 1 FROM pragma_table_info(?1) WHERE name = ?2 LIMIT 1",
+                params![table_name, column_name],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some();
+        Ok(exists)
+    }
+
+    fn migrate_legacy_meta(conn: &Connection) -> Result<u64, EngineError> {
+        let mut stmt = conn.prepare("SELECT key, value FROM engine_meta")?;
+        let rows = stmt.query_map([], |row| {
+            let key: String = row.get(0)?;
+            let value: String = row.get(1)?;
+            Ok((key, value))
+        })?;
+
+        let mut migrated = 0;
+        for row in rows {
+            let (key, value) = row?;
+            conn.execute(
+                r#"
+                INSERT INTO "user_preferences" ("id", "preferenceValue")
+                VALUES (?1, ?2)
+                ON CONFLICT("id") DO UPDATE SET "preferenceValue" = excluded."preferenceValue"
+                "#,
+                params![key, value],
+            )?;
+            migrated += 1;
+        }
+        Ok(migrated)
+    }
+
+    fn migrate_legacy_video_cache(conn: &Connection) -> Result<u64, EngineError> {
+        let mut stmt =
+            conn.prepare("SELECT video_id, payload_json, updated_at FROM video_cache")?;
+        let rows = stmt.query_map([], |row| {
+            let video_id: String = row.get(0)?;
+            let payload_json: String = row.get(1)?;
+            let updated_at: i64 = row.get(2)?;
+            Ok((video_id, payload_json, updated_at))
+        })?;
+
+        let mut migrated = 0;
+        for row in rows {
+            let (video_id, payload_json, updated_at) = row?;
+            let updated_iso = epoch_seconds_to_iso(updated_at);
+            let parsed = serde_json::from_str::<VideoItem>(&payload_json).ok();
+
+            let (url, title, thumb, views, duration, uploader, network) =
+                if let Some(video) = parsed {
+                    (
+                        video.page_url,
+                        video.title,
+                        video.image_url,
+                        video.view_count.and_then(|count| i64::try_from(count).ok()),
+                        video.duration_seconds.map(i64::from),
+                        video.author_name,
+                        video.network,
+                    )
+                } else {
+                    (
+                        fallback_url(&video_id),
+                        video_id.clone(),
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                    )
+                };
+
+            conn.execute(
+                r#"
+                INSERT INTO "video_details" (
+                    "id", "url", "title", "thumb", "dateAdded", "views", "duration",
+                    "uploader", "network", "lastUpdated", "rawData", "cacheDate"
+                )
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+                ON CONFLICT("id") DO UPDATE SET
+                    "url" = excluded."url",
+                    "title" = excluded."title",
+                    "thumb" = excluded."thumb",
+                    "views" = excluded."views",
+                    "duration" = excluded."duration",
+                    "uploader" = excluded."uploader",
+                    "network" = excluded."network",
+                    "lastUpdated" = excluded."lastUpdated",
+                    "rawData" = excluded."rawData",
+                    "cacheDate" = excluded."cacheDate"
+                "#,
+                params![
+                    video_id,
+                    url,
+                    title,
+                    thumb,
+                    updated_iso,
+                    views,
+                    duration,
+                    uploader,
+                    network,
+                    updated_iso,
+                    payload_json,
+                    updated_iso
+                ],
+            )?;
+            migrated += 1;
+        }
+        Ok(migrated)
+    }
+
+    fn migrate_legacy_favorites(conn: &Connection) -> Result<u64, EngineError> {
+        let mut stmt =
+            conn.prepare("SELECT video_id, title, image_url, network, added_at FROM favorites")?;
+        let rows = stmt.query_map([], |row| {
+            let video_id: String = row.get(0)?;
+            let title: String = row.get(1)?;
+            let image_url: Option<String> = row.get(2)?;
+            let network: Option<String> = row.get(3)?;
+            let added_at: i64 = row.get(4)?;
+            Ok((video_id, title, image_url, network, added_at))
+        })?;
+
+        let mut migrated = 0;
+        for row in rows {
+            let (video_id, title, image_url, network, added_at) = row?;
+            let favorite_date = epoch_seconds_to_iso(added_at);
+            let existing_url: Option<String> = conn
+                .query_row(
+                    r#"SELECT "url" FROM "video_details" WHERE "id" = ?1"#,
+                    params![video_id],
+                    |r| r.get(0),
+                )
+                .optional()?;
+
+            conn.execute(
+                r#"
+                INSERT INTO "video_details" (
+                    "id", "url", "title", "thumb", "network", "favoriteDate", "lastUpdated", "dateAdded"
+                )
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+                ON CONFLICT("id") DO UPDATE SET
+                    "title" = excluded."title",
+                    "thumb" = excluded."thumb",
+                    "network" = excluded."network",
+                    "favoriteDate" = excluded."favoriteDate",
+                    "lastUpdated" = excluded."lastUpdated"
+                "#,
+                params![
+                    video_id,
+                    existing_url.unwrap_or_else(|| fallback_url(&video_id)),
+                    title,
+                    image_url,
+                    network,
+                    favorite_date,
+                    favorite_date,
+                    favorite_date
+                ],
+            )?;
+            migrated += 1;
+        }
+        Ok(migrated)
+    }
+
+    fn migrate_legacy_resolved_cache(conn: &Connection) -> Result<u64, EngineError> {
+        let mut stmt =
+            conn.prepare("SELECT page_url, payload_json, updated_at FROM resolved_cache")?;
+        let rows = stmt.query_map([], |row| {
+            let page_url: String = row.get(0)?;
+            let payload_json: String = row.get(1)?;
+            let updated_at: i64 = row.get(2)?;
+            Ok((page_url, payload_json, updated_at))
+        })?;
+
+        let mut migrated = 0;
+        for row in rows {
+            let (page_url, payload_json, updated_at) = row?;
+            let updated_iso = epoch_seconds_to_iso(updated_at);
+            let parsed = serde_json::from_str::<ResolvedVideo>(&payload_json).ok();
+            let id = parsed
+                .as_ref()
+                .map(|video| video.id.trim())
+                .filter(|id| !id.is_empty())
+                .map(ToOwned::to_owned)
+                .unwrap_or_else(|| format_resolved_cache_id(&page_url));
+            let title = parsed
+                .as_ref()
+                .map(|video| video.title.trim())
+                .filter(|title| !title.is_empty())
+                .unwrap_or("Resolved Video");
+
+            let updated = conn.execute(
+                r#"
+                UPDATE "video_details"
+                SET
+                    "allFormats" = ?1,
+                    "cacheDate" = ?2,
+                    "lastUpdated" = ?3,
+                    "title" = COALESCE(NULLIF(?4, ''), "title")
+                WHERE "url" = ?5
+                "#,
+                params![payload_json, updated_iso, updated_iso, title, page_url],
+            )?;
+
+            if updated == 0 {
+                conn.execute(
+                    r#"
+                    INSERT INTO "video_details" (
+                        "id", "url", "title", "lastUpdated", "cacheDate", "allFormats", "rawData", "dateAdded"
+                    )
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+                    ON CONFLICT("id") DO UPDATE SET
+                        "url" = excluded."url",
+                        "title" = excluded."title",
+                        "lastUpdated" = excluded."lastUpdated",
+                        "cacheDate" = excluded."cacheDate",
+                        "allFormats" = excluded."allFormats",
+                        "rawData" = excluded."rawData"
+                    "#,
+                    params![
+                        id,
+                        page_url,
+                        title,
+                        updated_iso,
+                        updated_iso,
+                        payload_json,
+                        payload_json,
+                        updated_iso
+                    ],
+                )?;
+            }
+            migrated += 1;
+        }
+        Ok(migrated)
+    }
+}
+
+fn to_iso(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+fn epoch_seconds_to_iso(epoch_seconds: i64) -> String {
+    Utc.timestamp_opt(epoch_seconds, 0)
+        .single()
+        .map(|time| time.to_rfc3339_opts(SecondsFormat::Millis, true))
+        .unwrap_or_else(|| to_iso(Utc::now()))
+}
+
+fn parse_timestamp_to_epoch_seconds(value: &str) -> Option<i64> {
+    let trimmed = value.trim();
+    if trimmed.is_empty() {
+        return None;
+    }
+
+    if let Ok(raw_number) = trimmed.parse::<i64>() {
+        if raw_number > 10_000_000_000 {
+            return Some(raw_number / 1000);
+        }
+        return Some(raw_number);
+    }
+
+    if let Ok(parsed) = DateTime::parse_from_rfc3339(trimmed) {
+        return Some(parsed.timestamp());
+    }
+
+    const CANDIDATE_FORMATS: [&str; 3] = [
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y-%m-%dT%H:%M:%S",
+    ];
+
+    for format in CANDIDATE_FORMATS {
+        if let Ok(parsed) = NaiveDateTime::parse_from_str(trimmed, format) {
+            return Some(Utc.from_utc_datetime(&parsed).timestamp());
+        }
+    }
+
+    None
+}
+
+// Like `video_item_from_row`, but the stored payload wins when it is ours; template rows from
+// other clients only have columns.
+fn stored_video_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<VideoItem> {
+    let from_columns = video_item_from_row(row)?;
+    let raw_data: Option<String> = row.get(8)?;
+    Ok(raw_data
+        .and_then(|payload| serde_json::from_str::<VideoItem>(&payload).ok())
+        .filter(|video| video.id == from_columns.id && !video.page_url.is_empty())
+        .unwrap_or(from_columns))
+}
+
+fn video_item_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<VideoItem> {
+    let video_id: String = row.get(0)?;
+    let title: String = row.get(1)?;
+    let page_url: String = row.get(2)?;
+    let raw_data: Option<String> = row.get(8)?;
+    let parsed_video = raw_data
+        .as_deref()
+        .and_then(|payload| serde_json::from_str::<VideoItem>(payload).ok());
+    let extractor = parsed_video
+        .as_ref()
+        .and_then(|video| video.extractor.clone());
+    let raw_json = parsed_video
+        .as_ref()
+        .and_then(|video| video.raw_json.clone());
+
+    Ok(VideoItem {
+        id: video_id.clone(),
+        title: if title.trim().is_empty() {
+            video_id.clone()
+        } else {
+            title
+        },
+        page_url: non_empty_str(&page_url)
+            .map(ToOwned::to_owned)
+            .unwrap_or_else(|| fallback_url(&video_id)),
+        duration_seconds: row
+            .get::<_, Option<i64>>(3)?
+            .and_then(|seconds| u32::try_from(seconds).ok()),
+        image_url: row.get(4)?,
+        network: row.get(5)?,
+        author_name: row.get(6)?,
+        extractor,
+        view_count: row
+            .get::<_, Option<i64>>(7)?
+            .and_then(|views| u64::try_from(views).ok()),
+        raw_json,
+        aspect_ratio: row.get(9)?,
+    })
+}
+
+// Opening a staged copy (to validate it) can leave WAL sidecars next to it.
+fn remove_staged(path: &Path) {
+    let _ = fs::remove_file(path);
+    for suffix in ["-wal", "-shm"] {
+        let mut sidecar = path.as_os_str().to_owned();
+        sidecar.push(suffix);
+        let _ = fs::remove_file(PathBuf::from(sidecar));
+    }
+}
+
+fn merge_resolved_into_item(video: &mut VideoItem, resolved: &ResolvedVideo) -> bool {
+    fn fill<T: Clone>(slot: &mut Option<T>, value: &Option<T>) -> bool {
+        if slot.is_some() || value.is_none() {
+            return false;
+        }
+        slot.clone_from(value);
+        true
+    }
+
+    let mut changed = false;
+    let untitled = video.title.trim().is_empty() || video.title == video.id;
+    if untitled && !resolved.title.trim().is_empty() && resolved.title != "Untitled" {
+        video.title = resolved.title.clone();
+        changed = true;
+    }
+    changed |= fill(&mut video.image_url, &resolved.thumbnail_url);
+    changed |= fill(&mut video.author_name, &resolved.author_name);
+    changed |= fill(&mut video.extractor, &resolved.extractor);
+    changed |= fill(&mut video.view_count, &resolved.view_count);
+    changed |= fill(&mut video.duration_seconds, &resolved.duration_seconds);
+    changed
+}
+
+fn fresh_resolved_payload(
+    payload: &str,
+    cache_date: &str,
+    max_age_seconds: i64,
+    now: i64,
+) -> Option<ResolvedVideo> {
+    if !is_fresh(cache_date, max_age_seconds, now) {
+        return None;
+    }
+    serde_json::from_str::<ResolvedVideo>(payload).ok()
+}
+
+fn is_fresh(cache_date: &str, max_age_seconds: i64, now: i64) -> bool {
+    parse_timestamp_to_epoch_seconds(cache_date)
+        .is_some_and(|updated_at_epoch| now - updated_at_epoch <= max_age_seconds)
+}
+
+fn latest_timestamp(values: &[Option<&str>]) -> Option<i64> {
+    values
+        .iter()
+        .flatten()
+        .filter_map(|value| parse_timestamp_to_epoch_seconds(value))
+        .max()
+}
+
+fn fallback_url(video_id: &str) -> String {
+    format!("local://video/{video_id}")
+}
+
+fn format_resolved_cache_id(page_url: &str) -> String {
+    format!("resolved:{page_url}")
+}
+
+// Tags are trimmed, lowercased and deduplicated, keeping first-seen order.
+fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim().to_lowercase();
+        if !tag.is_empty() && !out.contains(&tag) {
+            out.push(tag);
+        }
+    }
+    out
+}
+
+fn is_plaintext_sqlite(path: &Path) -> bool {
+    let mut header = [0u8; 16];
+    fs::File::open(path)
+        .and_then(|mut file| std::io::Read::read_exact(&mut file, &mut header))
+        .is_ok()
+        && &header == b"SQLite format 3\0"
+}
+
+fn non_empty_str(value: &str) -> Option<&str> {
+    let trimmed = value.trim();
+    if trimmed.is_empty() {
+        None
+    } else {
+        Some(trimmed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::FakeClock;
+    use rusqlite::Connection;
+    use std::collections::HashMap;
+    use tempfile::tempdir;
+
+    fn sample_video(id: &str) -> VideoItem {
+        VideoItem {
+            id: id.to_string(),
+            title: "Sample".to_string(),
+            page_url: "https://example.com/v/1".to_string(),
+            duration_seconds: Some(120),
+            image_url: Some("https://example.com/image.jpg".to_string()),
+            network: Some("youtube".to_string()),
+            author_name: Some("author".to_string()),
+            extractor: Some("youtube".to_string()),
+            view_count: Some(42),
+            raw_json: Some(format!("{{\"id\":\"{id}\"}}")),
+            aspect_ratio: None,
+        }
+    }
+
+    #[test]
+    fn favorites_roundtrip() {
+        let tmp = tempdir().expect("tmpdir");
+        let db = Database::new(tmp.path().join("db.sqlite"));
+        db.init().expect("db init");
+
+        let favorite = db
+            .add_favorite(&sample_video("video-1"))
+            .expect("add favorite");
+        assert_eq!(favorite.video_id, "video-1");
+
+        let favorites = db.list_favorites().expect("list favorites");
+        assert_eq!(favorites.len(), 1);
+        assert_eq!(favorites[0].video_id, "video-1");
+
+        let favorite_videos = db.list_favorite_videos().expect("list favorite videos");
+        assert_eq!(favorite_videos.len(), 1);
+        assert_eq!(favorite_videos[0].id, "video-1");
+        assert_eq!(favorite_videos[0].page_url, "https://example.com/v/1");
+
+        let removed = db.remove_favorite("video-1").expect("remove favorite");
+        assert!(removed);
+        assert!(db.list_favorites().expect("list favorites").is_empty());
+        assert!(db.list_favorite_videos().expect("list favorite videos").is_empty());
+    }
+
+    #[test]
+    fn empty_page_url_does_not_clobber_existing_url() {
+        let tmp = tempdir().expect("tmpdir");
+        let db = Database::new(tmp.path().join("url-guard.sqlite"));
+        db.init().expect("db init");
+
+        db.add_favorite(&sample_video("video-url"))
+            .expect("add favorite");
+
+        let mut without_url = sample_video("video-url");
+        without_url.page_url = String::new();
+        db.cache_videos(&[without_url.clone()])
+            .expect("recache without url");
+        db.add_favorite(&without_url)
+            .expect("refavorite without url");
+
+        let favorite_videos = db.list_favorite_videos().expect("list favorite videos");
+        assert_eq!(favorite_videos.len(), 1);
+        assert_eq!(favorite_videos[0].page_url, "https://example.com/v/1");
+    }
+
+    #[test]
+    fn resolved_cache_write_prunes_stale_duplicates() {
+        let tmp = tempdir().expect("tmpdir");
+        let db = Database::new(tmp.path().join("resolved.sqlite"));
+        db.init().expect("db init");
+
+        let page_url = "https://example.com/v/dup";
+        {
+            let conn = db.conn().expect("conn");
+            for (id, cache_date) in [
+                ("resolved-old", "2024-01-01T00:00:00.000Z"),
+                ("resolved-new", "2024-06-01T00:00:00.000Z"),
+            ] {
+                conn.execute(
+                    r#"
+                    INSERT INTO "video_details" ("id", "url", "title", "allFormats", "cacheDate")
+                    VALUES (?1, ?2, 'Dup', '{}', ?3)
+                    "#,
+                    params![id, page_url, cache_date],
+                )
+                .expect("seed resolved row");
+            }
+        }
+
+        db.cache_resolved_video(
+            page_url,
+            &ResolvedVideo {
+                id: "resolved-new".to_string(),
+                title: "Dup".to_string(),
+                page_url: page_url.to_string(),
+                stream_url: "https://cdn.example.com/dup.mp4".to_string(),
+                thumbnail_url: None,
+                author_name: None,
+                extractor: None,
+                duration_seconds: None,
+                view_count: None,
+                upload_date_epoch: None,
+                description: None,
+                stream_protocol: None,
+                thumbnails: Vec::new(),
+            },
+        )
+        .expect("cache resolved");
+
+        let conn = db.conn().expect("conn");
+        let ids = conn
+            .prepare(r#"SELECT "id" FROM "video_details" WHERE "url" = ?1"#)
+            .expect("prepare ids")
+            .query_map(params![page_url], |row| row.get::<_, String>(0))
+            .expect("query ids")
+            .collect::<Result<Vec<_>, _>>()
+            .expect("collect ids");
+        assert_eq!(ids.len(), 1);
+        drop(conn);
+
+        let cached = db
+            .get_cached_resolved_video(page_url, 60)
+            .expect("get cached")
+            .expect("cached hit");
+        assert_eq!(cached.stream_url, "https://cdn.example.com/dup.mp4");
+    }
+
+    #[test]
+    fn list_networks_counts_totals_and_favorites() {
+        let tmp = tempdir().expect("tmpdir");
+        let db = Database::new(tmp.path().join("networks.sqlite"));
+        db.init().expect("db init");
+
+        let mut videos = Vec::new();
+        for (id, network) in [
+            ("a-1", "alpha"),
+            ("a-2", "alpha"),
+            ("a-3", "alpha"),
+            ("b-1", "beta"),
+            ("b-2", "beta"),
+        ] {
+            let mut video = sample_video(id);
+            video.network = Some(network.to_string());
+            videos.push(video);
+        }
+        let mut unnamed = sample_video("no-network");
+        unnamed.network = Some("  ".to_string());
+        videos.push(unnamed);
+        db.cache_videos(&videos).expect("cache videos");
+        db.add_favorite(&videos[0]).expect("favorite alpha");
+        db.add_favorite(&videos[3]).expect("favorite beta");
+        db.add_favorite(&videos[4]).expect("favorite beta");
+
+        let networks = db.list_networks().expect("list networks");
+        assert_eq!(networks.len(), 2);
+        assert_eq!(networks[0].network, "alpha");
+        assert_eq!(networks[0].total, 3);
+        assert_eq!(networks[0].favorites, 1);
+        assert_eq!(networks[1].network, "beta");
+        assert_eq!(networks[1].total, 2);
+        assert_eq!(networks[1].favorites, 2);
+    }
+
+    #[test]
+    fn raw_video_roundtrips_cached_payload() {
+        let tmp = tempdir().expect("tmpdir");
+        let db = Database::new(tmp.path().join("raw.sqlite"));
+        db.init().expect("db init");
+
+        let video = sample_video("video-raw");
+        db.cache_videos(std::slice::from_ref(&video))
+            .expect("cache videos");
+
+        let raw = db
+            .get_raw_video("video-raw")
+            .expect("get raw video")
+            .expect("raw payload present");
+        let parsed: VideoItem = serde_json::from_str(&raw).expect("raw payload is a VideoItem");
+        assert_eq!(
+            serde_json::to_value(&parsed).expect("parsed value"),
+            serde_json::to_value(&video).expect("original value")
+        );
+        assert_eq!(db.get_raw_video("missing").expect("get missing"), None);
+    }
+
+    #[test]
+    fn concurrent_reads_and_writes_share_connection() {
+        let tmp = tempdir().expect("tmpdir");
+        let db = Database::new(tmp.path().join("concurrent.sqlite"));
+        db.init().expect("db init");
+
+        let handles: Vec<_> = (0..8)
+            .map(|worker| {
+                let db = db.clone();
+                std::thread::spawn(move || {
+                    for round in 0..10 {
+                        let mut video = sample_video(&format!("video-{worker}-{round}"));
+                        video.page_url = format!("https://example.com/v/{worker}/{round}");
+                        db.cache_videos(std::slice::from_ref(&video))
+                            .expect("cache videos");
+                        db.add_favorite(&video).expect("add favorite");
+                        db.set_meta(&format!("worker.{worker}"), &round.to_string())
+                            .expect("set meta");
+                        db.list_favorites().expect("list favorites");
+                    }
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.join().expect("worker thread");
+        }
+
+        assert_eq!(db.list_favorites().expect("list favorites").len(), 80);
+        assert_eq!(
+            db.list_meta_with_prefix("worker.")
+                .expect("list meta")
+                .len(),
+            8
+        );
+    }
+
+    #[test]
+    fn clears_favorites_for_one_network_only() {
+        let tmp = tempdir().expect("tmpdir");
+        let db = Database::new(tmp.path().join("networks.sqlite"));
+        db.init().expect("db init");
+        for (id, network) in [
+            ("cat-1", "Catflix"),
+            ("cat-2", "catflix"),
+            ("dog-1", "dogflix"),
+        ] {
+            let mut video = sample_video(id);
+            video.page_url = format!("https://example.com/v/{id}");
+            video.network = Some(network.to_string());
+            db.add_favorite(&video).expect("add favorite");
+        }
+
+        assert!(matches!(
+            db.clear_favorites_for_network("  "),
+            Err(EngineError::InvalidConfig { .. })
+        ));
+        assert_eq!(
+            db.clear_favorites_for_network("CATFLIX")
+                .expect("clear catflix"),
+            2
+        );
+
+        let remaining: Vec<String> = db
+            .list_favorites()
+            .expect("list favorites")
+            .into_iter()
+            .map(|favorite| favorite.video_id)
+            .collect();
+        assert_eq!(remaining, ["dog-1"]);
+        assert_eq!(
+            db.clear_favorites_for_network("catflix")
+                .expect("clear again"),
+            0
+        );
+    }
+
+    #[test]
+    fn enrich_favorite_merges_cached_resolve_into_lean_item() {
+        let tmp = tempdir().expect("tmpdir");
+        let db = Database::new(tmp.path().join("enrich.sqlite"));
+        db.init().expect("db init");
+        let lean = VideoItem {
+            id: "lean".to_string(),
+            title: "Lean clip".to_string(),
+            page_url: "https://example.com/v/lean".to_string(),
+            duration_seconds: None,
+            image_url: None,
+            network: Some("catflix".to_string()),
+            author_name: None,
+            extractor: None,
+            view_count: None,
+            raw_json: None,
+            aspect_ratio: None,
+        };
+        db.add_favorite(&lean).expect("add favorite");
+        assert!(!db.enrich_favorite("lean").expect("nothing cached yet"));
+
+        db.cache_resolved_video(
+            &lean.page_url,
+            &ResolvedVideo {
+                id: "lean".to_string(),
+                title: "Resolved title".to_string(),
+                page_url: lean.page_url.clone(),
+                stream_url: "https://cdn.example.com/lean.mp4".to_string(),
+                thumbnail_url: Some("https://img.example.com/lean.jpg".to_string()),
+                author_name: Some("uploader".to_string()),
+                extractor: Some("generic".to_string()),
+                duration_seconds: Some(95),
+                view_count: Some(1200),
+                upload_date_epoch: None,
+                description: None,
+                stream_protocol: None,
+                thumbnails: Vec::new(),
+            },
+        )
+        .expect("cache resolved");
+
+        assert!(db.enrich_favorite("lean").expect("enrich"));
+        let stored = db.get_video("lean").expect("get video").expect("stored");
+        assert_eq!(stored.title, "Lean clip");
+        assert_eq!(stored.extractor.as_deref(), Some("generic"));
+        assert_eq!(stored.view_count, Some(1200));
+        assert_eq!(stored.duration_seconds, Some(95));
+        assert_eq!(stored.author_name.as_deref(), Some("uploader"));
+        assert_eq!(
+            stored.image_url.as_deref(),
+            Some("https://img.example.com/lean.jpg")
+        );
+        assert_eq!(stored.network.as_deref(), Some("catflix"));
+        let favorites = db.list_favorites().expect("list favorites");
+        assert_eq!(
+            favorites[0].image_url.as_deref(),
+            Some("https://img.example.com/lean.jpg")
+        );
+
+        assert!(!db.enrich_favorite("lean").expect("already enriched"));
+        assert!(!db.enrich_favorite("missing").expect("unknown id"));
+    }
+
+    #[test]
+    fn backfills_missing_favorite_fields_from_raw_data() {
+        let tmp = tempdir().expect("tmpdir");
+        let db = Database::new(tmp.path().join("backfill.sqlite"));
+        db.init().expect("db init");
+        for id in ["with-raw", "bare"] {
+            let mut video = sample_video(id);
+            video.page_url = format!("https://example.com/v/{id}");
+            db.add_favorite(&video).expect("add favorite");
+        }
+        {
+            let conn = db.conn().expect("conn");
+            conn.execute(
+                r#"UPDATE "video_details" SET "thumb" = NULL, "network" = '  '"#,
+                [],
+            )
+            .expect("strip metadata");
+            conn.execute(
+                r#"UPDATE "video_details" SET "rawData" = NULL WHERE "id" = 'bare'"#,
+                [],
+            )
+            .expect("drop raw data");
+        }
+
+        assert_eq!(db.backfill_favorite_metadata().expect("backfill"), 1);
+        let favorites = db.list_favorites().expect("list favorites");
+        let backfilled = favorites
+            .iter()
+            .find(|favorite| favorite.video_id == "with-raw")
+            .expect("backfilled favorite");
+        assert_eq!(
+            backfilled.image_url.as_deref(),
+            Some("https://example.com/image.jpg")
+        );
+        assert_eq!(backfilled.network.as_deref(), Some("youtube"));
+        assert_eq!(backfilled.title, "Sample");
+        let bare = favorites
+            .iter()
+            .find(|favorite| favorite.video_id == "bare")
+            .expect("bare favorite");
+        assert_eq!(bare.image_url, None);
+
+        assert_eq!(db.backfill_favorite_metadata().expect("second pass"), 0);
+    }
+
+    #[test]
+    fn list_all_meta_returns_every_key_sorted() {
+        let tmp = tempdir().expect("tmpdir");
+        let db = Database::new(tmp.path().join("meta.sqlite"));
+        db.init().expect("db init");
+        for (key, value) in [
+            ("theme.mode", "dark"),
+            ("achievement.first_watch", "1"),
+            ("player.speed", "1.5"),
+            ("active_channel", "catflix"),
+        ] {
+            db.set_meta(key, value).expect("set meta");
+        }
+
+        let keys: Vec<String> = db
+            .list_all_meta()
+            .expect("list all meta")
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect();
+
+        assert_eq!(
+            keys,
+            [
+                "achievement.first_watch",
+                "active_channel",
+                "player.speed",
+                "theme.mode"
+            ]
+        );
+    }
+
+    #[test]
+    fn encrypted_database_rejects_wrong_key() {
+        let tmp = tempdir().expect("tmpdir");
+        let path = tmp.path().join("encrypted.sqlite");
+        let db = Database::new(&path).with_encryption_key(Some("correct horse".to_string()));
+        db.init().expect("db init");
+        db.add_favorite(&sample_video("secret"))
+            .expect("add favorite");
+        drop(db);
+
+        assert!(!is_plaintext_sqlite(&path));
+        let reopened = Database::new(&path).with_encryption_key(Some("correct horse".to_string()));
+        assert_eq!(reopened.list_favorites().expect("list favorites").len(), 1);
+
+        let wrong = Database::new(&path).with_encryption_key(Some("battery staple".to_string()));
+        let err = wrong.list_favorites().expect_err("wrong key");
+        assert!(
+            matches!(err, EngineError::Database { ref detail } if detail.contains("unlock encrypted database"))
+        );
+        assert!(Database::new(&path).list_favorites().is_err());
+    }
+
+    #[test]
+    fn plaintext_import_is_encrypted_with_engine_key() {
+        let tmp = tempdir().expect("tmpdir");
+        let plain = Database::new(tmp.path().join("plain.sqlite"));
+        plain.init().expect("plain init");
+        plain
+            .add_favorite(&sample_video("carried"))
+            .expect("add favorite");
+        let bytes = plain.export_bytes().expect("export plaintext");
+
+        let key = Some("engine key".to_string());
+        let path = tmp.path().join("encrypted.sqlite");
+        let db = Database::new(&path).with_encryption_key(key.clone());
+        db.init().expect("db init");
+        db.import_bytes(&bytes, false)
+            .expect("import plaintext bytes");
+
+        assert!(!is_plaintext_sqlite(&path));
+        assert_eq!(db.list_favorites().expect("list favorites").len(), 1);
+
+        // Exports stay encrypted and round-trip through the same key.
+        let exported = db.export_bytes().expect("export encrypted");
+        assert_ne!(&exported[..16], b"SQLite format 3\0");
+        let copy = Database::new(tmp.path().join("copy.sqlite")).with_encryption_key(key);
+        copy.init().expect("copy init");
+        copy.import_bytes(&exported, false)
+            .expect("import encrypted bytes");
+        assert_eq!(copy.list_favorites().expect("copy favorites").len(), 1);
+
+        let file_import = tmp.path().join("file-import.sqlite");
+        let from_file = Database::new(&file_import).with_encryption_key(Some("other".to_string()));
+        from_file
+            .import_from(&plain.path().to_string_lossy(), true, false)
+            .expect("import plaintext file");
+        assert!(!is_plaintext_sqlite(&file_import));
+        assert_eq!(from_file.list_favorites().expect("file favorites").len(), 1);
+    }
+
+    #[test]
+    fn flush_checkpoints_and_releases_the_wal() {
+        let tmp = tempdir().expect("tmpdir");
+        let path = tmp.path().join("flush.sqlite");
+        let db = Database::new(&path);
+        db.init().expect("db init");
+        db.add_favorite(&sample_video("kept"))
+            .expect("add favorite");
+        let mut wal = path.clone().into_os_string();
+        wal.push("-wal");
+        let wal = PathBuf::from(wal);
+        assert!(wal.exists());
+
+        db.flush().expect("flush");
+
+        assert!(!wal.exists() || fs::metadata(&wal).expect("wal metadata").len() == 0);
+        let reopened = Connection::open(&path).expect("open flushed db");
+        let favorites: i64 = reopened
+            .query_row(
+                r#"SELECT COUNT(*) FROM "video_details" WHERE "favoriteDate" IS NOT NULL"#,
+                [],
+                |row| row.get(0),
+            )
+            .expect("count favorites");
+        assert_eq!(favorites, 1);
+        drop(reopened);
+        assert_eq!(db.list_favorites().expect("reopen after flush").len(), 1);
+    }
+
+    #[test]
+    fn busy_timeout_waits_for_other_connection_writes() {
+        let tmp = tempdir().expect("tmpdir");
+        let path = tmp.path().join("busy.sqlite");
+        let db = Database::new(&path).with_busy_timeout(Duration::from_secs(5));
+        db.init().expect("db init");
+
+        let mut blocker = Connection::open(&path).expect("open blocker");
+        let (locked_tx, locked_rx) = std::sync::mpsc::channel();
+        let holder = std::thread::spawn(move || {
+            let tx = blocker
+                .transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)
+                .expect("begin immediate");
+            tx.execute(
+                r#"INSERT INTO "video_details" ("id", "url", "title") VALUES ('held', 'https://example.com/held', 'Held')"#,
+                [],
+            )
+            .expect("insert held row");
+            locked_tx.send(()).expect("signal lock");
+            std::thread::sleep(Duration::from_millis(300));
+            tx.commit().expect("commit held row");
+        });
+        locked_rx.recv().expect("wait for lock");
+
+        let writers: Vec<_> = (0..4)
+            .map(|worker| {
+                let db = Database::new(&path).with_busy_timeout(Duration::from_secs(5));
+                std::thread::spawn(move || {
+                    for round in 0..5 {
+                        let mut video = sample_video(&format!("busy-{worker}-{round}"));
+                        video.page_url = format!("https://example.com/busy/{worker}/{round}");
+                        db.cache_videos(std::slice::from_ref(&video))
+                            .expect("cache videos without lock error");
+                        db.list_favorites().expect("read without lock error");
+                    }
+                })
+            })
+            .collect();
+        db.set_meta("busy", "1")
+            .expect("set meta without lock error");
+
+        holder.join().expect("lock holder");
+        for writer in writers {
+            writer.join().expect("writer thread");
+        }
+        assert_eq!(db.get_meta("busy").expect("get meta").as_deref(), Some("1"));
+    }
+
+    #[test]
+    fn template_schema_tables_exist() {
+        let tmp = tempdir().expect("tmpdir");
+        let db = Database::new(tmp.path().join("schema.sqlite"));
+        db.init().expect("db init");
+
+        let conn = Connection::open(db.path()).expect("open db");
+        let mut stmt = conn
+            .prepare("SELECT name FROM sqlite_master WHERE type = 'table' ORDER BY name")
+            .expect("prepare list tables");
+        let names = stmt
+            .query_map([], |row| row.get::<_, String>(0))
+            .expect("query tables")
+            .collect::<Result<Vec<_>, _>>()
+            .expect("collect tables");
+
+        assert!(names.iter().any(|name| name == "video_details"));
+        assert!(names.iter().any(|name| name == "searches"));
+        assert!(names.iter().any(|name| name == "categories"));
+        assert!(names.iter().any(|name| name == "user_preferences"));
+        assert!(names.iter().any(|name| name == "server_preferences"));
+    }
+
+    #[test]
+    fn searches_and_category_clicks_roundtrip() {
+        let tmp = tempdir().expect("tmpdir");
+        let db = Database::new(tmp.path().join("search.sqlite"));
+        db.init().expect("db init");
+
+        db.sync_categories(&["Amateur".to_string(), "Professional".to_string()])
+            .expect("sync categories");
+        db.record_search("Amateur").expect("record first search");
+        db.record_search("Amateur").expect("record second search");
+
+        let conn = Connection::open(db.path()).expect("open db");
+        let frequency: i64 = conn
+            .query_row(
+                r#"SELECT "frequency" FROM "searches" WHERE "query" = 'Amateur'"#,
+                [],
+                |row| row.get(0),
+            )
+            .expect("query search frequency");
+        assert_eq!(frequency, 2);
+
+        let clicks: i64 = conn
+            .query_row(
+                r#"SELECT "clicks" FROM "categories" WHERE "id" = 'Amateur'"#,
+                [],
+                |row| row.get(0),
+            )
+            .expect("query category clicks");
+        assert_eq!(clicks, 2);
+    }
+
+    #[test]
+    fn seed_data_keeps_existing_frequencies() {
+        let tmp = tempdir().expect("tmpdir");
+        let db = Database::new(tmp.path().join("seed.sqlite"));
+        db.init().expect("db init");
+
+        db.record_search("cats").expect("record search");
+        db.record_search("cats").expect("record search again");
+        db.record_search("cats").expect("record search third");
+        db.seed_data(
+            &["Amateur".to_string(), " ".to_string()],
+            &["cats".to_string(), " dogs ".to_string(), String::new()],
+        )
+        .expect("seed data");
+        db.seed_data(&["Amateur".to_string()], &["dogs".to_string()])
+            .expect("seed again");
+
+        let conn = Connection::open(db.path()).expect("open db");
+        let searches = conn
+            .prepare(r#"SELECT "query", "frequency" FROM "searches" ORDER BY "query""#)
+            .expect("prepare")
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+            })
+            .expect("query searches")
+            .collect::<Result<Vec<_>, _>>()
+            .expect("collect searches");
+        assert_eq!(searches, [("cats".to_string(), 3), ("dogs".to_string(), 1)]);
+
+        let categories = conn
+            .prepare(r#"SELECT "id", "clicks" FROM "categories""#)
+            .expect("prepare")
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+            })
+            .expect("query categories")
+            .collect::<Result<Vec<_>, _>>()
+            .expect("collect categories");
+        assert_eq!(categories, [("Amateur".to_string(), 0)]);
+    }
+
+    #[test]
+    fn import_template_schema_keeps_favorites_compatible() {
+        let tmp = tempdir().expect("tmpdir");
+        let template_path = tmp.path().join("template.sqlite");
+        let conn = Connection::open(&template_path).expect("create template db");
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS "user_preferences" ("id" TEXT PRIMARY KEY NOT NULL, "preferenceValue" TEXT);
+            CREATE TABLE IF NOT EXISTS "server_preferences" ("id" TEXT PRIMARY KEY NOT NULL, "preferenceValue" TEXT);
+            CREATE TABLE IF NOT EXISTS "video_details" ("id" TEXT PRIMARY KEY NOT NULL, "url" TEXT NOT NULL, "title" TEXT, "thumb" TEXT, "preview" TEXT, "dateAdded" TEXT, "views" INTEGER DEFAULT (0), "duration" INTEGER DEFAULT (0), "uploader" TEXT, "uploaderUrl" TEXT, "tags" TEXT, "lastUpdated" TEXT, "flags" TEXT, "favoriteDate" TEXT, "lastWatchDate" TEXT, "uploadedAt" TEXT, "rating" REAL, "userViews" INTEGER, "network" TEXT, "aspectRatio" REAL, "allFormats" TEXT, "session" TEXT, "rawData" TEXT, "cacheDate" TEXT, "adData" TEXT);
+            CREATE TABLE IF NOT EXISTS "categories" ("id" TEXT PRIMARY KEY NOT NULL, "name" TEXT NOT NULL, "clicks" INTEGER NOT NULL DEFAULT (0));
+            CREATE TABLE IF NOT EXISTS "searches" ("query" TEXT PRIMARY KEY NOT NULL, "timestamp" TEXT NOT NULL, "frequency" INTEGER NOT NULL DEFAULT (1));
+            "#,
+        )
+        .expect("create template tables");
+        conn.execute(
+            r#"
+            INSERT INTO "video_details" ("id", "url", "title", "thumb", "network", "favoriteDate")
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            "#,
+            params![
+                "video-42",
+                "https://example.com/v/42",
+                "Template favorite",
+                "https://example.com/42.jpg",
+                "example-network",
+                "2025-11-30T20:40:00Z"
+            ],
+        )
+        .expect("seed template favorite");
+
+        let imported = Database::new(tmp.path().join("imported.sqlite"));
+        imported.init().expect("import target init");
+        imported
+            .import_from(
+                template_path.to_str().expect("template path utf8"),
+                true,
+                false,
+            )
+            .expect("import template");
+
+        let favorites = imported.list_favorites().expect("list favorites");
+        assert_eq!(favorites.len(), 1);
+        assert_eq!(favorites[0].video_id, "video-42");
+    }
+
+    #[test]
+    fn legacy_favorites_migration_reports_stats() {
+        let tmp = tempdir().expect("tmpdir");
+        let path = tmp.path().join("legacy.sqlite");
+        let conn = Connection::open(&path).expect("create legacy db");
+        conn.execute_batch(
+            r#"
+            CREATE TABLE favorites (
+                video_id TEXT PRIMARY KEY,
+                title TEXT NOT NULL,
+                image_url TEXT,
+                network TEXT,
+                added_at INTEGER NOT NULL
+            );
+            INSERT INTO favorites VALUES ('legacy-1', 'Legacy One', NULL, 'catflix', 1700000000);
+            INSERT INTO favorites VALUES ('legacy-2', 'Legacy Two', NULL, 'catflix', 1700000100);
+            "#,
+        )
+        .expect("seed legacy favorites");
+        drop(conn);
+
+        let db = Database::new(&path);
+        assert_eq!(
+            db.last_migration_stats().ok().flatten().map(|s| s.total()),
+            None
+        );
+        let stats = db.init().expect("db init");
+        assert_eq!(stats.favorites, 2);
+        assert_eq!(stats.meta, 0);
+        assert_eq!(stats.videos, 0);
+        assert_eq!(stats.resolved, 0);
+
+        let stored = db
+            .last_migration_stats()
+            .expect("read stats")
+            .expect("stats recorded");
+        assert_eq!(stored.favorites, 2);
+        assert_eq!(db.list_favorites().expect("list favorites").len(), 2);
+    }
+
+    #[test]
+    fn ensure_migrated_sweeps_legacy_tables_in_a_stamped_database() {
+        let tmp = tempdir().expect("tmpdir");
+        let path = tmp.path().join("stamped.sqlite");
+        let db = Database::new(&path);
+        db.init().expect("db init");
+        {
+            let conn = db.conn().expect("conn");
+            conn.execute_batch(
+                r#"
+                CREATE TABLE favorites (
+                    video_id TEXT PRIMARY KEY,
+                    title TEXT NOT NULL,
+                    image_url TEXT,
+                    network TEXT,
+                    added_at INTEGER NOT NULL
+                );
+                INSERT INTO favorites VALUES ('legacy-1', 'Legacy One', NULL, 'catflix', 1700000000);
+                "#,
+            )
+            .expect("seed leftover legacy table");
+        }
+        assert_eq!(db.init().expect("init skips stamped file").total(), 0);
+        assert!(db.list_favorites().expect("list before").is_empty());
+
+        let stats = db.ensure_migrated().expect("ensure migrated");
+        assert_eq!(stats.favorites, 1);
+        let favorites = db.list_favorites().expect("list after");
+        assert_eq!(favorites.len(), 1);
+        assert_eq!(favorites[0].video_id, "legacy-1");
+        assert_eq!(db.ensure_migrated().expect("repeat").total(), 0);
+    }
+
+    #[test]
+    fn legacy_tables_are_dropped_after_migration() {
+        let tmp = tempdir().expect("tmpdir");
+        let path = tmp.path().join("legacy-drop.sqlite");
+        let conn = Connection::open(&path).expect("create legacy db");
+        conn.execute_batch(
+            r#"
+            CREATE TABLE favorites (
+                video_id TEXT PRIMARY KEY,
+                title TEXT NOT NULL,
+                image_url TEXT,
+                network TEXT,
+                added_at INTEGER NOT NULL
+            );
+            CREATE TABLE engine_meta (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+            INSERT INTO favorites VALUES ('legacy-1', 'Legacy One', NULL, 'catflix', 1700000000);
+            INSERT INTO engine_meta VALUES ('settings.theme', 'dark');
+            "#,
+        )
+        .expect("seed legacy tables");
+        drop(conn);
+
+        let db = Database::new(&path);
+        db.init().expect("db init");
+        {
+            let conn = db.conn().expect("conn");
+            assert!(!Database::table_exists(&conn, "favorites").expect("favorites exists"));
+            assert!(!Database::table_exists(&conn, "engine_meta").expect("engine_meta exists"));
+        }
+        assert_eq!(db.list_favorites().expect("list favorites").len(), 1);
+        assert_eq!(
+            db.get_meta("settings.theme").expect("get meta").as_deref(),
+            Some("dark")
+        );
+
+        let rerun = db.init().expect("second init");
+        assert_eq!(rerun.total(), 0);
+        assert_eq!(
+            db.last_migration_stats()
+                .expect("read stats")
+                .expect("stats recorded")
+                .favorites,
+            1
+        );
+    }
+
+    #[test]
+    fn export_and_import_roundtrip() {
+        let tmp = tempdir().expect("tmpdir");
+        let src = Database::new(tmp.path().join("src.sqlite"));
+        src.init().expect("src init");
+        src.add_favorite(&sample_video("video-2"))
+            .expect("add favorite");
+
+        let export_path = tmp.path().join("exports/backup.sqlite");
+        src.export_to(export_path.to_str().expect("export path utf8"))
+            .expect("export");
+
+        let imported = Database::new(tmp.path().join("dst.sqlite"));
+        imported.init().expect("dst init");
+        imported
+            .import_from(export_path.to_str().expect("export path utf8"), true, false)
+            .expect("import");
+
+        let favorites = imported.list_favorites().expect("list favorites");
+        assert_eq!(favorites.len(), 1);
+        assert_eq!(favorites[0].video_id, "video-2");
+    }
+
+    #[test]
+    fn import_rejects_non_sqlite_file_without_touching_live_db() {
+        let tmp = tempdir().expect("tmpdir");
+        let db = Database::new(tmp.path().join("live.sqlite"));
+        db.init().expect("db init");
+        db.add_favorite(&sample_video("video-keep"))
+            .expect("add favorite");
+
+        let bogus_path = tmp.path().join("bogus.sqlite");
+        fs::write(
+            &bogus_path,
+            b"this is definitely not a sqlite database, just text",
+        )
+        .expect("write bogus file");
+        let bogus = bogus_path.to_str().expect("bogus path utf8");
+
+        let report = db.validate_import(bogus).expect("validate import");
+        assert!(!report.valid);
+        assert!(!report.integrity_ok);
+        assert!(!report.issues.is_empty());
+
+        let err = db
+            .import_from(bogus, true, true)
+            .expect_err("import should fail");
+        assert!(matches!(err, EngineError::Database { .. }));
+
+        let favorites = db.list_favorites().expect("list favorites");
+        assert_eq!(favorites.len(), 1);
+        assert_eq!(favorites[0].video_id, "video-keep");
+    }
+
+    #[test]
+    fn validate_import_reports_counts() {
+        let tmp = tempdir().expect("tmpdir");
+        let src = Database::new(tmp.path().join("src.sqlite"));
+        src.init().expect("src init");
+        src.add_favorite(&sample_video("video-3"))
+            .expect("add favorite");
+        src.cache_videos(&[sample_video("video-4")])
+            .expect("cache videos");
+        src.set_meta("settings.theme", "dark").expect("set meta");
+
+        let export_path = tmp.path().join("export.sqlite");
+        src.export_to(export_path.to_str().expect("export path utf8"))
+            .expect("export");
+
+        let dst = Database::new(tmp.path().join("dst.sqlite"));
+        dst.init().expect("dst init");
+        let report = dst
+            .validate_import(export_path.to_str().expect("export path utf8"))
+            .expect("validate import");
+        assert!(report.valid);
+        assert!(report.integrity_ok);
+        assert!(report.missing_tables.is_empty());
+        assert_eq!(report.video_count, 2);
+        assert_eq!(report.favorite_count, 1);
+        assert_eq!(report.preference_count, 1);
+        assert!(dst.list_favorites().expect("list favorites").is_empty());
+    }
+
+    #[test]
+    fn import_backs_up_existing_database_and_restores_it() {
+        let tmp = tempdir().expect("tmpdir");
+        let live = Database::new(tmp.path().join("live.sqlite"));
+        live.init().expect("live init");
+        live.add_favorite(&sample_video("video-before"))
+            .expect("add favorite");
+
+        let other = Database::new(tmp.path().join("other.sqlite"));
+        other.init().expect("other init");
+        let export_path = tmp.path().join("other-export.sqlite");
+        other
+            .export_to(export_path.to_str().expect("export path utf8"))
+            .expect("export");
+
+        live.import_from(export_path.to_str().expect("export path utf8"), true, true)
+            .expect("import");
+        assert!(live.list_favorites().expect("list favorites").is_empty());
+
+        let backups = live.list_backups().expect("list backups");
+        assert_eq!(backups.len(), 1);
+        assert!(Path::new(&backups[0].path).exists());
+        assert!(backups[0].size_bytes > 0);
+
+        live.restore_backup(&backups[0].path, false)
+            .expect("restore backup");
+        let favorites = live.list_favorites().expect("list favorites");
+        assert_eq!(favorites.len(), 1);
+        assert_eq!(favorites[0].video_id, "video-before");
+    }
+
+    #[test]
+    fn reset_selective_clears_only_requested_domains() {
+        let tmp = tempdir().expect("tmpdir");
+        let db = Database::new(tmp.path().join("reset.sqlite"));
+        db.init().expect("db init");
+        let favorite = sample_video("kept");
+        let mut cached = sample_video("cached");
+        cached.page_url = "https://example.com/v/cached".to_string();
+        db.add_favorite(&favorite).expect("add favorite");
+        db.cache_videos(std::slice::from_ref(&cached))
+            .expect("cache video");
+        db.record_watch("kept").expect("watch favorite");
+        db.record_watch("cached").expect("watch cached");
+        db.record_search("cats").expect("record search");
+        db.upsert_server(&SourceServer {
+            base_url: "https://example.com".to_string(),
+            title: "Example".to_string(),
+            color: None,
+            icon_url: None,
+            headers: None,
+        })
+        .expect("upsert server");
+
+        let report = db
+            .reset_selective(&ResetOptions {
+                cache: true,
+                history: true,
+                ..ResetOptions::default()
+            })
+            .expect("selective reset");
+
+        assert_eq!(
+            report,
+            ResetReport {
+                cache: 1,
+                history: 2,
+                ..ResetReport::default()
+            }
+        );
+        let favorites = db.list_favorites().expect("list favorites");
+        assert_eq!(favorites.len(), 1);
+        assert_eq!(favorites[0].video_id, "kept");
+        assert_eq!(db.user_views("kept").expect("views kept"), 1);
+        assert!(db.get_video("cached").expect("get cached").is_none());
+        assert_eq!(db.list_servers().expect("list servers").len(), 1);
+        assert_eq!(
+            db.dashboard().expect("dashboard").last_search.as_deref(),
+            Some("cats")
+        );
+    }
+
+    #[test]
+    fn server_preferences_roundtrip() {
+        let tmp = tempdir().expect("tmpdir");
+        let db = Database::new(tmp.path().join("servers.sqlite"));
+        db.init().expect("db init");
+
+        db.upsert_server(&SourceServer {
+            base_url: "https://getfigleaf.com".to_string(),
+            title: "Fig Leaf".to_string(),
+            color: Some("#478003".to_string()),
+            icon_url: Some("https://cdn.example.com/figleaf.png".to_string()),
+            headers: None,
+        })
+        .expect("upsert server");
+        db.upsert_server(&SourceServer {
+            base_url: "https://example.com".to_string(),
+            title: "Example".to_string(),
+            color: None,
+            icon_url: None,
+            headers: None,
+        })
+        .expect("upsert second server");
+
+        let servers = db.list_servers().expect("list servers");
+        assert_eq!(servers.len(), 2);
+        assert!(servers.iter().any(|server| {
+            server.base_url == "https://getfigleaf.com"
+                && server.title == "Fig Leaf"
+                && server.color.as_deref() == Some("#478003")
+                && server.icon_url.as_deref() == Some("https://cdn.example.com/figleaf.png")
+        }));
+
+        let removed = db
+            .remove_server("https://example.com")
+            .expect("remove server");
+        assert!(removed);
+        assert_eq!(db.list_servers().expect("list servers").len(), 1);
+    }
+
+    #[test]
+    fn server_custom_headers_roundtrip() {
+        let tmp = tempdir().expect("tmpdir");
+        let db = Database::new(tmp.path().join("server-headers.sqlite"));
+        db.init().expect("db init");
+
+        let headers = HashMap::from([
+            ("X-Api-Key".to_string(), "abc123".to_string()),
+            ("Referer".to_string(), "https://getfigleaf.com/".to_string()),
+        ]);
+        db.upsert_server(&SourceServer {
+            base_url: "https://getfigleaf.com".to_string(),
+            title: "Fig Leaf".to_string(),
+            color: None,
+            icon_url: None,
+            headers: Some(headers.clone()),
+        })
+        .expect("upsert server");
+
+        let servers = db.list_servers().expect("list servers");
+        assert_eq!(servers.len(), 1);
+        assert_eq!(servers[0].headers.as_ref(), Some(&headers));
+
+        let found = db
+            .get_server("https://getfigleaf.com/")
+            .expect("get server")
+            .expect("server found");
+        assert_eq!(found.headers, Some(headers));
+    }
+
+    #[test]
+    fn reset_all_data_clears_tables() {
+        let tmp = tempdir().expect("tmpdir");
+        let db = Database::new(tmp.path().join("reset.sqlite"));
+        db.init().expect("db init");
+
+        db.add_favorite(&sample_video("video-reset"))
+            .expect("add favorite");
+        db.set_meta("settings.theme", "dark").expect("set meta");
+        db.upsert_server(&SourceServer {
+            base_url: "https://getfigleaf.com".to_string(),
+            title: "Fig Leaf".to_string(),
+            color: None,
+            icon_url: None,
+            headers: None,
+        })
+        .expect("set server");
+
+        db.reset_all_data().expect("reset all");
+
+        let conn = Connection::open(db.path()).expect("open db");
+        let count_video_details: i64 = conn
+            .query_row(r#"SELECT COUNT(*) FROM "video_details""#, [], |row| row.get(0))
+            .expect("count video_details");
+        let count_meta: i64 = conn
+            .query_row(r#"SELECT COUNT(*) FROM "user_preferences""#, [], |row| row.get(0))
+            .expect("count user_preferences");
+        let count_servers: i64 = conn
+            .query_row(r#"SELECT COUNT(*) FROM "server_preferences""#, [], |row| row.get(0))
+            .expect("count server_preferences");
+        assert_eq!(count_video_details, 0);
+        assert_eq!(count_meta, 0);
+        assert_eq!(count_servers, 0);
+    }
+
+    #[test]
+    fn merge_from_upserts_favorites_and_keeps_live_rows() {
+        let tmp = tempdir().expect("tmpdir");
+        let live = Database::new(tmp.path().join("live.sqlite"));
+        live.init().expect("live init");
+        let backup = Database::new(tmp.path().join("backup.sqlite"));
+        backup.init().expect("backup init");
+
+        for id in ["shared-old", "shared-new", "live-only"] {
+            live.add_favorite(&sample_video(id)).expect("live favorite");
+        }
+        live.set_meta("theme", "light").expect("live pref");
+        for id in ["shared-old", "shared-new", "backup-only"] {
+            backup
+                .add_favorite(&sample_video(id))
+                .expect("backup favorite");
+        }
+        backup.set_meta("theme", "dark").expect("backup pref");
+        backup.set_meta("language", "en").expect("backup pref");
+        {
+            let conn = backup.conn().expect("backup conn");
+            conn.execute(
+                r#"UPDATE "video_details" SET "favoriteDate" = ?1, "lastUpdated" = ?1 WHERE "id" = ?2"#,
+                params!["2000-01-01T00:00:00Z", "shared-old"],
+            )
+            .expect("age shared-old");
+            conn.execute(
+                r#"UPDATE "video_details" SET "favoriteDate" = ?1, "lastUpdated" = ?1, "title" = 'Renamed' WHERE "id" = ?2"#,
+                params!["2099-01-01T00:00:00Z", "shared-new"],
+            )
+            .expect("age shared-new");
+        }
+        backup
+            .export_to(tmp.path().join("export.sqlite").to_str().expect("utf8"))
+            .expect("export backup");
+
+        let report = live
+            .merge_from(tmp.path().join("export.sqlite").to_str().expect("utf8"))
+            .expect("merge");
+        assert_eq!(
+            report,
+            MergeReport {
+                added: 2,
+                updated: 1,
+                skipped: 2,
+            }
+        );
+
+        let favorites = live.list_favorites().expect("list favorites");
+        let mut ids: Vec<&str> = favorites.iter().map(|fav| fav.video_id.as_str()).collect();
+        ids.sort_unstable();
+        assert_eq!(
+            ids,
+            ["backup-only", "live-only", "shared-new", "shared-old"]
+        );
+        let renamed = favorites
+            .iter()
+            .find(|fav| fav.video_id == "shared-new")
+            .expect("shared-new");
+        assert_eq!(renamed.title, "Renamed");
+        let kept = favorites
+            .iter()
+            .find(|fav| fav.video_id == "shared-old")
+            .expect("shared-old");
+        assert!(kept.added_at_epoch > 946_684_800);
+        assert_eq!(
+            live.get_meta("theme").expect("theme").as_deref(),
+            Some("light")
+        );
+        assert_eq!(
+            live.get_meta("language").expect("language").as_deref(),
+            Some("en")
+        );
+    }
+
+    #[test]
+    fn favorites_changed_since_filters_by_parsed_cursor() {
+        let tmp = tempdir().expect("tmpdir");
+        let db = Database::new(tmp.path().join("db.sqlite"));
+        db.init().expect("db init");
+
+        for id in ["early", "middle", "late", "epoch", "garbled"] {
+            db.add_favorite(&sample_video(id)).expect("add favorite");
+        }
+        {
+            let conn = db.conn().expect("conn");
+            for (id, date) in [
+                ("early", "2024-01-01T00:00:00.000Z"),
+                ("middle", "2024-02-01 12:00:00"),
+                ("late", "2024-03-01T00:00:00Z"),
+                ("epoch", "1706400000000"),
+                ("garbled", "not a date"),
+            ] {
+                conn.execute(
+                    r#"UPDATE "video_details" SET "favoriteDate" = ?1 WHERE "id" = ?2"#,
+                    params![date, id],
+                )
+                .expect("set favorite date");
+            }
+        }
+
+        let changed = db
+            .favorites_changed_since(1_704_067_200)
+            .expect("changed since early");
+        let ids: Vec<&str> = changed.iter().map(|fav| fav.video_id.as_str()).collect();
+        assert_eq!(ids, ["epoch", "middle", "late"]);
+        assert_eq!(changed[0].added_at_epoch, 1_706_400_000);
+
+        let cursor = changed[1].added_at_epoch;
+        let rest = db
+            .favorites_changed_since(cursor)
+            .expect("changed since middle");
+        assert_eq!(rest.len(), 1);
+        assert_eq!(rest[0].video_id, "late");
+    }
+
+    #[test]
+    fn cached_resolved_lookup_by_id_applies_ttl() {
+        let tmp = tempdir().expect("tmpdir");
+        let db = Database::new(tmp.path().join("db.sqlite"));
+        db.init().expect("db init");
+        let video = sample_video("video-1");
+        db.cache_videos(std::slice::from_ref(&video))
+            .expect("cache videos");
+        assert!(db
+            .get_cached_resolved_by_id("video-1", 60)
+            .expect("lookup before resolve")
+            .is_none());
+
+        db.cache_resolved_video(
+            &video.page_url,
+            &ResolvedVideo {
+                id: "video-1".to_string(),
+                title: "Sample".to_string(),
+                page_url: video.page_url.clone(),
+                stream_url: "https://cdn.example.com/1.mp4".to_string(),
+                thumbnail_url: None,
+                author_name: None,
+                extractor: None,
+                duration_seconds: None,
+                view_count: None,
+                upload_date_epoch: None,
+                description: None,
+                stream_protocol: None,
+                thumbnails: Vec::new(),
+            },
+        )
+        .expect("cache resolved");
+
+        let hit = db
+            .get_cached_resolved_by_id("video-1", 60)
+            .expect("lookup within ttl")
+            .expect("cached resolved video");
+        assert_eq!(hit.stream_url, "https://cdn.example.com/1.mp4");
+        assert!(db
+            .get_cached_resolved_by_id("missing", 60)
+            .expect("lookup missing")
+            .is_none());
 
+        {
+            let conn = db.conn().expect("conn");
             conn.execute(
-                r#"
-                INSERT INTO "video_details" (
-                    "id", "url", "title", "thumb", "dateAdded", "views", "duration",
-                    "uploader", "network", "lastUpdated", "rawData", "cacheDate"
-                )
-                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
-                ON CONFLICT("id") DO UPDATE SET
-                    "url" = excluded."url",
-                    "title" = excluded."title",
-                    "thumb" = excluded."thumb",
-                    "views" = excluded."views",
-                    "duration" = excluded."duration",
-                    "uploader" = excluded."uploader",
-                    "network" = excluded."network",
-                    "lastUpdated" = excluded."lastUpdated",
-                    "rawData" = excluded."rawData",
-                    "cacheDate" = excluded."cacheDate"
-                "#,
-                params![
-                    video_id,
-                    url,
-                    title,
-                    thumb,
-                    updated_iso,
-                    views,
-                    duration,
-                    uploader,
-                    network,
-                    updated_iso,
-                    payload_json,
-                    updated_iso
-                ],
-            )?;
+                r#"UPDATE "video_details" SET "cacheDate" = ?1 WHERE "id" = 'video-1'"#,
+                params![epoch_seconds_to_iso(Utc::now().timestamp() - 120)],
+            )
+            .expect("age cache row");
         }
-        Ok(())
+        assert!(db
+            .get_cached_resolved_by_id("video-1", 60)
+            .expect("lookup beyond ttl")
+            .is_none());
     }
 
-    fn migrate_legacy_favorites(conn: &Connection) -> Result<(), EngineError> {
-        let mut stmt =
-            conn.prepare("SELECT video_id, title, image_url, network, added_at FROM favorites")?;
-        let rows = stmt.query_map([], |row| {
-            let video_id: String = row.get(0)?;
-            let title: String = row.get(1)?;
-            let image_url: Option<String> = row.get(2)?;
-            let network: Option<String> = row.get(3)?;
-            let added_at: i64 = row.get(4)?;
-            Ok((video_id, title, image_url, network, added_at))
-        })?;
+    #[test]
+    fn init_records_schema_version_once() {
+        let tmp = tempdir().expect("tmpdir");
+        let db = Database::new(tmp.path().join("db.sqlite"));
+        db.init().expect("db init");
+        assert_eq!(db.schema_version().expect("schema version"), SCHEMA_VERSION);
 
-        for row in rows {
-            let (video_id, title, image_url, network, added_at) = row?;
-            let favorite_date = epoch_seconds_to_iso(added_at);
-            let existing_url: Option<String> = conn
-                .query_row(
-                    r#"SELECT "url" FROM "video_details" WHERE "id" = ?1"#,
-                    params![video_id],
-                    |r| r.get(0),
-                )
-                .optional()?;
+        db.init().expect("db re-init");
+        assert_eq!(db.schema_version().expect("schema version"), SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn init_creates_lookup_indexes() {
+        let tmp = tempdir().expect("tmpdir");
+        let db = Database::new(tmp.path().join("db.sqlite"));
+        db.init().expect("db init");
+        db.init().expect("db re-init");
+
+        let conn = db.conn().expect("conn");
+        for (index, _) in VIDEO_DETAILS_INDEXES {
+            assert!(
+                Database::index_exists(&conn, index).expect("index lookup"),
+                "missing {index}"
+            );
+        }
+        let analyzed = conn
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE name = 'sqlite_stat1'",
+                [],
+                |row| row.get::<_, i64>(0),
+            )
+            .expect("stat table lookup");
+        assert_eq!(analyzed, 1);
+
+        let plan: Vec<String> = conn
+            .prepare(
+                r#"EXPLAIN QUERY PLAN SELECT "allFormats" FROM "video_details" WHERE "url" = ?1"#,
+            )
+            .expect("prepare plan")
+            .query_map(params!["https://example.com/watch"], |row| row.get(3))
+            .expect("query plan")
+            .collect::<Result<_, _>>()
+            .expect("plan rows");
+        assert!(
+            plan.iter()
+                .any(|detail| detail.contains("idx_video_details_url")),
+            "{plan:?}"
+        );
+    }
+
+    #[test]
+    fn remove_favorites_clears_only_listed_ids() {
+        let tmp = tempdir().expect("tmpdir");
+        let db = Database::new(tmp.path().join("db.sqlite"));
+        db.init().expect("db init");
+        for id in ["one", "two", "three"] {
+            db.add_favorite(&sample_video(id)).expect("add favorite");
+        }
+
+        assert_eq!(db.remove_favorites(&[]).expect("empty removal"), 0);
+        let removed = db
+            .remove_favorites(&[
+                "one".to_string(),
+                "three".to_string(),
+                "missing".to_string(),
+            ])
+            .expect("bulk remove");
+        assert_eq!(removed, 2);
+        assert_eq!(
+            db.remove_favorites(&["one".to_string()])
+                .expect("repeat removal"),
+            0
+        );
+
+        let remaining = db.list_favorites().expect("list favorites");
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].video_id, "two");
+    }
+
+    #[test]
+    fn dashboard_snapshots_counts_and_meta() {
+        let tmp = tempdir().expect("tmpdir");
+        let db = Database::new(tmp.path().join("db.sqlite"));
+        db.init().expect("db init");
+
+        db.cache_videos(&[sample_video("cached-1"), sample_video("cached-2")])
+            .expect("cache videos");
+        db.add_favorite(&sample_video("cached-1"))
+            .expect("add favorite");
+        db.record_search("older").expect("record search");
+        {
+            let conn = db.conn().expect("conn");
+            conn.execute(
+                r#"UPDATE "video_details" SET "lastWatchDate" = ?1 WHERE "id" = 'cached-2'"#,
+                params![db.now_iso()],
+            )
+            .expect("mark watched");
+            conn.execute(
+                r#"UPDATE "searches" SET "timestamp" = '2000-01-01T00:00:00.000Z'"#,
+                [],
+            )
+            .expect("age search");
+        }
+        db.record_search("newest").expect("record search");
+        db.set_meta("yt_dlp_update_available", "true")
+            .expect("set meta");
+
+        assert_eq!(
+            db.dashboard().expect("dashboard"),
+            Dashboard {
+                favorite_count: 1,
+                watched_count: 1,
+                cached_count: 2,
+                last_search: Some("newest".to_string()),
+                yt_dlp_update_available: true,
+            }
+        );
+    }
 
+    #[test]
+    fn get_video_prefers_raw_data_and_falls_back_to_columns() {
+        let tmp = tempdir().expect("tmpdir");
+        let db = Database::new(tmp.path().join("db.sqlite"));
+        db.init().expect("db init");
+        let mut cached = sample_video("cached");
+        cached.extractor = Some("generic".to_string());
+        cached.aspect_ratio = Some(0.5625);
+        db.cache_videos(std::slice::from_ref(&cached))
+            .expect("cache video");
+        {
+            let conn = db.conn().expect("conn");
             conn.execute(
                 r#"
                 INSERT INTO "video_details" (
-                    "id", "url", "title", "thumb", "network", "favoriteDate", "lastUpdated", "dateAdded"
+                    "id", "url", "title", "thumb", "views", "duration", "uploader", "network"
                 )
-                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
-                ON CONFLICT("id") DO UPDATE SET
-                    "title" = excluded."title",
-                    "thumb" = excluded."thumb",
-                    "network" = excluded."network",
-                    "favoriteDate" = excluded."favoriteDate",
-                    "lastUpdated" = excluded."lastUpdated"
+                VALUES ('columns-only', 'https://example.com/v/columns', 'From columns',
+                        'https://example.com/c.jpg', 9, 30, 'someone', 'catflix')
                 "#,
-                params![
-                    video_id,
-                    existing_url.unwrap_or_else(|| fallback_url(&video_id)),
-                    title,
-                    image_url,
-                    network,
-                    favorite_date,
-                    favorite_date,
-                    favorite_date
-                ],
-            )?;
+                [],
+            )
+            .expect("seed columns-only row");
         }
-        Ok(())
+
+        let video = db
+            .get_video("cached")
+            .expect("get cached")
+            .expect("cached video");
+        assert_eq!(video.page_url, cached.page_url);
+        assert_eq!(video.extractor.as_deref(), Some("generic"));
+        assert_eq!(video.aspect_ratio, Some(0.5625));
+
+        let video = db
+            .get_video("columns-only")
+            .expect("get columns-only")
+            .expect("columns-only video");
+        assert_eq!(video.title, "From columns");
+        assert_eq!(video.page_url, "https://example.com/v/columns");
+        assert_eq!(
+            video.image_url.as_deref(),
+            Some("https://example.com/c.jpg")
+        );
+        assert_eq!(video.view_count, Some(9));
+        assert_eq!(video.duration_seconds, Some(30));
+        assert_eq!(video.author_name.as_deref(), Some("someone"));
+        assert_eq!(video.network.as_deref(), Some("catflix"));
+        assert!(video.raw_json.is_none());
+
+        assert!(db.get_video("missing").expect("get missing").is_none());
     }
 
-    fn migrate_legacy_resolved_cache(conn: &Connection) -> Result<(), EngineError> {
-        let mut stmt =
-            conn.prepare("SELECT page_url, payload_json, updated_at FROM resolved_cache")?;
-        let rows = stmt.query_map([], |row| {
-            let page_url: String = row.get(0)?;
-            let payload_json: String = row.get(1)?;
-            let updated_at: i64 = row.get(2)?;
-            Ok((page_url, payload_json, updated_at))
-        })?;
+    #[test]
+    fn watches_count_user_views_and_rank_rewatches() {
+        let tmp = tempdir().expect("tmpdir");
+        let db = Database::new(tmp.path().join("db.sqlite"));
+        db.init().expect("db init");
+        let videos: Vec<VideoItem> = ["once", "thrice", "never"]
+            .into_iter()
+            .map(|id| {
+                let mut video = sample_video(id);
+                video.page_url = format!("https://example.com/v/{id}");
+                video
+            })
+            .collect();
+        db.cache_videos(&videos).expect("cache videos");
 
-        for row in rows {
-            let (page_url, payload_json, updated_at) = row?;
-            let updated_iso = epoch_seconds_to_iso(updated_at);
-            let parsed = serde_json::from_str::<ResolvedVideo>(&payload_json).ok();
-            let id = parsed
-                .as_ref()
-                .map(|video| video.id.trim())
-                .filter(|id| !id.is_empty())
-                .map(ToOwned::to_owned)
-                .unwrap_or_else(|| format_resolved_cache_id(&page_url));
-            let title = parsed
-                .as_ref()
-                .map(|video| video.title.trim())
-                .filter(|title| !title.is_empty())
-                .unwrap_or("Resolved Video");
+        for _ in 0..3 {
+            assert!(db.record_watch("thrice").expect("watch thrice"));
+        }
+        db.record_watch("once").expect("watch once");
+        assert!(!db.record_watch("missing").expect("unknown video"));
 
-            let updated = conn.execute(
-                r#"
-                UPDATE "video_details"
-                SET
-                    "allFormats" = ?1,
-                    "cacheDate" = ?2,
-                    "lastUpdated" = ?3,
-                    "title" = COALESCE(NULLIF(?4, ''), "title")
-                WHERE "url" = ?5
-                "#,
-                params![payload_json, updated_iso, updated_iso, title, page_url],
-            )?;
+        assert_eq!(db.user_views("thrice").expect("views"), 3);
+        assert_eq!(db.user_views("never").expect("no views"), 0);
+        let ids: Vec<String> = db
+            .most_rewatched(10)
+            .expect("most rewatched")
+            .into_iter()
+            .map(|video| video.id)
+            .collect();
+        assert_eq!(ids, ["thrice", "once"]);
+        assert_eq!(db.most_rewatched(1).expect("limited").len(), 1);
+    }
 
-            if updated == 0 {
-                conn.execute(
-                    r#"
-                    INSERT INTO "video_details" (
-                        "id", "url", "title", "lastUpdated", "cacheDate", "allFormats", "rawData", "dateAdded"
-                    )
-                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
-                    ON CONFLICT("id") DO UPDATE SET
-                        "url" = excluded."url",
-                        "title" = excluded."title",
-                        "lastUpdated" = excluded."lastUpdated",
-                        "cacheDate" = excluded."cacheDate",
-                        "allFormats" = excluded."allFormats",
-                        "rawData" = excluded."rawData"
-                    "#,
-                    params![
-                        id,
-                        page_url,
-                        title,
-                        updated_iso,
-                        updated_iso,
-                        payload_json,
-                        payload_json,
-                        updated_iso
-                    ],
-                )?;
-            }
+    #[test]
+    fn ratings_validate_and_order_favorites() {
+        let tmp = tempdir().expect("tmpdir");
+        let db = Database::new(tmp.path().join("db.sqlite"));
+        db.init().expect("db init");
+        for id in ["unrated", "good", "best"] {
+            let mut video = sample_video(id);
+            video.page_url = format!("https://example.com/v/{id}");
+            db.add_favorite(&video).expect("add favorite");
         }
-        Ok(())
+
+        assert!(db.set_rating("good", 3.5).expect("rate good"));
+        assert!(db.set_rating("best", 5.0).expect("rate best"));
+        assert!(!db.set_rating("missing", 1.0).expect("unknown video"));
+        for invalid in [-0.5, 5.1, f64::NAN] {
+            let err = db.set_rating("good", invalid).expect_err("invalid rating");
+            assert!(matches!(err, EngineError::InvalidConfig { .. }));
+        }
+
+        let ids: Vec<String> = db
+            .list_favorites_by_rating()
+            .expect("favorites by rating")
+            .into_iter()
+            .map(|favorite| favorite.video_id)
+            .collect();
+        assert_eq!(ids, ["best", "good", "unrated"]);
     }
-}
 
-fn now_iso() -> String {
-    Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true)
-}
+    #[test]
+    fn tags_normalize_and_filter_favorites() {
+        let tmp = tempdir().expect("tmpdir");
+        let db = Database::new(tmp.path().join("db.sqlite"));
+        db.init().expect("db init");
+        for id in ["tagged", "other"] {
+            let mut video = sample_video(id);
+            video.page_url = format!("https://example.com/v/{id}");
+            db.add_favorite(&video).expect("add favorite");
+        }
+        let mut cached = sample_video("cached-only");
+        cached.page_url = "https://example.com/v/cached-only".to_string();
+        db.cache_videos(&[cached]).expect("cache video");
 
-fn epoch_seconds_to_iso(epoch_seconds: i64) -> String {
-    Utc.timestamp_opt(epoch_seconds, 0)
-        .single()
-        .map(|time| time.to_rfc3339_opts(SecondsFormat::Millis, true))
-        .unwrap_or_else(now_iso)
-}
+        let tags = [" Cats ", "cats", "Road Trip", ""].map(String::from);
+        assert!(db.set_video_tags("tagged", &tags).expect("set tags"));
+        assert!(db
+            .set_video_tags("other", &["dogs".to_string()])
+            .expect("set other tags"));
+        assert!(db
+            .set_video_tags("cached-only", &["cats".to_string()])
+            .expect("tag non-favorite"));
+        assert!(!db
+            .set_video_tags("missing", &["cats".to_string()])
+            .expect("unknown video"));
 
-fn parse_timestamp_to_epoch_seconds(value: &str) -> Option<i64> {
-    let trimmed = value.trim();
-    if trimmed.is_empty() {
-        return None;
+        assert_eq!(
+            db.get_video_tags("tagged").expect("get tags"),
+            ["cats", "road trip"]
+        );
+        assert!(db
+            .get_video_tags("missing")
+            .expect("missing tags")
+            .is_empty());
+
+        let favorites = db.list_favorites_by_tag(" CATS").expect("filter by tag");
+        assert_eq!(favorites.len(), 1);
+        assert_eq!(favorites[0].video_id, "tagged");
+        assert!(db
+            .list_favorites_by_tag("birds")
+            .expect("unused tag")
+            .is_empty());
+
+        db.set_video_tags("tagged", &[]).expect("clear tags");
+        assert!(db
+            .get_video_tags("tagged")
+            .expect("cleared tags")
+            .is_empty());
+    }
+
+    #[test]
+    fn migrates_old_shape_cached_payloads() {
+        let tmp = tempdir().expect("tmpdir");
+        let db = Database::new(tmp.path().join("db.sqlite"));
+        db.init().expect("db init");
+        {
+            let conn = db.conn().expect("conn");
+            for (id, raw_data) in [
+                (
+                    "old-shape",
+                    r#"{"id":"old-shape","title":"Old","page_url":"https://example.com/v/old","view_count":7}"#,
+                ),
+                ("foreign", r#"{"videoId":"foreign","someOtherClient":true}"#),
+                ("not-json", "legacy text"),
+            ] {
+                conn.execute(
+                    r#"INSERT INTO "video_details" ("id", "url", "rawData") VALUES (?1, 'https://example.com/v', ?2)"#,
+                    params![id, raw_data],
+                )
+                .expect("seed rawData");
+            }
+        }
+
+        assert_eq!(db.migrate_cached_payloads().expect("migrate payloads"), 1);
+        assert_eq!(db.migrate_cached_payloads().expect("already migrated"), 0);
+
+        let conn = db.conn().expect("conn");
+        let raw = |id: &str| -> String {
+            conn.query_row(
+                r#"SELECT "rawData" FROM "video_details" WHERE "id" = ?1"#,
+                params![id],
+                |row| row.get(0),
+            )
+            .expect("read rawData")
+        };
+        let upgraded: serde_json::Value =
+            serde_json::from_str(&raw("old-shape")).expect("upgraded json");
+        assert_eq!(upgraded["view_count"], 7);
+        assert!(upgraded
+            .as_object()
+            .expect("object payload")
+            .contains_key("aspect_ratio"));
+        assert_eq!(
+            raw("foreign"),
+            r#"{"videoId":"foreign","someOtherClient":true}"#
+        );
+        assert_eq!(raw("not-json"), "legacy text");
     }
 
-    if let Ok(raw_number) = trimmed.parse::<i64>() {
-        if raw_number > 10_000_000_000 {
-            return Some(raw_number / 1000);
+    #[test]
+    fn lists_cached_videos_by_network_with_paging() {
+        let tmp = tempdir().expect("tmpdir");
+        let clock = FakeClock::new(Utc::now());
+        let db = Database::new(tmp.path().join("db.sqlite")).with_clock(clock.clone());
+        db.init().expect("db init");
+        for (id, network) in [
+            ("cat-1", "catflix"),
+            ("dog-1", "dogflix"),
+            ("cat-2", "Catflix"),
+            ("cat-3", "catflix"),
+        ] {
+            let mut video = sample_video(id);
+            video.page_url = format!("https://example.com/v/{id}");
+            video.network = Some(network.to_string());
+            db.cache_videos(&[video]).expect("cache video");
+            clock.advance(chrono::Duration::seconds(1));
         }
-        return Some(raw_number);
-    }
+        let ids = |videos: Vec<VideoItem>| -> Vec<String> {
+            videos.into_iter().map(|video| video.id).collect()
+        };
 
-    if let Ok(parsed) = DateTime::parse_from_rfc3339(trimmed) {
-        return Some(parsed.timestamp());
+        let first_page = db
+            .list_cached_by_network(" CATFLIX ", 0, 2)
+            .expect("first page");
+        assert_eq!(first_page[0].extractor.as_deref(), Some("youtube"));
+        assert_eq!(ids(first_page), ["cat-3", "cat-2"]);
+        assert_eq!(
+            ids(db
+                .list_cached_by_network("catflix", 2, 2)
+                .expect("second page")),
+            ["cat-1"]
+        );
+        assert!(db
+            .list_cached_by_network("catflix", 4, 2)
+            .expect("past the end")
+            .is_empty());
+        assert_eq!(
+            ids(db.list_cached_by_network("", 0, 10).expect("all cached")),
+            ["cat-3", "cat-2", "dog-1", "cat-1"]
+        );
     }
 
-    const CANDIDATE_FORMATS: [&str; 3] = [
-        "%Y-%m-%dT%H:%M:%S%.f",
-        "%Y-%m-%d %H:%M:%S%.f",
-        "%Y-%m-%dT%H:%M:%S",
-    ];
-
-    for format in CANDIDATE_FORMATS {
-        if let Ok(parsed) = NaiveDateTime::parse_from_str(trimmed, format) {
-            return Some(Utc.from_utc_datetime(&parsed).timestamp());
+    #[test]
+    fn favorites_follow_custom_sort_position() {
+        let tmp = tempdir().expect("tmpdir");
+        let clock = FakeClock::new(Utc::now());
+        let db = Database::new(tmp.path().join("db.sqlite")).with_clock(clock.clone());
+        db.init().expect("db init");
+        for id in ["first", "second", "third"] {
+            db.add_favorite(&sample_video(id)).expect("add favorite");
+            clock.advance(chrono::Duration::seconds(1));
         }
-    }
 
-    None
-}
+        assert!(db
+            .set_favorite_position("first", 0)
+            .expect("position first"));
+        assert!(db
+            .set_favorite_position("second", 1)
+            .expect("position second"));
+        assert!(!db
+            .set_favorite_position("missing", 2)
+            .expect("unknown favorite"));
 
-fn fallback_url(video_id: &str) -> String {
-    format!("local://video/{video_id}")
-}
+        let ids = |db: &Database| -> Vec<String> {
+            db.list_favorites()
+                .expect("list favorites")
+                .into_iter()
+                .map(|favorite| favorite.video_id)
+                .collect()
+        };
+        assert_eq!(ids(&db), ["first", "second", "third"]);
 
-fn format_resolved_cache_id(page_url: &str) -> String {
-    format!("resolved:{page_url}")
-}
+        db.set_favorite_position("first", 5).expect("move first");
+        assert_eq!(ids(&db), ["second", "first", "third"]);
 
-fn non_empty_str(value: &str) -> Option<&str> {
-    let trimmed = value.trim();
-    if trimmed.is_empty() {
-        None
-    } else {
-        Some(trimmed)
+        db.remove_favorite("first").expect("remove first");
+        db.add_favorite(&sample_video("first"))
+            .expect("re-add first");
+        assert_eq!(ids(&db), ["second", "first", "third"]);
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use rusqlite::Connection;
-    use tempfile::tempdir;
 
-    fn sample_video(id: &str) -> VideoItem {
-        VideoItem {
-            id: id.to_string(),
-            title: "Sample".to_string(),
-            page_url: "https://example.com/v/1".to_string(),
-            duration_seconds: Some(120),
-            image_url: Some("https://example.com/image.jpg".to_string()),
-            network: Some("youtube".to_string()),
-            author_name: Some("author".to_string()),
-            extractor: Some("youtube".to_string()),
-            view_count: Some(42),
-            raw_json: Some(format!("{{\"id\":\"{id}\"}}")),
+    #[test]
+    fn category_sync_prunes_removed_and_keeps_clicks() {
+        let tmp = tempdir().expect("tmpdir");
+        let db = Database::new(tmp.path().join("db.sqlite"));
+        db.init().expect("db init");
+        db.sync_categories(&["cats".to_string(), "dogs".to_string(), "birds".to_string()])
+            .expect("sync categories");
+        for _ in 0..3 {
+            db.record_search("cats").expect("record cats");
         }
+        db.record_search("birds").expect("record birds");
+
+        db.sync_categories(&["cats".to_string(), " dogs ".to_string()])
+            .expect("sync smaller list");
+        db.sync_categories(&[" ".to_string()])
+            .expect("empty sync is a no-op");
+
+        let conn = db.conn().expect("conn");
+        let rows: Vec<(String, i64)> = conn
+            .prepare(r#"SELECT "id", "clicks" FROM "categories" ORDER BY "id""#)
+            .expect("prepare")
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .expect("query categories")
+            .collect::<Result<_, _>>()
+            .expect("collect categories");
+        assert_eq!(rows, [("cats".to_string(), 3), ("dogs".to_string(), 0)]);
     }
 
     #[test]
-    fn favorites_roundtrip() {
+    fn category_clicks_decay_and_reset() {
         let tmp = tempdir().expect("tmpdir");
         let db = Database::new(tmp.path().join("db.sqlite"));
         db.init().expect("db init");
+        db.sync_categories(&["cats".to_string(), "dogs".to_string()])
+            .expect("sync categories");
+        for _ in 0..5 {
+            db.record_search("cats").expect("record cats");
+        }
+        for _ in 0..4 {
+            db.record_search("dogs").expect("record dogs");
+        }
 
-        let favorite = db
-            .add_favorite(&sample_video("video-1"))
-            .expect("add favorite");
-        assert_eq!(favorite.video_id, "video-1");
-
-        let favorites = db.list_favorites().expect("list favorites");
-        assert_eq!(favorites.len(), 1);
-        assert_eq!(favorites[0].video_id, "video-1");
+        let clicks = |db: &Database| -> Vec<(String, i64)> {
+            let conn = db.conn().expect("conn");
+            let mut stmt = conn
+                .prepare(r#"SELECT "id", "clicks" FROM "categories" ORDER BY "id""#)
+                .expect("prepare");
+            stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+                .expect("query clicks")
+                .collect::<Result<_, _>>()
+                .expect("collect clicks")
+        };
 
-        let favorite_videos = db.list_favorite_videos().expect("list favorite videos");
-        assert_eq!(favorite_videos.len(), 1);
-        assert_eq!(favorite_videos[0].id, "video-1");
-        assert_eq!(favorite_videos[0].page_url, "https://example.com/v/1");
+        db.decay_category_clicks(0.5).expect("decay");
+        assert_eq!(
+            clicks(&db),
+            vec![("cats".to_string(), 2), ("dogs".to_string(), 2)]
+        );
+        for factor in [0.0, 1.0, -0.5, f64::NAN] {
+            assert!(matches!(
+                db.decay_category_clicks(factor),
+                Err(EngineError::InvalidConfig { .. })
+            ));
+        }
 
-        let removed = db.remove_favorite("video-1").expect("remove favorite");
-        assert!(removed);
-        assert!(db.list_favorites().expect("list favorites").is_empty());
-        assert!(db.list_favorite_videos().expect("list favorite videos").is_empty());
+        db.reset_category_clicks().expect("reset");
+        assert_eq!(
+            clicks(&db),
+            vec![("cats".to_string(), 0), ("dogs".to_string(), 0)]
+        );
     }
 
     #[test]
-    fn template_schema_tables_exist() {
+    fn export_bytes_includes_wal_contents() {
         let tmp = tempdir().expect("tmpdir");
-        let db = Database::new(tmp.path().join("schema.sqlite"));
+        let db = Database::new(tmp.path().join("db.sqlite"));
         db.init().expect("db init");
+        db.add_favorite(&sample_video("video-1"))
+            .expect("add favorite");
 
-        let conn = Connection::open(db.path()).expect("open db");
-        let mut stmt = conn
-            .prepare("SELECT name FROM sqlite_master WHERE type = 'table' ORDER BY name")
-            .expect("prepare list tables");
-        let names = stmt
-            .query_map([], |row| row.get::<_, String>(0))
-            .expect("query tables")
-            .collect::<Result<Vec<_>, _>>()
-            .expect("collect tables");
+        let bytes = db.export_bytes().expect("export bytes");
+        assert!(bytes.starts_with(b"SQLite format 3\0"));
 
-        assert!(names.iter().any(|name| name == "video_details"));
-        assert!(names.iter().any(|name| name == "searches"));
-        assert!(names.iter().any(|name| name == "categories"));
-        assert!(names.iter().any(|name| name == "user_preferences"));
-        assert!(names.iter().any(|name| name == "server_preferences"));
+        let copy_path = tmp.path().join("copy.sqlite");
+        fs::write(&copy_path, &bytes).expect("write copy");
+        let report = db
+            .validate_import(copy_path.to_str().expect("utf8"))
+            .expect("validate copy");
+        assert!(report.valid);
+        assert_eq!(report.favorite_count, 1);
     }
 
     #[test]
-    fn searches_and_category_clicks_roundtrip() {
+    fn import_bytes_swaps_in_valid_database_only() {
         let tmp = tempdir().expect("tmpdir");
-        let db = Database::new(tmp.path().join("search.sqlite"));
+        let source = Database::new(tmp.path().join("source.sqlite"));
+        source.init().expect("source init");
+        source
+            .add_favorite(&sample_video("exported"))
+            .expect("add favorite");
+        let bytes = source.export_bytes().expect("export bytes");
+
+        let db = Database::new(tmp.path().join("db.sqlite"));
         db.init().expect("db init");
+        db.add_favorite(&sample_video("local"))
+            .expect("add favorite");
 
-        db.sync_categories(&["Amateur".to_string(), "Professional".to_string()])
-            .expect("sync categories");
-        db.record_search("Amateur").expect("record first search");
-        db.record_search("Amateur").expect("record second search");
+        let err = db
+            .import_bytes(b"definitely not sqlite", false)
+            .expect_err("garbage rejected");
+        assert!(matches!(err, EngineError::Database { .. }));
+        let favorites = db.list_favorites().expect("list favorites");
+        assert_eq!(favorites.len(), 1);
+        assert_eq!(favorites[0].video_id, "local");
 
-        let conn = Connection::open(db.path()).expect("open db");
-        let frequency: i64 = conn
-            .query_row(
-                r#"SELECT "frequency" FROM "searches" WHERE "query" = 'Amateur'"#,
-                [],
-                |row| row.get(0),
-            )
-            .expect("query search frequency");
-        assert_eq!(frequency, 2);
+        assert!(db.import_bytes(&bytes, true).expect("import bytes"));
+        let favorites = db.list_favorites().expect("list favorites");
+        assert_eq!(favorites.len(), 1);
+        assert_eq!(favorites[0].video_id, "exported");
+        assert_eq!(db.list_backups().expect("list backups").len(), 1);
 
-        let clicks: i64 = conn
-            .query_row(
-                r#"SELECT "clicks" FROM "categories" WHERE "id" = 'Amateur'"#,
-                [],
-                |row| row.get(0),
-            )
-            .expect("query category clicks");
-        assert_eq!(clicks, 2);
+        let leftovers: Vec<_> = fs::read_dir(tmp.path())
+            .expect("read dir")
+            .flatten()
+            .filter(|entry| entry.file_name().to_string_lossy().ends_with(".tmp"))
+            .collect();
+        assert!(leftovers.is_empty());
     }
 
     #[test]
-    fn import_template_schema_keeps_favorites_compatible() {
+    fn import_swaps_staged_copy_and_clears_stray_staging_files() {
         let tmp = tempdir().expect("tmpdir");
-        let template_path = tmp.path().join("template.sqlite");
-        let conn = Connection::open(&template_path).expect("create template db");
-        conn.execute_batch(
-            r#"
-            CREATE TABLE IF NOT EXISTS "user_preferences" ("id" TEXT PRIMARY KEY NOT NULL, "preferenceValue" TEXT);
-            CREATE TABLE IF NOT EXISTS "server_preferences" ("id" TEXT PRIMARY KEY NOT NULL, "preferenceValue" TEXT);
-            CREATE TABLE IF NOT EXISTS "video_details" ("id" TEXT PRIMARY KEY NOT NULL, "url" TEXT NOT NULL, "title" TEXT, "thumb" TEXT, "preview" TEXT, "dateAdded" TEXT, "views" INTEGER DEFAULT (0), "duration" INTEGER DEFAULT (0), "uploader" TEXT, "uploaderUrl" TEXT, "tags" TEXT, "lastUpdated" TEXT, "flags" TEXT, "favoriteDate" TEXT, "lastWatchDate" TEXT, "uploadedAt" TEXT, "rating" REAL, "userViews" INTEGER, "network" TEXT, "aspectRatio" REAL, "allFormats" TEXT, "session" TEXT, "rawData" TEXT, "cacheDate" TEXT, "adData" TEXT);
-            CREATE TABLE IF NOT EXISTS "categories" ("id" TEXT PRIMARY KEY NOT NULL, "name" TEXT NOT NULL, "clicks" INTEGER NOT NULL DEFAULT (0));
-            CREATE TABLE IF NOT EXISTS "searches" ("query" TEXT PRIMARY KEY NOT NULL, "timestamp" TEXT NOT NULL, "frequency" INTEGER NOT NULL DEFAULT (1));
-            "#,
-        )
-        .expect("create template tables");
-        conn.execute(
-            r#"
-            INSERT INTO "video_details" ("id", "url", "title", "thumb", "network", "favoriteDate")
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
-            "#,
-            params![
-                "video-42",
-                "https://example.com/v/42",
-                "Template favorite",
-                "https://example.com/42.jpg",
-                "example-network",
-                "2025-11-30T20:40:00Z"
-            ],
-        )
-        .expect("seed template favorite");
+        let source = Database::new(tmp.path().join("source.sqlite"));
+        source.init().expect("source init");
+        source
+            .add_favorite(&sample_video("exported"))
+            .expect("add favorite");
+        let export = tmp.path().join("export.sqlite");
+        source
+            .export_to(export.to_str().expect("utf8"))
+            .expect("export");
 
-        let imported = Database::new(tmp.path().join("imported.sqlite"));
-        imported.init().expect("import target init");
-        imported
-            .import_from(template_path.to_str().expect("template path utf8"))
-            .expect("import template");
+        let live = tmp.path().join("db.sqlite");
+        let db = Database::new(&live);
+        db.init().expect("db init");
+        db.add_favorite(&sample_video("local"))
+            .expect("add favorite");
+        db.flush().expect("flush");
 
-        let favorites = imported.list_favorites().expect("list favorites");
+        // A copy cut short by a killed process: the live file must be untouched and the
+        // leftover removed on the next open.
+        let interrupted = tmp.path().join("db.sqlite.import-1700000000000.tmp");
+        fs::write(&interrupted, b"SQLite format 3\0partial").expect("write partial copy");
+        let unrelated = tmp.path().join("notes.import-1.tmp");
+        fs::write(&unrelated, b"keep").expect("write unrelated file");
+        let reopened = Database::new(&live);
+        reopened.init().expect("reopen");
+        assert!(!interrupted.exists());
+        assert!(unrelated.exists());
+        let favorites = reopened.list_favorites().expect("list favorites");
         assert_eq!(favorites.len(), 1);
-        assert_eq!(favorites[0].video_id, "video-42");
+        assert_eq!(favorites[0].video_id, "local");
+
+        assert!(reopened
+            .import_from(export.to_str().expect("utf8"), true, false)
+            .expect("import"));
+        let favorites = reopened.list_favorites().expect("list favorites");
+        assert_eq!(favorites.len(), 1);
+        assert_eq!(favorites[0].video_id, "exported");
+        let leftovers: Vec<_> = fs::read_dir(tmp.path())
+            .expect("read dir")
+            .flatten()
+            .filter(|entry| {
+                entry
+                    .file_name()
+                    .to_string_lossy()
+                    .starts_with("db.sqlite.import-")
+            })
+            .map(|entry| entry.file_name())
+            .collect();
+        assert!(leftovers.is_empty(), "{leftovers:?}");
     }
 
     #[test]
-    fn export_and_import_roundtrip() {
+    fn evict_resolved_keeps_favorite_metadata() {
         let tmp = tempdir().expect("tmpdir");
-        let src = Database::new(tmp.path().join("src.sqlite"));
-        src.init().expect("src init");
-        src.add_favorite(&sample_video("video-2"))
-            .expect("add favorite");
-
-        let export_path = tmp.path().join("exports/backup.sqlite");
-        src.export_to(export_path.to_str().expect("export path utf8"))
-            .expect("export");
+        let db = Database::new(tmp.path().join("db.sqlite"));
+        db.init().expect("db init");
+        let video = sample_video("video-1");
+        db.add_favorite(&video).expect("add favorite");
+        db.cache_resolved_video(
+            &video.page_url,
+            &ResolvedVideo {
+                id: "video-1".to_string(),
+                title: "Sample".to_string(),
+                page_url: video.page_url.clone(),
+                stream_url: "https://cdn.example.com/1.mp4".to_string(),
+                thumbnail_url: None,
+                author_name: None,
+                extractor: None,
+                duration_seconds: None,
+                view_count: None,
+                upload_date_epoch: None,
+                description: None,
+                stream_protocol: None,
+                thumbnails: Vec::new(),
+            },
+        )
+        .expect("cache resolved");
+        assert!(db
+            .get_cached_resolved_video(&video.page_url, 60)
+            .expect("lookup")
+            .is_some());
 
-        let imported = Database::new(tmp.path().join("dst.sqlite"));
-        imported.init().expect("dst init");
-        imported
-            .import_from(export_path.to_str().expect("export path utf8"))
-            .expect("import");
+        assert!(db.evict_resolved(&video.page_url).expect("evict"));
+        assert!(!db.evict_resolved(&video.page_url).expect("evict again"));
+        assert!(db
+            .get_cached_resolved_video(&video.page_url, 60)
+            .expect("lookup after evict")
+            .is_none());
 
-        let favorites = imported.list_favorites().expect("list favorites");
+        let favorites = db.list_favorite_videos().expect("list favorites");
         assert_eq!(favorites.len(), 1);
-        assert_eq!(favorites[0].video_id, "video-2");
+        assert_eq!(favorites[0].id, "video-1");
+        assert_eq!(favorites[0].title, "Sample");
     }
 
     #[test]
-    fn server_preferences_roundtrip() {
+    fn activity_log_reads_newest_first_and_trims_to_cap() {
         let tmp = tempdir().expect("tmpdir");
-        let db = Database::new(tmp.path().join("servers.sqlite"));
+        let clock = FakeClock::new(
+            DateTime::parse_from_rfc3339("2025-03-01T12:00:00Z")
+                .expect("start time")
+                .with_timezone(&Utc),
+        );
+        let db = Database::new(tmp.path().join("activity.sqlite")).with_clock(clock.clone());
         db.init().expect("db init");
 
-        db.upsert_server(&SourceServer {
-            base_url: "https://getfigleaf.com".to_string(),
-            title: "Fig Leaf".to_string(),
-            color: Some("#478003".to_string()),
-            icon_url: Some("https://cdn.example.com/figleaf.png".to_string()),
-        })
-        .expect("upsert server");
-        db.upsert_server(&SourceServer {
-            base_url: "https://example.com".to_string(),
-            title: "Example".to_string(),
-            color: None,
-            icon_url: None,
-        })
-        .expect("upsert second server");
+        db.record_activity("search", "cats").expect("record search");
+        clock.advance(chrono::Duration::seconds(5));
+        db.record_activity("resolve", "https://example.com/v/1")
+            .expect("record resolve");
+        db.record_activity("favorite_added", "video-1")
+            .expect("record favorite");
 
-        let servers = db.list_servers().expect("list servers");
-        assert_eq!(servers.len(), 2);
-        assert!(servers.iter().any(|server| {
-            server.base_url == "https://getfigleaf.com"
-                && server.title == "Fig Leaf"
-                && server.color.as_deref() == Some("#478003")
-                && server.icon_url.as_deref() == Some("https://cdn.example.com/figleaf.png")
-        }));
+        let entries = db.recent_activity(10).expect("recent activity");
+        let kinds: Vec<&str> = entries.iter().map(|entry| entry.kind.as_str()).collect();
+        assert_eq!(kinds, ["favorite_added", "resolve", "search"]);
+        assert_eq!(entries[0].detail, "video-1");
+        assert_eq!(
+            entries[2].recorded_at_epoch + 5,
+            entries[0].recorded_at_epoch
+        );
+        assert_eq!(db.recent_activity(1).expect("limited").len(), 1);
 
-        let removed = db
-            .remove_server("https://example.com")
-            .expect("remove server");
-        assert!(removed);
-        assert_eq!(db.list_servers().expect("list servers").len(), 1);
+        db.append_activity("search", "dogs", 2)
+            .expect("capped insert");
+        let details: Vec<String> = db
+            .recent_activity(10)
+            .expect("after trim")
+            .into_iter()
+            .map(|entry| entry.detail)
+            .collect();
+        assert_eq!(details, ["dogs", "video-1"]);
+
+        assert_eq!(db.clear_activity_log().expect("clear"), 2);
+        assert!(db.recent_activity(10).expect("after clear").is_empty());
     }
 
     #[test]
-    fn reset_all_data_clears_tables() {
+    fn resolved_cache_expires_when_fake_clock_advances() {
         let tmp = tempdir().expect("tmpdir");
-        let db = Database::new(tmp.path().join("reset.sqlite"));
+        let clock = FakeClock::new(
+            DateTime::parse_from_rfc3339("2025-03-01T12:00:00Z")
+                .expect("start time")
+                .with_timezone(&Utc),
+        );
+        let db = Database::new(tmp.path().join("ttl.sqlite")).with_clock(clock.clone());
         db.init().expect("db init");
 
-        db.add_favorite(&sample_video("video-reset"))
-            .expect("add favorite");
-        db.set_meta("settings.theme", "dark").expect("set meta");
-        db.upsert_server(&SourceServer {
-            base_url: "https://getfigleaf.com".to_string(),
-            title: "Fig Leaf".to_string(),
-            color: None,
-            icon_url: None,
-        })
-        .expect("set server");
+        let page_url = "https://example.com/v/ttl";
+        db.cache_resolved_video(
+            page_url,
+            &ResolvedVideo {
+                id: "ttl".to_string(),
+                title: "TTL".to_string(),
+                page_url: page_url.to_string(),
+                stream_url: "https://cdn.example.com/ttl.mp4".to_string(),
+                thumbnail_url: None,
+                author_name: None,
+                extractor: None,
+                duration_seconds: None,
+                view_count: None,
+                upload_date_epoch: None,
+                description: None,
+                stream_protocol: None,
+                thumbnails: Vec::new(),
+            },
+        )
+        .expect("cache resolved");
 
-        db.reset_all_data().expect("reset all");
+        clock.advance(chrono::Duration::seconds(60));
+        assert!(db
+            .get_cached_resolved_video(page_url, 60)
+            .expect("get cached")
+            .is_some());
+        assert!(db.is_resolved_cached(page_url, 60).expect("cached check"));
 
-        let conn = Connection::open(db.path()).expect("open db");
-        let count_video_details: i64 = conn
-            .query_row(r#"SELECT COUNT(*) FROM "video_details""#, [], |row| row.get(0))
-            .expect("count video_details");
-        let count_meta: i64 = conn
-            .query_row(r#"SELECT COUNT(*) FROM "user_preferences""#, [], |row| row.get(0))
-            .expect("count user_preferences");
-        let count_servers: i64 = conn
-            .query_row(r#"SELECT COUNT(*) FROM "server_preferences""#, [], |row| row.get(0))
-            .expect("count server_preferences");
-        assert_eq!(count_video_details, 0);
-        assert_eq!(count_meta, 0);
-        assert_eq!(count_servers, 0);
+        clock.advance(chrono::Duration::seconds(1));
+        assert!(db
+            .get_cached_resolved_video(page_url, 60)
+            .expect("get cached")
+            .is_none());
+        assert!(!db.is_resolved_cached(page_url, 60).expect("expired check"));
+        assert!(!db
+            .is_resolved_cached("https://example.com/v/other", 60)
+            .expect("uncached check"));
+        assert!(db
+            .get_cached_resolved_by_id("ttl", 60)
+            .expect("get cached by id")
+            .is_none());
     }
 }
@@ -4,6 +4,12 @@ pub enum EngineError {
     InvalidConfig { detail: String },
     #[error("network failure: {detail}")]
     Network { detail: String },
+    #[error("http status {status} at {url}: {body_snippet}")]
+    Http {
+        status: u16,
+        url: String,
+        body_snippet: String,
+    },
     #[error("database failure: {detail}")]
     Database { detail: String },
     #[error("serialization failure: {detail}")]
@@ -12,6 +18,8 @@ pub enum EngineError {
     Process { detail: String },
     #[error("not found: {detail}")]
     NotFound { detail: String },
+    #[error("cancelled: {detail}")]
+    Cancelled { detail: String },
 }
 
 impl From<rusqlite::Error> for EngineError {
@@ -1,73 +1,226 @@
 mod api;
+mod cancellation;
+mod clock;
 mod curl_cffi;
 mod db;
 mod errors;
+mod listener;
 mod models;
+mod preview;
+mod runtime;
+#[cfg(test)]
+mod test_support;
 mod updater;
 mod ytdlp;
 
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
 
-use api::ApiClient;
+use api::{configured_proxy, endpoint_method, ApiClient, NetworkCounters};
+use cancellation::CancellationToken;
+use clock::{system_clock, Clock};
+use curl_cffi::validate_bridge_env;
 use db::Database;
 use errors::EngineError;
+use listener::PreferenceListener;
 use models::{
-    BridgeHealth, EngineConfig, FavoriteItem, FilterSelection, ResolvedVideo, SourceServer,
-    StatusSummary, UserPreference, VideoItem, YtDlpUpdateInfo,
+    ActivityEntry, BootReport, BridgeHealth, Dashboard, DatabaseBackup, DiscoverOptions,
+    DiscoverPage, EngineConfig, FavoriteItem, FilterSelection, ImportReport, LinkPreview,
+    MergeReport, MigrationStats, NetworkCount, NetworkStats, ProbeResult, ResetOptions,
+    ResetReport, ResolvedOrError, ResolvedVideo, ServerLatency, SourceServer, StatusChannel,
+    StatusSummary, UserPreference, VideoItem, YtDlpUpdateInfo, YtDlpUpdateResult,
 };
 use updater::{check_yt_dlp_update, default_release_api};
-use ytdlp::YtDlpClient;
+use ytdlp::{parse_update_output, validate_extra_args, YtDlpClient};
 
 uniffi::setup_scaffolding!();
 
+const ACTIVE_CHANNEL_KEY: &str = "active_channel";
+const STATUS_CACHE_PREFIX: &str = "status_cache:";
+const PREFERRED_API_BASE_KEY: &str = "preferred_api_base";
+const NETWORK_REQUESTS_KEY: &str = "network_requests_total";
+const NETWORK_BYTES_KEY: &str = "network_bytes_total";
+const RESOLVED_CACHE_TTL_SECONDS: i64 = 60 * 60 * 6;
+// How long a resolve waits on its cache write before handing the stream back uncached.
+const RESOLVE_CACHE_WRITE_TIMEOUT: Duration = Duration::from_secs(2);
+// How long a resolve waits for the connection to check its cache before treating it as a miss.
+const RESOLVE_CACHE_READ_TIMEOUT: Duration = Duration::from_millis(500);
+
+struct DiscoverRequest<'a> {
+    query: &'a str,
+    page: u32,
+    limit: u32,
+    channel_id: &'a str,
+    filters: &'a [FilterSelection],
+    extra_params: Option<&'a HashMap<String, String>>,
+}
+
 #[derive(uniffi::Object)]
 pub struct Engine {
     config: EngineConfig,
     db: Database,
     api: ApiClient,
     yt_dlp: YtDlpClient,
+    boot_report: BootReport,
+    offline: AtomicBool,
+    last_discover: Mutex<Option<Instant>>,
+    clock: Arc<dyn Clock>,
+    preference_listener: Mutex<Option<Arc<dyn PreferenceListener>>>,
 }
 
 #[uniffi::export]
 impl Engine {
     #[uniffi::constructor]
     pub fn new(config: EngineConfig) -> Result<Arc<Self>, EngineError> {
-        validate_config(&config)?;
-
-        let db = Database::new(&config.db_path);
-        db.init()?;
+        let (engine, _) = Self::new_with_report(config)?;
+        Ok(engine)
+    }
 
-        let engine = Arc::new(Self {
-            api: ApiClient::new(&config),
-            yt_dlp: YtDlpClient::new(config.yt_dlp_path.clone(), config.python_executable.clone()),
-            db,
-            config,
-        });
+    pub fn boot_report(&self) -> BootReport {
+        self.boot_report.clone()
+    }
 
-        // Boot-time update check; errors are persisted and surfaced through bridge health.
-        if let Err(err) = engine.sync_boot_metadata() {
-            let _ = engine.db.set_meta("boot_error", &err.to_string());
-        }
+    pub fn set_offline(&self, offline: bool) {
+        self.offline.store(offline, Ordering::SeqCst);
+    }
 
-        Ok(engine)
+    pub fn is_offline(&self) -> bool {
+        self.offline.load(Ordering::SeqCst)
     }
 
     pub fn sync_status(&self) -> Result<StatusSummary, EngineError> {
-        let status = self.api.fetch_status()?;
+        self.ensure_online()?;
+        let api = self.source_api()?;
+        let status = self.persisting_network_stats(api.fetch_status())?;
+        self.remember_api_base(&api)?;
         self.db.sync_categories(&status.sources)?;
+        self.db.set_meta(
+            &status_cache_key(api.base_url()),
+            &serde_json::to_string(&status)?,
+        )?;
         Ok(status)
     }
 
+    // Answers from the status cached by `sync_status` (or an earlier call here); only a server
+    // with no cached status is fetched.
+    pub fn adblock_required(&self, base_url: String) -> Result<bool, EngineError> {
+        if let Some(status) = self.last_known_status(base_url.clone())? {
+            return Ok(status.adblock_required);
+        }
+        self.ensure_online()?;
+        let status =
+            self.persisting_network_stats(self.client_for_server(&base_url)?.fetch_status())?;
+        self.db.set_meta(
+            &status_cache_key(&base_url),
+            &serde_json::to_string(&status)?,
+        )?;
+        Ok(status.adblock_required)
+    }
+
+    pub fn channel_details(&self, channel_id: String) -> Result<StatusChannel, EngineError> {
+        self.ensure_online()?;
+        let api = self.source_api()?;
+        let status = self.persisting_network_stats(api.fetch_status())?;
+        self.remember_api_base(&api)?;
+        let channel_id = channel_id.trim();
+        status
+            .channel_details
+            .into_iter()
+            .find(|channel| channel.id == channel_id)
+            .ok_or_else(|| EngineError::NotFound {
+                detail: format!("channel {channel_id} not returned by /api/status"),
+            })
+    }
+
+    pub fn last_known_status(
+        &self,
+        base_url: String,
+    ) -> Result<Option<StatusSummary>, EngineError> {
+        Ok(self
+            .db
+            .get_meta(&status_cache_key(&base_url))?
+            .and_then(|payload| serde_json::from_str(&payload).ok()))
+    }
+
     pub fn probe_status(&self, api_base_url: String) -> Result<StatusSummary, EngineError> {
-        let normalized = api_base_url.trim().trim_end_matches('/').to_string();
-        if normalized.is_empty() {
+        self.ensure_online()?;
+        self.persisting_network_stats(self.client_for_server(&api_base_url)?.fetch_status())
+    }
+
+    // For a settings "test" button: only a blank or malformed URL is an Err; offline, network, HTTP and parse
+    // failures come back as `ok: false` with the error text.
+    pub fn probe_status_result(&self, api_base_url: String) -> Result<ProbeResult, EngineError> {
+        let client = self.client_for_server(&api_base_url)?;
+        let started = Instant::now();
+        let result = self
+            .persisting_network_stats(self.ensure_online().and_then(|()| client.fetch_status()));
+        let latency_ms = started.elapsed().as_millis().try_into().unwrap_or(u64::MAX);
+        Ok(match result {
+            Ok(status) => ProbeResult {
+                ok: true,
+                status: Some(status),
+                error: None,
+                latency_ms,
+            },
+            Err(err) => ProbeResult {
+                ok: false,
+                status: None,
+                error: Some(err.to_string()),
+                latency_ms,
+            },
+        })
+    }
+
+    // Custom server headers are not sent: the URL is arbitrary, not one of our sources.
+    pub fn preview_url(&self, url: String) -> Result<LinkPreview, EngineError> {
+        self.ensure_online()?;
+        let url = url.trim();
+        let is_web_url = reqwest::Url::parse(url)
+            .map(|parsed| matches!(parsed.scheme(), "http" | "https"))
+            .unwrap_or(false);
+        if !is_web_url {
             return Err(EngineError::InvalidConfig {
-                detail: "api_base_url cannot be empty".to_string(),
+                detail: format!("cannot preview non-http url: {url}"),
             });
         }
-        let mut config = self.config.clone();
-        config.api_base_url = normalized;
-        ApiClient::new(&config).fetch_status()
+        self.persisting_network_stats(self.api.fetch_link_preview(url))
+    }
+
+    pub fn measure_server(&self, base_url: String) -> Result<ServerLatency, EngineError> {
+        self.ensure_online()?;
+        let latency = self.client_for_server(&base_url)?.measure_latency();
+        self.persisting_network_stats(Ok(latency))
+    }
+
+    pub fn discover_videos_on_server(
+        &self,
+        base_url: String,
+        query: String,
+        page: u32,
+        limit: u32,
+    ) -> Result<Vec<VideoItem>, EngineError> {
+        // Validated up front so a bad URL fails the same way online and offline.
+        let client = self.client_for_server(&base_url)?;
+        let query = normalize_query(&query);
+        if self.is_offline() {
+            return self.search_cache(&query, page, limit);
+        }
+        self.wait_for_discover_slot()?;
+        let videos = self.persisting_network_stats(client.discover_videos_with_filters(
+            &query,
+            page,
+            limit,
+            None,
+            &[],
+            None,
+        ))?;
+        self.db.cache_videos(&videos)?;
+        self.db.record_search(&query)?;
+        self.db.record_activity("search", &query)?;
+        Ok(videos)
     }
 
     pub fn discover_videos(
@@ -76,7 +229,23 @@ impl Engine {
         page: u32,
         limit: u32,
     ) -> Result<Vec<VideoItem>, EngineError> {
-        self.discover_videos_with_filters(query, page, limit, String::new(), vec![])
+        let channel_id = self.get_active_channel()?.unwrap_or_default();
+        self.discover_videos_with_filters(query, page, limit, channel_id, vec![])
+    }
+
+    pub fn set_active_channel(&self, channel_id: String) -> Result<bool, EngineError> {
+        self.notifying_preference_changes(|| match non_empty(&channel_id) {
+            Some(channel_id) => self.db.set_meta(ACTIVE_CHANNEL_KEY, channel_id),
+            None => self.db.remove_meta(ACTIVE_CHANNEL_KEY).map(|_| ()),
+        })?;
+        Ok(true)
+    }
+
+    pub fn get_active_channel(&self) -> Result<Option<String>, EngineError> {
+        Ok(self
+            .db
+            .get_meta(ACTIVE_CHANNEL_KEY)?
+            .filter(|channel_id| !channel_id.trim().is_empty()))
     }
 
     pub fn discover_videos_with_filters(
@@ -87,51 +256,394 @@ impl Engine {
         channel_id: String,
         filters: Vec<FilterSelection>,
     ) -> Result<Vec<VideoItem>, EngineError> {
-        let channel = non_empty(&channel_id);
-        let videos = self
-            .api
-            .discover_videos_with_filters(&query, page, limit, channel, &filters)?;
-        self.db.cache_videos(&videos)?;
-        self.db.record_search(&query)?;
-        Ok(videos)
+        self.run_discover(
+            DiscoverRequest {
+                query: &query,
+                page,
+                limit,
+                channel_id: &channel_id,
+                filters: &filters,
+                extra_params: None,
+            },
+            &DiscoverOptions::default(),
+            None,
+        )
+    }
+
+    // Server-specific knobs (e.g. a hidden `region`) sent alongside the declared options; they
+    // never replace a declared option or the fixed query/page fields.
+    pub fn discover_videos_with_params(
+        &self,
+        query: String,
+        page: u32,
+        limit: u32,
+        channel_id: String,
+        filters: Vec<FilterSelection>,
+        extra_params: HashMap<String, String>,
+    ) -> Result<Vec<VideoItem>, EngineError> {
+        self.run_discover(
+            DiscoverRequest {
+                query: &query,
+                page,
+                limit,
+                channel_id: &channel_id,
+                filters: &filters,
+                extra_params: Some(&extra_params),
+            },
+            &DiscoverOptions::default(),
+            None,
+        )
+    }
+
+    pub fn default_selections(
+        &self,
+        channel_id: String,
+    ) -> Result<Vec<FilterSelection>, EngineError> {
+        self.ensure_online()?;
+        let api = self.source_api()?;
+        let selections =
+            self.persisting_network_stats(api.default_selections(non_empty(&channel_id)))?;
+        self.remember_api_base(&api)?;
+        Ok(selections)
+    }
+
+    pub fn discover_page(
+        &self,
+        query: String,
+        page: u32,
+        limit: u32,
+        channel_id: String,
+        filters: Vec<FilterSelection>,
+    ) -> Result<DiscoverPage, EngineError> {
+        self.run_discover_page(
+            DiscoverRequest {
+                query: &query,
+                page,
+                limit,
+                channel_id: &channel_id,
+                filters: &filters,
+                extra_params: None,
+            },
+            &DiscoverOptions::default(),
+            None,
+        )
+    }
+
+    pub fn discover_videos_with_options(
+        &self,
+        query: String,
+        page: u32,
+        limit: u32,
+        channel_id: String,
+        filters: Vec<FilterSelection>,
+        options: DiscoverOptions,
+    ) -> Result<Vec<VideoItem>, EngineError> {
+        self.run_discover(
+            DiscoverRequest {
+                query: &query,
+                page,
+                limit,
+                channel_id: &channel_id,
+                filters: &filters,
+                extra_params: None,
+            },
+            &options,
+            None,
+        )
+    }
+
+    pub fn discover_videos_cancellable(
+        &self,
+        query: String,
+        page: u32,
+        limit: u32,
+        channel_id: String,
+        filters: Vec<FilterSelection>,
+        token: Arc<CancellationToken>,
+    ) -> Result<Vec<VideoItem>, EngineError> {
+        self.run_discover(
+            DiscoverRequest {
+                query: &query,
+                page,
+                limit,
+                channel_id: &channel_id,
+                filters: &filters,
+                extra_params: None,
+            },
+            &DiscoverOptions::default(),
+            Some(&token),
+        )
     }
 
     pub fn resolve_stream(&self, page_url: String) -> Result<ResolvedVideo, EngineError> {
-        if let Some(cached) = self.db.get_cached_resolved_video(&page_url, 60 * 60 * 6)? {
-            return Ok(cached);
+        self.resolve_with_token(&page_url, None)
+    }
+
+    pub fn resolve_stream_from_item(&self, video: VideoItem) -> Result<ResolvedVideo, EngineError> {
+        if video.page_url.trim().is_empty() {
+            return Err(EngineError::InvalidConfig {
+                detail: format!("video {} has no page_url to resolve", video.id),
+            });
         }
+        let resolved = self.resolve_with_token(&video.page_url, None)?;
+        Ok(fill_from_item(resolved, &video))
+    }
 
-        let resolved = self.yt_dlp.extract_stream(&page_url)?;
-        self.db.cache_resolved_video(&page_url, &resolved)?;
-        Ok(resolved)
+    // Sequential on purpose: each URL may spawn a yt-dlp process, and a failure only marks its entry.
+    pub fn resolve_streams(
+        &self,
+        page_urls: Vec<String>,
+    ) -> Result<Vec<ResolvedOrError>, EngineError> {
+        Ok(page_urls
+            .into_iter()
+            .map(|page_url| match self.resolve_with_token(&page_url, None) {
+                Ok(video) => ResolvedOrError::Resolved { video },
+                Err(err) => ResolvedOrError::Failed {
+                    page_url,
+                    error: err.to_string(),
+                },
+            })
+            .collect())
+    }
+
+    // Meant for a host background thread: fresh entries are skipped, failures are only not counted,
+    // and each extraction waits for the shared rate-limit slot.
+    pub fn prefetch_resolve(&self, page_urls: Vec<String>) -> Result<u64, EngineError> {
+        self.ensure_online()?;
+        let mut warmed = 0;
+        for page_url in page_urls {
+            if self
+                .db
+                .is_resolved_cached(&page_url, RESOLVED_CACHE_TTL_SECONDS)?
+            {
+                continue;
+            }
+            self.wait_for_discover_slot()?;
+            if self.resolve_with_token(&page_url, None).is_ok() {
+                warmed += 1;
+            }
+        }
+        Ok(warmed)
+    }
+
+    pub fn resolve_stream_quality(
+        &self,
+        page_url: String,
+        max_height: u32,
+    ) -> Result<ResolvedVideo, EngineError> {
+        // Quality-specific picks bypass the resolved cache, which holds the default stream.
+        self.ensure_online()?;
+        self.check_resolve_host(&page_url)?;
+        self.yt_dlp
+            .extract_stream_with_height(&page_url, Some(max_height), None)
+    }
+
+    pub fn yt_dlp_supports(&self, url: String) -> Result<bool, EngineError> {
+        self.ensure_online()?;
+        self.yt_dlp.supports_url(&url, None)
+    }
+
+    pub fn resolve_stream_cancellable(
+        &self,
+        page_url: String,
+        token: Arc<CancellationToken>,
+    ) -> Result<ResolvedVideo, EngineError> {
+        self.resolve_with_token(&page_url, Some(&token))
+    }
+
+    pub fn is_resolved_cached(
+        &self,
+        page_url: String,
+        max_age_seconds: i64,
+    ) -> Result<bool, EngineError> {
+        self.db.is_resolved_cached(&page_url, max_age_seconds)
+    }
+
+    pub fn get_cached_resolved_by_id(
+        &self,
+        video_id: String,
+        max_age_seconds: i64,
+    ) -> Result<Option<ResolvedVideo>, EngineError> {
+        self.db
+            .get_cached_resolved_by_id(&video_id, max_age_seconds)
+    }
+
+    pub fn evict_resolved(&self, page_url: String) -> Result<bool, EngineError> {
+        self.db.evict_resolved(&page_url)
+    }
+
+    // 403/404/410 from the CDN mean the signed stream URL expired; anything else is treated as
+    // transient and the cached stream is handed back unchanged, never re-extracted.
+    pub fn report_playback_failure(
+        &self,
+        page_url: String,
+        http_status: u16,
+    ) -> Result<ResolvedVideo, EngineError> {
+        if !matches!(http_status, 403 | 404 | 410) {
+            return self
+                .db
+                .get_cached_resolved_video(&page_url, RESOLVED_CACHE_TTL_SECONDS)?
+                .ok_or_else(|| EngineError::NotFound {
+                    detail: format!("no cached stream for {page_url}"),
+                });
+        }
+        self.ensure_online()?;
+        self.db.evict_resolved(&page_url)?;
+        self.resolve_with_token(&page_url, None)
     }
 
     pub fn list_favorites(&self) -> Result<Vec<FavoriteItem>, EngineError> {
         self.db.list_favorites()
     }
 
+    pub fn favorites_changed_since(
+        &self,
+        epoch_seconds: i64,
+    ) -> Result<Vec<FavoriteItem>, EngineError> {
+        self.db.favorites_changed_since(epoch_seconds)
+    }
+
     pub fn list_favorite_videos(&self) -> Result<Vec<VideoItem>, EngineError> {
         self.db.list_favorite_videos()
     }
 
     pub fn add_favorite(&self, video: VideoItem) -> Result<FavoriteItem, EngineError> {
-        self.db.add_favorite(&video)
+        let favorite = self.db.add_favorite(&video)?;
+        self.db.record_activity("favorite_added", &video.id)?;
+        Ok(favorite)
+    }
+
+    pub fn enrich_favorite(&self, video_id: String) -> Result<bool, EngineError> {
+        self.db.enrich_favorite(&video_id)
+    }
+
+    pub fn set_favorite_position(
+        &self,
+        video_id: String,
+        position: i64,
+    ) -> Result<bool, EngineError> {
+        self.db.set_favorite_position(&video_id, position)
+    }
+
+    pub fn set_rating(&self, video_id: String, rating: f64) -> Result<bool, EngineError> {
+        self.db.set_rating(&video_id, rating)
+    }
+
+    pub fn list_favorites_by_rating(&self) -> Result<Vec<FavoriteItem>, EngineError> {
+        self.db.list_favorites_by_rating()
+    }
+
+    pub fn set_video_tags(&self, video_id: String, tags: Vec<String>) -> Result<bool, EngineError> {
+        self.db.set_video_tags(&video_id, &tags)
+    }
+
+    pub fn get_video_tags(&self, video_id: String) -> Result<Vec<String>, EngineError> {
+        self.db.get_video_tags(&video_id)
+    }
+
+    pub fn list_favorites_by_tag(&self, tag: String) -> Result<Vec<FavoriteItem>, EngineError> {
+        self.db.list_favorites_by_tag(&tag)
     }
 
     pub fn remove_favorite(&self, video_id: String) -> Result<bool, EngineError> {
         self.db.remove_favorite(&video_id)
     }
 
+    pub fn export_favorites_json(&self) -> Result<String, EngineError> {
+        Ok(serde_json::to_string(
+            &self.db.list_stored_favorite_videos()?,
+        )?)
+    }
+
+    pub fn import_favorites_json(&self, json: String) -> Result<u64, EngineError> {
+        let videos: Vec<VideoItem> = serde_json::from_str::<Vec<VideoItem>>(&json)?
+            .into_iter()
+            .filter(|video| !video.id.trim().is_empty())
+            .collect();
+        self.db.add_favorites(&videos)
+    }
+
+    pub fn remove_favorites(&self, video_ids: Vec<String>) -> Result<u64, EngineError> {
+        self.db.remove_favorites(&video_ids)
+    }
+
+    pub fn get_raw_video(&self, video_id: String) -> Result<Option<String>, EngineError> {
+        self.db.get_raw_video(&video_id)
+    }
+
+    pub fn list_networks(&self) -> Result<Vec<NetworkCount>, EngineError> {
+        self.db.list_networks()
+    }
+
+    // Hosts call this when backgrounded and before `export_database` or moving the file;
+    // the next engine call reopens the connection.
+    pub fn flush(&self) -> Result<(), EngineError> {
+        self.persist_network_stats()?;
+        self.db.flush()
+    }
+
+    // Totals since the last reset, surviving restarts; meta is updated after every API call.
+    pub fn network_stats(&self) -> Result<NetworkStats, EngineError> {
+        self.persist_network_stats()
+    }
+
+    pub fn reset_network_stats(&self) -> Result<(), EngineError> {
+        self.api.network_counters().reset();
+        self.db.remove_meta(NETWORK_REQUESTS_KEY)?;
+        self.db.remove_meta(NETWORK_BYTES_KEY)?;
+        Ok(())
+    }
+
     pub fn export_database(&self, export_path: String) -> Result<bool, EngineError> {
         self.db.export_to(&export_path)
     }
 
+    pub fn export_database_bytes(&self) -> Result<Vec<u8>, EngineError> {
+        self.db.export_bytes()
+    }
+
     pub fn import_database(&self, import_path: String) -> Result<bool, EngineError> {
-        self.db.import_from(&import_path)
+        self.notifying_preference_changes(|| {
+            self.db
+                .import_from(&import_path, true, self.config.backup_before_import)
+        })
+    }
+
+    pub fn merge_import(&self, import_path: String) -> Result<MergeReport, EngineError> {
+        self.notifying_preference_changes(|| self.db.merge_from(&import_path))
+    }
+
+    pub fn import_database_bytes(&self, bytes: Vec<u8>) -> Result<bool, EngineError> {
+        self.notifying_preference_changes(|| {
+            self.db
+                .import_bytes(&bytes, self.config.backup_before_import)
+        })
+    }
+
+    pub fn list_backups(&self) -> Result<Vec<DatabaseBackup>, EngineError> {
+        self.db.list_backups()
+    }
+
+    pub fn restore_backup(&self, backup_path: String) -> Result<bool, EngineError> {
+        self.notifying_preference_changes(|| {
+            self.db
+                .restore_backup(&backup_path, self.config.backup_before_import)
+        })
+    }
+
+    pub fn validate_import(&self, import_path: String) -> Result<ImportReport, EngineError> {
+        self.db.validate_import(&import_path)
+    }
+
+    pub fn set_preference_listener(&self, listener: Option<Arc<dyn PreferenceListener>>) {
+        if let Ok(mut current) = self.preference_listener.lock() {
+            *current = listener;
+        }
     }
 
     pub fn set_user_preference(&self, key: String, value: String) -> Result<bool, EngineError> {
         self.db.set_meta(&key, &value)?;
+        self.notify_preference_changed(key, value);
         Ok(true)
     }
 
@@ -139,6 +651,30 @@ impl Engine {
         self.db.get_meta(&key)
     }
 
+    pub fn get_bool_preference(&self, key: String, default: bool) -> Result<bool, EngineError> {
+        Ok(self
+            .db
+            .get_meta(&key)?
+            .and_then(|value| parse_bool_preference(&value))
+            .unwrap_or(default))
+    }
+
+    pub fn set_bool_preference(&self, key: String, value: bool) -> Result<bool, EngineError> {
+        self.set_user_preference(key, value.to_string())
+    }
+
+    pub fn get_int_preference(&self, key: String, default: i64) -> Result<i64, EngineError> {
+        Ok(self
+            .db
+            .get_meta(&key)?
+            .and_then(|value| value.trim().parse().ok())
+            .unwrap_or(default))
+    }
+
+    pub fn set_int_preference(&self, key: String, value: i64) -> Result<bool, EngineError> {
+        self.set_user_preference(key, value.to_string())
+    }
+
     pub fn list_user_preferences(&self, prefix: String) -> Result<Vec<UserPreference>, EngineError> {
         let values = self.db.list_meta_with_prefix(&prefix)?;
         Ok(values
@@ -150,6 +686,17 @@ impl Engine {
             .collect())
     }
 
+    pub fn list_all_preferences(&self) -> Result<Vec<UserPreference>, EngineError> {
+        let values = self.db.list_all_meta()?;
+        Ok(values
+            .into_iter()
+            .map(|(id, preference_value)| UserPreference {
+                id,
+                preference_value,
+            })
+            .collect())
+    }
+
     pub fn upsert_source_server(&self, server: SourceServer) -> Result<bool, EngineError> {
         self.db.upsert_server(&server)?;
         Ok(true)
@@ -163,10 +710,42 @@ impl Engine {
         self.db.list_servers()
     }
 
+    pub fn seed_data(
+        &self,
+        categories: Vec<String>,
+        searches: Vec<String>,
+    ) -> Result<(), EngineError> {
+        self.db.seed_data(&categories, &searches)
+    }
+
+    pub fn decay_category_clicks(&self, factor: f64) -> Result<(), EngineError> {
+        self.db.decay_category_clicks(factor)
+    }
+
+    pub fn reset_category_clicks(&self) -> Result<(), EngineError> {
+        self.db.reset_category_clicks()
+    }
+
     pub fn clear_cache_data(&self) -> Result<u64, EngineError> {
         self.db.clear_cache_data()
     }
 
+    pub fn get_video(&self, video_id: String) -> Result<Option<VideoItem>, EngineError> {
+        self.db.get_video(&video_id)
+    }
+
+    pub fn record_watch(&self, video_id: String) -> Result<bool, EngineError> {
+        self.db.record_watch(&video_id)
+    }
+
+    pub fn user_views(&self, video_id: String) -> Result<u64, EngineError> {
+        self.db.user_views(&video_id)
+    }
+
+    pub fn most_rewatched(&self, limit: u32) -> Result<Vec<VideoItem>, EngineError> {
+        self.db.most_rewatched(limit)
+    }
+
     pub fn clear_watch_history(&self) -> Result<u64, EngineError> {
         self.db.clear_watch_history()
     }
@@ -175,16 +754,46 @@ impl Engine {
         self.db.clear_favorites()
     }
 
+    pub fn list_cached_by_network(
+        &self,
+        network: String,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<VideoItem>, EngineError> {
+        self.db.list_cached_by_network(&network, offset, limit)
+    }
+
+    pub fn clear_favorites_for_network(&self, network: String) -> Result<u64, EngineError> {
+        self.db.clear_favorites_for_network(&network)
+    }
+
+    pub fn backfill_favorite_metadata(&self) -> Result<u64, EngineError> {
+        self.db.backfill_favorite_metadata()
+    }
+
     pub fn clear_achievements(&self) -> Result<u64, EngineError> {
         self.db.clear_achievements()
     }
 
+    pub fn recent_activity(&self, limit: u32) -> Result<Vec<ActivityEntry>, EngineError> {
+        self.db.recent_activity(limit)
+    }
+
+    pub fn clear_activity_log(&self) -> Result<u64, EngineError> {
+        self.db.clear_activity_log()
+    }
+
+    pub fn reset_selective(&self, options: ResetOptions) -> Result<ResetReport, EngineError> {
+        self.notifying_preference_changes(|| self.db.reset_selective(&options))
+    }
+
     pub fn reset_all_data(&self) -> Result<bool, EngineError> {
-        self.db.reset_all_data()?;
+        self.notifying_preference_changes(|| self.db.reset_all_data())?;
         Ok(true)
     }
 
     pub fn check_yt_dlp_update(&self) -> Result<YtDlpUpdateInfo, EngineError> {
+        self.ensure_online()?;
         let release_api = self
             .config
             .yt_dlp_repo_api
@@ -192,7 +801,12 @@ impl Engine {
             .unwrap_or(default_release_api());
 
         let current = self.yt_dlp.current_version().ok();
-        let update = check_yt_dlp_update(release_api, current)?;
+        let update = check_yt_dlp_update(
+            release_api,
+            current,
+            configured_proxy(&self.config),
+            self.clock.as_ref(),
+        )?;
 
         if let Some(current) = &update.current_version {
             self.db.set_meta("yt_dlp_current", current)?;
@@ -209,23 +823,398 @@ impl Engine {
     }
 
     pub fn run_yt_dlp_update(&self) -> Result<String, EngineError> {
+        self.ensure_online()?;
         let output = self.yt_dlp.update_binary()?;
         self.db.set_meta("yt_dlp_last_update_output", &output)?;
+        self.db.record_activity("yt_dlp_update", output.trim())?;
         Ok(output)
     }
 
+    pub fn run_yt_dlp_update_typed(&self) -> Result<YtDlpUpdateResult, EngineError> {
+        self.ensure_online()?;
+        let before = self.yt_dlp.current_version().ok();
+        let output = self.yt_dlp.update_binary()?;
+        self.db.set_meta("yt_dlp_last_update_output", &output)?;
+        let after = self.yt_dlp.current_version().ok();
+
+        let mut result = parse_update_output(&output);
+        if let (Some(before), Some(after)) = (&before, &after) {
+            result.updated |= before != after;
+        }
+        result.from_version = before.or(result.from_version);
+        result.to_version = after.or(result.to_version);
+        if let Some(current) = &result.to_version {
+            self.db.set_meta("yt_dlp_current", current)?;
+        }
+        self.db.record_activity(
+            "yt_dlp_update",
+            result.to_version.as_deref().unwrap_or_default(),
+        )?;
+        Ok(result)
+    }
+
+    pub fn migrate_cached_payloads(&self) -> Result<u64, EngineError> {
+        self.db.migrate_cached_payloads()
+    }
+
+    pub fn schema_version(&self) -> Result<u32, EngineError> {
+        self.db.schema_version()
+    }
+
+    pub fn ensure_migrated(&self) -> Result<MigrationStats, EngineError> {
+        self.db.ensure_migrated()
+    }
+
+    pub fn last_migration_stats(&self) -> Result<Option<MigrationStats>, EngineError> {
+        self.db.last_migration_stats()
+    }
+
+    pub fn dashboard(&self) -> Result<Dashboard, EngineError> {
+        self.db.dashboard()
+    }
+
     pub fn bridge_health(&self) -> Result<BridgeHealth, EngineError> {
         let last_error = self.db.get_meta("boot_error")?;
         Ok(BridgeHealth {
             engine_ready: true,
-            db_accessible: self.db.path().exists(),
+            db_accessible: self.db.is_in_memory() || self.db.path().exists(),
             last_error,
         })
     }
 }
 
 impl Engine {
-    fn sync_boot_metadata(&self) -> Result<(), EngineError> {
+    // UniFFI constructors can only return the object, so hosts read the report via `boot_report`.
+    pub fn new_with_report(config: EngineConfig) -> Result<(Arc<Self>, BootReport), EngineError> {
+        Self::new_with_clock(config, system_clock())
+    }
+
+    fn new_with_clock(
+        config: EngineConfig,
+        clock: Arc<dyn Clock>,
+    ) -> Result<(Arc<Self>, BootReport), EngineError> {
+        validate_config(&config)?;
+
+        let db = Database::new(&config.db_path)
+            .with_clock(Arc::clone(&clock))
+            .with_busy_timeout(Duration::from_millis(config.db_busy_timeout_ms))
+            .with_encryption_key(config.db_encryption_key.clone());
+        let db_migrated = db.init()?.total() > 0;
+        let offline = config.offline;
+        let stored_count = |key| -> Result<u64, EngineError> {
+            Ok(db
+                .get_meta(key)?
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(0))
+        };
+        let network = NetworkCounters::new(NetworkStats {
+            requests: stored_count(NETWORK_REQUESTS_KEY)?,
+            bytes_downloaded: stored_count(NETWORK_BYTES_KEY)?,
+        });
+        let api = ApiClient::new(&config)
+            .with_preferred_base(db.get_meta(PREFERRED_API_BASE_KEY)?)
+            .with_network_counters(Arc::new(network));
+
+        let mut engine = Self {
+            api,
+            yt_dlp: YtDlpClient::new(config.yt_dlp_path.clone(), config.python_executable.clone())
+                .with_expected_sha256(config.yt_dlp_expected_sha256.clone())
+                .with_extra_args(config.yt_dlp_extra_args.clone().unwrap_or_default()),
+            db,
+            config,
+            boot_report: BootReport {
+                db_migrated,
+                update_check_ok: false,
+                boot_error: None,
+            },
+            offline: AtomicBool::new(offline),
+            last_discover: Mutex::new(None),
+            clock,
+            preference_listener: Mutex::new(None),
+        };
+
+        // Boot-time update check; errors are persisted and surfaced through bridge health.
+        // Offline engines skip it entirely, leaving update_check_ok false.
+        if !offline {
+            match engine.sync_boot_metadata() {
+                Ok(()) => engine.boot_report.update_check_ok = true,
+                Err(err) => {
+                    let detail = err.to_string();
+                    let _ = engine.db.set_meta("boot_error", &detail);
+                    engine.boot_report.boot_error = Some(detail);
+                }
+            }
+        }
+
+        let report = engine.boot_report.clone();
+        Ok((Arc::new(engine), report))
+    }
+
+    fn run_discover(
+        &self,
+        request: DiscoverRequest<'_>,
+        options: &DiscoverOptions,
+        cancel: Option<&CancellationToken>,
+    ) -> Result<Vec<VideoItem>, EngineError> {
+        Ok(self.run_discover_page(request, options, cancel)?.videos)
+    }
+
+    fn run_discover_page(
+        &self,
+        request: DiscoverRequest<'_>,
+        options: &DiscoverOptions,
+        cancel: Option<&CancellationToken>,
+    ) -> Result<DiscoverPage, EngineError> {
+        let query = normalize_query(request.query);
+        if self.is_offline() {
+            return Ok(DiscoverPage {
+                videos: self.search_cache(&query, request.page, request.limit)?,
+                has_next_page: false,
+                recommendations: Vec::new(),
+            });
+        }
+        self.wait_for_discover_slot()?;
+        let mut api = self.source_api()?;
+        if let Some(extra_params) = request.extra_params {
+            api = api.with_extra_params(extra_params);
+        }
+        let page = self.persisting_network_stats(api.discover_page_with_filters(
+            &query,
+            request.page,
+            request.limit,
+            non_empty(request.channel_id),
+            request.filters,
+            cancel,
+        ))?;
+        self.remember_api_base(&api)?;
+        if options.write_cache {
+            self.db.cache_videos(&page.videos)?;
+        }
+        if options.record_history {
+            self.db.record_search(&query)?;
+            self.db.record_activity("search", &query)?;
+        }
+        Ok(page)
+    }
+
+    fn resolve_with_token(
+        &self,
+        page_url: &str,
+        cancel: Option<&CancellationToken>,
+    ) -> Result<ResolvedVideo, EngineError> {
+        self.check_resolve_host(page_url)?;
+        if let Some(cached) = self.db.get_cached_resolved_video_within(
+            page_url,
+            RESOLVED_CACHE_TTL_SECONDS,
+            RESOLVE_CACHE_READ_TIMEOUT,
+        )? {
+            return Ok(cached);
+        }
+        if self.is_offline() {
+            return Err(EngineError::NotFound {
+                detail: format!("no cached stream for {page_url} in offline mode"),
+            });
+        }
+
+        let resolved = self.yt_dlp.extract_stream(page_url, cancel)?;
+        // The stream URL matters more than caching it: a failed or timed-out write is dropped and
+        // the caller still gets the stream. The "resolve_cache_failed" activity entry (when the
+        // database accepts it) is the only trace; the next resolve simply runs yt-dlp again.
+        let _ =
+            self.db
+                .cache_resolved_video_within(page_url, &resolved, RESOLVE_CACHE_WRITE_TIMEOUT);
+        Ok(resolved)
+    }
+
+    fn search_cache(
+        &self,
+        query: &str,
+        page: u32,
+        limit: u32,
+    ) -> Result<Vec<VideoItem>, EngineError> {
+        let offset = page.saturating_sub(1).saturating_mul(limit);
+        self.db.search_cached_videos(query, limit, offset)
+    }
+
+    // Calls arriving sooner than `min_discover_interval_ms` block until the interval has passed,
+    // so every caller still gets fresh results instead of a cache fallback.
+    fn wait_for_discover_slot(&self) -> Result<(), EngineError> {
+        let Some(interval) = self
+            .config
+            .min_discover_interval_ms
+            .filter(|millis| *millis > 0)
+            .map(Duration::from_millis)
+        else {
+            return Ok(());
+        };
+        let mut last = self
+            .last_discover
+            .lock()
+            .map_err(|_| EngineError::Process {
+                detail: "discover throttle lock poisoned".to_string(),
+            })?;
+        if let Some(remaining) = last.and_then(|previous| interval.checked_sub(previous.elapsed()))
+        {
+            thread::sleep(remaining);
+        }
+        *last = Some(Instant::now());
+        Ok(())
+    }
+
+    // The listener is cloned out first so the callback runs without the lock held.
+    fn notify_preference_changed(&self, key: String, value: String) {
+        let listener = self
+            .preference_listener
+            .lock()
+            .ok()
+            .and_then(|listener| listener.clone());
+        if let Some(listener) = listener {
+            listener.on_preference_changed(key, value);
+        }
+    }
+
+    // For writes that may touch any number of preferences: compares the stored values before and
+    // after and reports each changed key, with "" for a removed one. Skipped without a listener.
+    fn notifying_preference_changes<T>(
+        &self,
+        write: impl FnOnce() -> Result<T, EngineError>,
+    ) -> Result<T, EngineError> {
+        let has_listener = self
+            .preference_listener
+            .lock()
+            .is_ok_and(|listener| listener.is_some());
+        if !has_listener {
+            return write();
+        }
+        let mut before: HashMap<String, String> = self.db.list_all_meta()?.into_iter().collect();
+        let result = write()?;
+        let mut changes = Vec::new();
+        for (key, value) in self.db.list_all_meta()? {
+            if before.remove(&key).as_ref() != Some(&value) {
+                changes.push((key, value));
+            }
+        }
+        changes.extend(before.into_keys().map(|key| (key, String::new())));
+        changes.sort();
+        for (key, value) in changes {
+            self.notify_preference_changed(key, value);
+        }
+        Ok(result)
+    }
+
+    // With an allowlist configured, yt-dlp only sees URLs whose host is a listed domain or one of
+    // its subdomains; anything unparseable is rejected too.
+    fn check_resolve_host(&self, page_url: &str) -> Result<(), EngineError> {
+        let Some(allowed) = &self.config.allowed_resolve_hosts else {
+            return Ok(());
+        };
+        let host = reqwest::Url::parse(page_url.trim())
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_ascii_lowercase));
+        let permitted = host.as_deref().is_some_and(|host| {
+            allowed
+                .iter()
+                .filter_map(|entry| non_empty(entry.trim_start_matches("*.")))
+                .map(str::to_ascii_lowercase)
+                .any(|entry| {
+                    host == entry
+                        || host
+                            .strip_suffix(entry.as_str())
+                            .is_some_and(|prefix| prefix.ends_with('.'))
+                })
+        });
+        if permitted {
+            Ok(())
+        } else {
+            Err(EngineError::InvalidConfig {
+                detail: format!(
+                    "host {} is not in allowed_resolve_hosts",
+                    host.as_deref().unwrap_or(page_url)
+                ),
+            })
+        }
+    }
+
+    fn ensure_online(&self) -> Result<(), EngineError> {
+        if self.is_offline() {
+            return Err(EngineError::Network {
+                detail: "offline mode".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    fn client_for_server(&self, api_base_url: &str) -> Result<ApiClient, EngineError> {
+        let normalized = api_base_url.trim().trim_end_matches('/').to_string();
+        if normalized.is_empty() {
+            return Err(EngineError::InvalidConfig {
+                detail: "api_base_url cannot be empty".to_string(),
+            });
+        }
+        let is_web_url = reqwest::Url::parse(&normalized)
+            .map(|parsed| matches!(parsed.scheme(), "http" | "https") && parsed.has_host())
+            .unwrap_or(false);
+        if !is_web_url {
+            return Err(EngineError::InvalidConfig {
+                detail: format!("api_base_url is not an http(s) url: {normalized}"),
+            });
+        }
+        let mut config = self.config.clone();
+        config.api_base_url = normalized;
+        // Fallbacks belong to the configured source, not to an ad-hoc server.
+        config.fallback_api_base_urls = None;
+        let client = ApiClient::new(&config).with_network_counters(self.api.network_counters());
+        self.with_server_headers(client)
+    }
+
+    // Persists the base that last answered so a restart goes straight to it.
+    fn remember_api_base(&self, api: &ApiClient) -> Result<(), EngineError> {
+        let Some(preferred) = api.preferred_base() else {
+            return Ok(());
+        };
+        if self.db.get_meta(PREFERRED_API_BASE_KEY)?.as_deref() != Some(preferred.as_str()) {
+            self.db.set_meta(PREFERRED_API_BASE_KEY, &preferred)?;
+        }
+        Ok(())
+    }
+
+    // Wraps every API call so the running totals reach meta as soon as it returns (failed
+    // requests count too); Android can kill the process before `flush` or `network_stats` run.
+    fn persisting_network_stats<T>(
+        &self,
+        result: Result<T, EngineError>,
+    ) -> Result<T, EngineError> {
+        let persisted = self.persist_network_stats();
+        let value = result?;
+        persisted?;
+        Ok(value)
+    }
+
+    fn persist_network_stats(&self) -> Result<NetworkStats, EngineError> {
+        let stats = self.api.network_counters().snapshot();
+        self.db
+            .set_meta(NETWORK_REQUESTS_KEY, &stats.requests.to_string())?;
+        self.db
+            .set_meta(NETWORK_BYTES_KEY, &stats.bytes_downloaded.to_string())?;
+        Ok(stats)
+    }
+
+    fn source_api(&self) -> Result<ApiClient, EngineError> {
+        self.with_server_headers(self.api.clone())
+    }
+
+    fn with_server_headers(&self, client: ApiClient) -> Result<ApiClient, EngineError> {
+        let headers = self
+            .db
+            .get_server(client.base_url())?
+            .and_then(|server| server.headers);
+        Ok(match headers {
+            Some(headers) => client.with_custom_headers(&headers),
+            None => client,
+        })
+    }
+
+    fn sync_boot_metadata(&self) -> Result<(), EngineError> {
         let update = self.check_yt_dlp_update()?;
         self.db
             .set_meta("boot_checked_at", &update.checked_at_epoch.to_string())?;
@@ -254,6 +1243,62 @@ fn validate_config(config: &EngineConfig) -> Result<(), EngineError> {
             detail: "python_executable cannot be empty".to_string(),
         });
     }
+    for (field, method) in [
+        ("status_method", &config.status_method),
+        ("videos_method", &config.videos_method),
+    ] {
+        let method = endpoint_method(method.as_deref());
+        if method != "GET" && method != "POST" {
+            return Err(EngineError::InvalidConfig {
+                detail: format!("{field} must be GET or POST, got {method}"),
+            });
+        }
+    }
+    if let Some(proxy_url) = configured_proxy(config) {
+        reqwest::Proxy::all(proxy_url).map_err(|err| EngineError::InvalidConfig {
+            detail: format!("invalid proxy_url {proxy_url}: {err}"),
+        })?;
+    }
+    if config
+        .db_encryption_key
+        .as_deref()
+        .is_some_and(|key| key.is_empty())
+    {
+        return Err(EngineError::InvalidConfig {
+            detail: "db_encryption_key cannot be empty".to_string(),
+        });
+    }
+    if let Some(extra_args) = &config.yt_dlp_extra_args {
+        validate_extra_args(extra_args)?;
+    }
+    for (field, timeout) in [
+        ("request_timeout_ms", config.request_timeout_ms),
+        ("connect_timeout_ms", config.connect_timeout_ms),
+        ("read_timeout_ms", config.read_timeout_ms),
+    ] {
+        if timeout == Some(0) {
+            return Err(EngineError::InvalidConfig {
+                detail: format!("{field} must be greater than zero"),
+            });
+        }
+    }
+    if config
+        .allowed_resolve_hosts
+        .as_ref()
+        .is_some_and(|hosts| hosts.iter().all(|host| host.trim().is_empty()))
+    {
+        return Err(EngineError::InvalidConfig {
+            detail: "allowed_resolve_hosts cannot be empty".to_string(),
+        });
+    }
+    if let Some(env) = &config.curl_cffi_env {
+        validate_bridge_env(env)?;
+    }
+    if config.max_response_bytes == Some(0) {
+        return Err(EngineError::InvalidConfig {
+            detail: "max_response_bytes must be greater than zero".to_string(),
+        });
+    }
     Ok(())
 }
 
@@ -262,15 +1307,81 @@ const _: fn() = || {
     assert_send_sync::<Engine>();
 };
 
+pub use cancellation::CancellationToken as UniFfiCancellationToken;
 pub use errors::EngineError as UniFfiEngineError;
 pub use models::{
-    BridgeHealth as UniFfiBridgeHealth, EngineConfig as UniFfiEngineConfig,
+    ActivityEntry as UniFfiActivityEntry, BootReport as UniFfiBootReport,
+    BridgeHealth as UniFfiBridgeHealth, Dashboard as UniFfiDashboard,
+    DatabaseBackup as UniFfiDatabaseBackup, DiscoverOptions as UniFfiDiscoverOptions,
+    DiscoverPage as UniFfiDiscoverPage, EngineConfig as UniFfiEngineConfig,
     FavoriteItem as UniFfiFavoriteItem, FilterSelection as UniFfiFilterSelection,
-    ResolvedVideo as UniFfiResolvedVideo, SourceServer as UniFfiSourceServer,
-    StatusSummary as UniFfiStatusSummary, UserPreference as UniFfiUserPreference,
-    VideoItem as UniFfiVideoItem, YtDlpUpdateInfo as UniFfiYtDlpUpdateInfo,
+    ImportReport as UniFfiImportReport, LinkPreview as UniFfiLinkPreview,
+    MergeReport as UniFfiMergeReport, MigrationStats as UniFfiMigrationStats,
+    NetworkCount as UniFfiNetworkCount, NetworkStats as UniFfiNetworkStats,
+    ProbeResult as UniFfiProbeResult, ResetOptions as UniFfiResetOptions,
+    ResetReport as UniFfiResetReport, ResolvedOrError as UniFfiResolvedOrError,
+    ResolvedVideo as UniFfiResolvedVideo, ServerLatency as UniFfiServerLatency,
+    SourceServer as UniFfiSourceServer, StatusChannel as UniFfiStatusChannel,
+    StatusNotice as UniFfiStatusNotice, StatusSummary as UniFfiStatusSummary,
+    UserPreference as UniFfiUserPreference, VideoItem as UniFfiVideoItem,
+    YtDlpUpdateInfo as UniFfiYtDlpUpdateInfo, YtDlpUpdateResult as UniFfiYtDlpUpdateResult,
 };
 
+fn status_cache_key(base_url: &str) -> String {
+    format!(
+        "{STATUS_CACHE_PREFIX}{}",
+        base_url.trim().trim_end_matches('/')
+    )
+}
+
+fn normalize_query(query: &str) -> String {
+    query
+        .split_whitespace()
+        .map(|word| {
+            word.chars()
+                .filter(|ch| !ch.is_control())
+                .collect::<String>()
+        })
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// yt-dlp leaves some fields blank for sites it only half understands; the discovered item
+// usually already knows them. The cached resolve stays as yt-dlp returned it.
+fn fill_from_item(mut resolved: ResolvedVideo, video: &VideoItem) -> ResolvedVideo {
+    let untitled = non_empty(&resolved.title).map_or(true, |title| title == "Untitled");
+    if untitled && non_empty(&video.title).is_some() {
+        resolved.title = video.title.clone();
+    }
+    if resolved
+        .thumbnail_url
+        .as_deref()
+        .and_then(non_empty)
+        .is_none()
+    {
+        resolved.thumbnail_url = video.image_url.clone();
+    }
+    if resolved
+        .author_name
+        .as_deref()
+        .and_then(non_empty)
+        .is_none()
+    {
+        resolved.author_name = video.author_name.clone();
+    }
+    resolved
+}
+
+// Older clients wrote booleans in several spellings; anything unrecognized falls back to the default.
+fn parse_bool_preference(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "true" | "1" | "yes" | "on" => Some(true),
+        "false" | "0" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
 fn non_empty(value: &str) -> Option<&str> {
     let trimmed = value.trim();
     if trimmed.is_empty() {
@@ -279,3 +1390,1414 @@ fn non_empty(value: &str) -> Option<&str> {
         Some(trimmed)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+    use test_support::{
+        test_config, MockResponse, MockServer, FIGLEAF_STATUS, STATUS_WITH_TWO_CHANNELS,
+    };
+
+    const VIDEOS: &str = r#"{
+        "items": [{
+            "id": "clip-1",
+            "title": "Clip",
+            "url": "https://example.com/watch?v=1"
+        }]
+    }"#;
+
+    fn source_server() -> MockServer {
+        source_server_with_videos(VIDEOS)
+    }
+
+    fn source_server_with_videos(videos: &str) -> MockServer {
+        MockServer::start(vec![
+            ("/api/status", MockResponse::json(STATUS_WITH_TWO_CHANNELS)),
+            ("/api/videos", MockResponse::json(videos)),
+            (
+                "/releases/latest",
+                MockResponse::json(r#"{ "tag_name": "2025.01.01" }"#),
+            ),
+        ])
+    }
+
+    fn engine_for(server: &MockServer, dir: &std::path::Path) -> Arc<Engine> {
+        let db_path = dir.join("engine.sqlite");
+        Engine::new(test_config(
+            server.base_url(),
+            db_path.to_str().expect("db path utf8"),
+        ))
+        .expect("engine")
+    }
+
+    #[test]
+    fn discover_uses_persisted_active_channel() {
+        let server = source_server();
+        let tmp = tempdir().expect("tmpdir");
+        let engine = engine_for(&server, tmp.path());
+
+        assert_eq!(engine.get_active_channel().expect("active channel"), None);
+        engine
+            .set_active_channel("dogflix".to_string())
+            .expect("set active channel");
+        assert_eq!(
+            engine
+                .get_active_channel()
+                .expect("active channel")
+                .as_deref(),
+            Some("dogflix")
+        );
+
+        let videos = engine
+            .discover_videos(String::new(), 1, 10)
+            .expect("discover videos");
+        assert_eq!(videos.len(), 1);
+        assert_eq!(videos[0].network.as_deref(), Some("dogflix"));
+
+        let requests = server.requests_to("/api/videos");
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(
+            requests[0].headers.get("content-type").map(String::as_str),
+            Some("application/json")
+        );
+        let payload: serde_json::Value =
+            serde_json::from_str(&requests[0].body).expect("videos payload json");
+        assert_eq!(payload["channel"], "dogflix");
+    }
+
+    #[test]
+    fn discover_with_params_passes_extra_keys_through() {
+        let server = source_server();
+        let tmp = tempdir().expect("tmpdir");
+        let engine = engine_for(&server, tmp.path());
+
+        engine
+            .discover_videos_with_params(
+                "cats".to_string(),
+                1,
+                10,
+                String::new(),
+                vec![],
+                HashMap::from([
+                    ("region".to_string(), "eu".to_string()),
+                    ("query".to_string(), "dogs".to_string()),
+                ]),
+            )
+            .expect("discover with params");
+        engine
+            .discover_videos("cats".to_string(), 1, 10)
+            .expect("plain discover");
+
+        let requests = server.requests_to("/api/videos");
+        assert_eq!(requests.len(), 2);
+        let payload: serde_json::Value =
+            serde_json::from_str(&requests[0].body).expect("payload json");
+        assert_eq!(payload["region"], "eu");
+        assert_eq!(payload["query"], "cats");
+        let plain: serde_json::Value =
+            serde_json::from_str(&requests[1].body).expect("payload json");
+        assert!(plain.get("region").is_none());
+    }
+
+    #[test]
+    fn discover_on_server_targets_the_given_base_url() {
+        let primary = source_server();
+        let secondary = source_server_with_videos(
+            r#"{ "items": [{ "id": "other-1", "title": "Other", "url": "https://other.example.com/1" }] }"#,
+        );
+        let tmp = tempdir().expect("tmpdir");
+        let engine = engine_for(&primary, tmp.path());
+
+        let videos = engine
+            .discover_videos_on_server(
+                format!(" {}/ ", secondary.base_url()),
+                "cats".to_string(),
+                1,
+                10,
+            )
+            .expect("discover on secondary");
+        assert_eq!(videos.len(), 1);
+        assert_eq!(videos[0].id, "other-1");
+        assert_eq!(secondary.requests_to("/api/videos").len(), 1);
+        assert!(primary.requests_to("/api/videos").is_empty());
+        let networks = engine.list_networks().expect("list networks");
+        assert_eq!(networks.len(), 1);
+        assert_eq!(networks[0].total, 1);
+
+        for base_url in ["  ", "not a url", "ftp://example.com"] {
+            for offline in [true, false] {
+                engine.set_offline(offline);
+                let err = engine
+                    .discover_videos_on_server(base_url.to_string(), "cats".to_string(), 1, 10)
+                    .expect_err("invalid base url");
+                assert!(
+                    matches!(err, EngineError::InvalidConfig { .. }),
+                    "{base_url:?} offline={offline}: {err:?}"
+                );
+            }
+        }
+        engine.set_offline(true);
+        let cached = engine
+            .discover_videos_on_server(secondary.base_url().to_string(), "Other".to_string(), 1, 10)
+            .expect("offline search with a valid base url");
+        assert_eq!(cached.len(), 1);
+    }
+
+    #[test]
+    fn boot_report_reflects_successful_boot() {
+        let server = source_server();
+        let tmp = tempdir().expect("tmpdir");
+        let db_path = tmp.path().join("boot.sqlite");
+
+        let (engine, report) = Engine::new_with_report(test_config(
+            server.base_url(),
+            db_path.to_str().expect("db path utf8"),
+        ))
+        .expect("engine");
+
+        assert!(!report.db_migrated);
+        assert!(report.update_check_ok);
+        assert_eq!(report.boot_error, None);
+        assert_eq!(server.requests_to("/releases/latest").len(), 1);
+
+        let stored = engine.boot_report();
+        assert_eq!(stored.update_check_ok, report.update_check_ok);
+        assert_eq!(stored.boot_error, report.boot_error);
+    }
+
+    #[test]
+    fn cancelled_token_short_circuits_discover_and_resolve() {
+        let server = source_server();
+        let tmp = tempdir().expect("tmpdir");
+        let engine = engine_for(&server, tmp.path());
+        let token = CancellationToken::new();
+        token.cancel();
+
+        let err = engine
+            .discover_videos_cancellable(String::new(), 1, 10, String::new(), vec![], token.clone())
+            .expect_err("cancelled discover");
+        assert!(matches!(err, EngineError::Cancelled { .. }));
+        assert!(server.requests_to("/api/status").is_empty());
+        assert!(server.requests_to("/api/videos").is_empty());
+
+        let err = engine
+            .resolve_stream_cancellable("https://example.com/v/1".to_string(), token)
+            .expect_err("cancelled resolve");
+        assert!(matches!(err, EngineError::Cancelled { .. }));
+    }
+
+    // Fake yt-dlp: logs extraction URLs to `extractions.log`, fails URLs containing "bad",
+    // and resolves everything else to a stream named after the last path segment.
+    #[cfg(unix)]
+    fn engine_with_fake_yt_dlp(server: &MockServer, dir: &std::path::Path) -> Arc<Engine> {
+        Engine::new(fake_yt_dlp_config(server, dir)).expect("engine")
+    }
+
+    #[cfg(unix)]
+    fn fake_yt_dlp_config(server: &MockServer, dir: &std::path::Path) -> EngineConfig {
+        use std::os::unix::fs::PermissionsExt;
+
+        let yt_dlp = dir.join("yt-dlp");
+        std::fs::write(
+            &yt_dlp,
+            format!(
+                "#!/bin/sh\nfor arg; do last=$arg; done\ncase \"$last\" in\n  --version) echo 2025.01.01; exit 0 ;;\nesac\necho \"$last\" >> '{}'\ncase \"$last\" in\n  *bad*) echo 'ERROR: Unsupported URL' >&2; exit 1 ;;\nesac\nid=${{last##*/}}\necho \"{{\\\"id\\\": \\\"$id\\\", \\\"url\\\": \\\"https://cdn.example.com/$id.mp4\\\"}}\"\n",
+                dir.join("extractions.log").display()
+            ),
+        )
+        .expect("write fake yt-dlp");
+        std::fs::set_permissions(&yt_dlp, std::fs::Permissions::from_mode(0o755))
+            .expect("chmod fake yt-dlp");
+        let db_path = dir.join("engine.sqlite");
+        let mut config = test_config(server.base_url(), db_path.to_str().expect("db path utf8"));
+        config.yt_dlp_path = yt_dlp.to_string_lossy().into_owned();
+        config
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn resolve_stream_honors_allowed_resolve_hosts() {
+        let server = source_server();
+        let tmp = tempdir().expect("tmpdir");
+        let mut config = fake_yt_dlp_config(&server, tmp.path());
+        config.allowed_resolve_hosts = Some(vec![" Example.com ".to_string()]);
+        let engine = Engine::new(config).expect("engine");
+
+        let resolved = engine
+            .resolve_stream("https://www.example.com/watch/ok".to_string())
+            .expect("allowed host resolves");
+        assert_eq!(resolved.stream_url, "https://cdn.example.com/ok.mp4");
+
+        for page_url in [
+            "https://evil.test/watch/nope",
+            "https://notexample.com/watch/nope",
+            "not a url",
+        ] {
+            let err = engine
+                .resolve_stream(page_url.to_string())
+                .expect_err("disallowed host");
+            assert!(
+                matches!(err, EngineError::InvalidConfig { .. }),
+                "{page_url}: {err:?}"
+            );
+        }
+        let log = std::fs::read_to_string(tmp.path().join("extractions.log")).expect("log");
+        assert!(log.contains("/watch/ok"), "{log}");
+        assert!(!log.contains("nope") && !log.contains("not a url"), "{log}");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn resolve_returns_stream_when_cache_write_stalls() {
+        let server = source_server();
+        let tmp = tempdir().expect("tmpdir");
+        let engine = engine_with_fake_yt_dlp(&server, tmp.path());
+        let page_url = "https://example.com/watch/slow".to_string();
+
+        let blocker =
+            rusqlite::Connection::open(tmp.path().join("engine.sqlite")).expect("open blocker");
+        blocker
+            .execute_batch("BEGIN IMMEDIATE;")
+            .expect("hold write lock");
+        let started = Instant::now();
+        let resolved = engine
+            .resolve_stream(page_url.clone())
+            .expect("resolve despite locked db");
+        let elapsed = started.elapsed();
+        assert_eq!(resolved.stream_url, "https://cdn.example.com/slow.mp4");
+        assert!(elapsed >= RESOLVE_CACHE_WRITE_TIMEOUT, "{elapsed:?}");
+        assert!(elapsed < Duration::from_millis(4500), "{elapsed:?}");
+
+        // Nothing is left holding the engine connection: reads answer at once.
+        let started = Instant::now();
+        assert!(!engine
+            .is_resolved_cached(page_url.clone(), RESOLVED_CACHE_TTL_SECONDS)
+            .expect("cache lookup"));
+        assert!(started.elapsed() < Duration::from_millis(500));
+        blocker.execute_batch("ROLLBACK;").expect("release lock");
+
+        // Another operation keeping the engine connection is waited out just as briefly.
+        let held = engine.db.hold_connection();
+        let resolver = Arc::clone(&engine);
+        let url = page_url.clone();
+        let started = Instant::now();
+        let pending = thread::spawn(move || resolver.resolve_stream(url));
+        while !pending.is_finished() {
+            assert!(
+                started.elapsed() < Duration::from_secs(10),
+                "resolve stuck behind the held connection"
+            );
+            thread::sleep(Duration::from_millis(20));
+        }
+        let elapsed = started.elapsed();
+        drop(held);
+        let resolved = pending
+            .join()
+            .expect("resolve thread")
+            .expect("resolve despite held connection");
+        assert_eq!(resolved.stream_url, "https://cdn.example.com/slow.mp4");
+        assert!(elapsed >= RESOLVE_CACHE_WRITE_TIMEOUT, "{elapsed:?}");
+        assert!(
+            elapsed
+                < RESOLVE_CACHE_WRITE_TIMEOUT + RESOLVE_CACHE_READ_TIMEOUT + Duration::from_secs(1),
+            "{elapsed:?}"
+        );
+
+        // A write that fails outright is dropped the same way and leaves a trace.
+        blocker
+            .execute_batch(
+                r#"
+                CREATE TRIGGER "reject_insert" BEFORE INSERT ON "video_details"
+                BEGIN SELECT RAISE(ABORT, 'rejected'); END;
+                CREATE TRIGGER "reject_update" BEFORE UPDATE ON "video_details"
+                BEGIN SELECT RAISE(ABORT, 'rejected'); END;
+                "#,
+            )
+            .expect("install failing triggers");
+        let started = Instant::now();
+        let resolved = engine
+            .resolve_stream(page_url.clone())
+            .expect("resolve despite failing write");
+        assert_eq!(resolved.stream_url, "https://cdn.example.com/slow.mp4");
+        assert!(started.elapsed() < RESOLVE_CACHE_WRITE_TIMEOUT);
+        let activity = engine.recent_activity(10).expect("recent activity");
+        assert_eq!(activity[0].kind, "resolve_cache_failed");
+        assert_eq!(activity[0].detail, page_url);
+        assert!(activity.iter().all(|entry| entry.kind != "resolve"));
+        assert!(!engine
+            .is_resolved_cached(page_url, RESOLVED_CACHE_TTL_SECONDS)
+            .expect("cache lookup"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn playback_failure_reresolves_only_expired_streams() {
+        let server = source_server();
+        let tmp = tempdir().expect("tmpdir");
+        let engine = engine_with_fake_yt_dlp(&server, tmp.path());
+        let page_url = "https://example.com/clip".to_string();
+        let extractions = || {
+            std::fs::read_to_string(tmp.path().join("extractions.log"))
+                .expect("log")
+                .lines()
+                .count()
+        };
+
+        engine.resolve_stream(page_url.clone()).expect("resolve");
+        let cached = engine
+            .report_playback_failure(page_url.clone(), 500)
+            .expect("transient failure");
+        assert_eq!(cached.stream_url, "https://cdn.example.com/clip.mp4");
+        assert_eq!(extractions(), 1);
+
+        let fresh = engine
+            .report_playback_failure(page_url.clone(), 403)
+            .expect("expired stream");
+        assert_eq!(fresh.stream_url, "https://cdn.example.com/clip.mp4");
+        assert_eq!(extractions(), 2);
+        assert!(engine
+            .is_resolved_cached(page_url, 60)
+            .expect("cached again"));
+
+        let err = engine
+            .report_playback_failure("https://example.com/never-resolved".to_string(), 500)
+            .expect_err("nothing cached");
+        assert!(matches!(err, EngineError::NotFound { .. }), "{err:?}");
+        assert_eq!(extractions(), 2);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn resolve_from_item_fills_blank_metadata() {
+        let server = source_server();
+        let tmp = tempdir().expect("tmpdir");
+        let engine = engine_with_fake_yt_dlp(&server, tmp.path());
+        let video = VideoItem {
+            id: "clip".to_string(),
+            title: "Known title".to_string(),
+            page_url: "https://example.com/clip".to_string(),
+            image_url: Some("https://example.com/clip.jpg".to_string()),
+            author_name: Some("uploader".to_string()),
+            ..VideoItem::default()
+        };
+
+        let resolved = engine
+            .resolve_stream_from_item(video.clone())
+            .expect("resolve from item");
+
+        assert_eq!(resolved.stream_url, "https://cdn.example.com/clip.mp4");
+        assert_eq!(resolved.title, "Known title");
+        assert_eq!(
+            resolved.thumbnail_url.as_deref(),
+            Some("https://example.com/clip.jpg")
+        );
+        assert_eq!(resolved.author_name.as_deref(), Some("uploader"));
+
+        let cached = engine
+            .resolve_stream(video.page_url)
+            .expect("cached by page url");
+        assert_eq!(cached.title, "Untitled");
+        let log = std::fs::read_to_string(tmp.path().join("extractions.log")).expect("log");
+        assert_eq!(log.lines().count(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn resolve_streams_reports_each_outcome() {
+        let server = source_server();
+        let tmp = tempdir().expect("tmpdir");
+        let engine = engine_with_fake_yt_dlp(&server, tmp.path());
+
+        let results = engine
+            .resolve_streams(vec![
+                "https://example.com/good".to_string(),
+                "https://example.com/bad".to_string(),
+            ])
+            .expect("resolve batch");
+
+        assert_eq!(results.len(), 2);
+        match &results[0] {
+            ResolvedOrError::Resolved { video } => {
+                assert_eq!(video.stream_url, "https://cdn.example.com/good.mp4");
+            }
+            other => panic!("expected resolved entry, got {other:?}"),
+        }
+        match &results[1] {
+            ResolvedOrError::Failed { page_url, error } => {
+                assert_eq!(page_url, "https://example.com/bad");
+                assert!(error.contains("Unsupported URL"));
+            }
+            other => panic!("expected failed entry, got {other:?}"),
+        }
+        assert!(engine
+            .get_cached_resolved_by_id("good".to_string(), 60)
+            .expect("cached lookup")
+            .is_some());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn prefetch_resolve_skips_fresh_entries_and_counts_warmed() {
+        let server = source_server();
+        let tmp = tempdir().expect("tmpdir");
+        let engine = engine_with_fake_yt_dlp(&server, tmp.path());
+        engine
+            .resolve_stream("https://example.com/cached".to_string())
+            .expect("seed resolved cache");
+        let log = tmp.path().join("extractions.log");
+        std::fs::remove_file(&log).expect("reset extraction log");
+
+        let warmed = engine
+            .prefetch_resolve(vec![
+                "https://example.com/cached".to_string(),
+                "https://example.com/fresh".to_string(),
+                "https://example.com/bad".to_string(),
+            ])
+            .expect("prefetch");
+
+        assert_eq!(warmed, 1);
+        let extractions = std::fs::read_to_string(&log).expect("read extraction log");
+        assert_eq!(
+            extractions.lines().collect::<Vec<_>>(),
+            ["https://example.com/fresh", "https://example.com/bad"]
+        );
+        assert!(engine
+            .is_resolved_cached("https://example.com/fresh".to_string(), 60)
+            .expect("fresh cached"));
+
+        engine.set_offline(true);
+        let err = engine
+            .prefetch_resolve(vec!["https://example.com/other".to_string()])
+            .expect_err("offline prefetch");
+        assert!(matches!(err, EngineError::Network { ref detail } if detail == "offline mode"));
+    }
+
+    #[test]
+    fn aspect_ratio_persists_through_video_cache() {
+        let server = source_server_with_videos(
+            r#"{
+                "items": [
+                    { "id": "wide", "title": "Wide clip", "url": "https://example.com/v/wide", "aspectRatio": 1.7778 },
+                    { "id": "bogus", "title": "Bogus clip", "url": "https://example.com/v/bogus", "aspectRatio": -1 }
+                ]
+            }"#,
+        );
+        let tmp = tempdir().expect("tmpdir");
+        let engine = engine_for(&server, tmp.path());
+
+        let videos = engine
+            .discover_videos("clip".to_string(), 1, 10)
+            .expect("discover");
+        assert_eq!(videos[0].aspect_ratio, Some(1.7778));
+        assert_eq!(videos[1].aspect_ratio, None);
+
+        let cached = engine
+            .db
+            .search_cached_videos("clip", 10, 0)
+            .expect("search cache");
+        let wide = cached
+            .iter()
+            .find(|video| video.id == "wide")
+            .expect("cached wide clip");
+        assert_eq!(wide.aspect_ratio, Some(1.7778));
+    }
+
+    #[test]
+    fn discover_with_options_can_skip_history_and_cache() {
+        let server = source_server();
+        let tmp = tempdir().expect("tmpdir");
+        let engine = engine_for(&server, tmp.path());
+
+        let videos = engine
+            .discover_videos_with_options(
+                "kittens".to_string(),
+                1,
+                10,
+                String::new(),
+                vec![],
+                DiscoverOptions {
+                    record_history: false,
+                    write_cache: false,
+                },
+            )
+            .expect("discover without side effects");
+        assert_eq!(videos.len(), 1);
+        assert!(engine.db.list_networks().expect("list networks").is_empty());
+
+        let conn = rusqlite::Connection::open(tmp.path().join("engine.sqlite")).expect("open db");
+        let searches: i64 = conn
+            .query_row(r#"SELECT COUNT(*) FROM "searches""#, [], |row| row.get(0))
+            .expect("count searches");
+        assert_eq!(searches, 0);
+    }
+
+    #[test]
+    fn requests_carry_headers_of_matching_source_server() {
+        let server = source_server();
+        let tmp = tempdir().expect("tmpdir");
+        let engine = engine_for(&server, tmp.path());
+
+        engine
+            .upsert_source_server(SourceServer {
+                base_url: server.base_url().to_string(),
+                title: "Mock".to_string(),
+                color: None,
+                icon_url: None,
+                headers: Some(std::collections::HashMap::from([(
+                    "X-Api-Key".to_string(),
+                    "abc123".to_string(),
+                )])),
+            })
+            .expect("upsert server");
+
+        engine
+            .discover_videos(String::new(), 1, 10)
+            .expect("discover videos");
+        let requests = server.requests_to("/api/videos");
+        assert_eq!(requests.len(), 1);
+        assert_eq!(
+            requests[0].headers.get("x-api-key").map(String::as_str),
+            Some("abc123")
+        );
+    }
+
+    #[test]
+    fn offline_mode_serves_cache_and_never_hits_the_network() {
+        let server = source_server();
+        let tmp = tempdir().expect("tmpdir");
+        let engine = engine_for(&server, tmp.path());
+        engine
+            .discover_videos("clip".to_string(), 1, 10)
+            .expect("online discover");
+        let online_requests = server.requests().len();
+
+        engine.set_offline(true);
+        let cached = engine
+            .discover_videos("CLI".to_string(), 1, 10)
+            .expect("offline discover");
+        assert_eq!(cached.len(), 1);
+        assert_eq!(cached[0].id, "clip-1");
+        assert!(engine
+            .discover_videos("clip".to_string(), 2, 10)
+            .expect("offline second page")
+            .is_empty());
+
+        let err = engine
+            .resolve_stream("https://example.com/watch?v=1".to_string())
+            .expect_err("offline resolve miss");
+        assert!(matches!(err, EngineError::NotFound { .. }));
+        for err in [
+            engine.sync_status().expect_err("offline sync"),
+            engine
+                .probe_status(server.base_url().to_string())
+                .expect_err("offline probe"),
+        ] {
+            assert!(matches!(err, EngineError::Network { ref detail } if detail == "offline mode"));
+        }
+        let err = engine
+            .check_yt_dlp_update()
+            .expect_err("offline update check");
+        assert!(matches!(err, EngineError::Network { ref detail } if detail == "offline mode"));
+        assert_eq!(server.requests().len(), online_requests);
+    }
+
+    #[test]
+    fn network_stats_accumulate_and_persist() {
+        let server = source_server();
+        let tmp = tempdir().expect("tmpdir");
+        let engine = engine_for(&server, tmp.path());
+        assert_eq!(
+            engine.network_stats().expect("initial stats"),
+            NetworkStats::default()
+        );
+
+        for _ in 0..2 {
+            engine
+                .discover_videos("cats".to_string(), 1, 10)
+                .expect("discover");
+        }
+        let expected = NetworkStats {
+            requests: 4,
+            bytes_downloaded: 2 * (STATUS_WITH_TWO_CHANNELS.len() + VIDEOS.len()) as u64,
+        };
+        assert_eq!(engine.network_stats().expect("stats"), expected);
+        engine
+            .probe_status(server.base_url().to_string())
+            .expect("probe");
+        assert_eq!(engine.network_stats().expect("stats").requests, 5);
+        drop(engine);
+
+        let reopened = engine_for(&server, tmp.path());
+        assert_eq!(
+            reopened.network_stats().expect("reopened stats").requests,
+            5
+        );
+
+        // Totals reach meta without `network_stats()` or `flush()` ever being called.
+        reopened
+            .discover_videos("dogs".to_string(), 1, 10)
+            .expect("discover");
+        drop(reopened);
+        let reopened = engine_for(&server, tmp.path());
+        assert_eq!(
+            reopened.network_stats().expect("reopened stats").requests,
+            7
+        );
+        reopened.reset_network_stats().expect("reset");
+        assert_eq!(
+            reopened.network_stats().expect("reset stats"),
+            NetworkStats::default()
+        );
+    }
+
+    #[test]
+    fn adblock_required_reads_cached_status() {
+        let server = MockServer::start(vec![(
+            "/api/status",
+            MockResponse::json(
+                r#"{ "id": "figleaf", "name": "Fig Leaf", "adblockRequired": true }"#,
+            ),
+        )]);
+        let other = source_server();
+        let tmp = tempdir().expect("tmpdir");
+        let engine = engine_for(&server, tmp.path());
+
+        engine.sync_status().expect("sync status");
+        assert!(engine
+            .adblock_required(format!("{}/", server.base_url()))
+            .expect("cached adblock flag"));
+        assert_eq!(server.requests_to("/api/status").len(), 1);
+
+        for _ in 0..2 {
+            assert!(!engine
+                .adblock_required(other.base_url().to_string())
+                .expect("fetched adblock flag"));
+        }
+        assert_eq!(other.requests_to("/api/status").len(), 1);
+
+        let err = engine
+            .adblock_required(" ".to_string())
+            .expect_err("blank url");
+        assert!(matches!(err, EngineError::InvalidConfig { .. }));
+    }
+
+    #[test]
+    fn channel_details_returns_one_channel() {
+        let server = MockServer::start(vec![("/api/status", MockResponse::json(FIGLEAF_STATUS))]);
+        let tmp = tempdir().expect("tmpdir");
+        let engine = engine_for(&server, tmp.path());
+
+        let channel = engine
+            .channel_details(" catflix ".to_string())
+            .expect("catflix details");
+        assert_eq!(channel.id, "catflix");
+        assert_eq!(channel.title, "Catflix");
+        assert_eq!(
+            channel.description.as_deref(),
+            Some("All cats, all the time.")
+        );
+        assert_eq!(channel.options.len(), 1);
+        assert_eq!(channel.options[0].id, "sort");
+
+        let err = engine
+            .channel_details("dogflix".to_string())
+            .expect_err("unknown channel");
+        assert!(matches!(err, EngineError::NotFound { .. }));
+    }
+
+    #[test]
+    fn probe_status_result_reports_failures_in_band() {
+        let failing = MockServer::start(vec![(
+            "/api/status",
+            MockResponse {
+                status: 500,
+                headers: Vec::new(),
+                body: "upstream exploded".to_string(),
+            },
+        )]);
+        let server = source_server();
+        let tmp = tempdir().expect("tmpdir");
+        let engine = engine_for(&server, tmp.path());
+
+        let failed = engine
+            .probe_status_result(failing.base_url().to_string())
+            .expect("failure is reported in band");
+        assert!(!failed.ok);
+        assert!(failed.status.is_none());
+        assert!(failed
+            .error
+            .as_deref()
+            .is_some_and(|error| error.contains("500")));
+
+        let probed = engine
+            .probe_status_result(server.base_url().to_string())
+            .expect("probe source");
+        assert!(probed.ok);
+        assert!(probed.error.is_none());
+        assert!(probed.status.is_some());
+
+        let err = engine
+            .probe_status_result("  ".to_string())
+            .expect_err("blank url");
+        assert!(matches!(err, EngineError::InvalidConfig { .. }));
+    }
+
+    #[test]
+    fn previews_url_from_open_graph_tags() {
+        let server = source_server();
+        let tmp = tempdir().expect("tmpdir");
+        let engine = engine_for(&server, tmp.path());
+        let page = MockServer::start(vec![(
+            "/watch/1",
+            MockResponse {
+                status: 200,
+                headers: vec![("Content-Type".to_string(), "text/html".to_string())],
+                body: r#"<html><head>
+                    <title>Page title</title>
+                    <meta property="og:title" content="Clip title">
+                    <meta property="og:image" content="/thumb.jpg">
+                    <meta property="og:description" content="A clip">
+                </head><body></body></html>"#
+                    .to_string(),
+            },
+        )]);
+
+        let preview = engine
+            .preview_url(format!("{}/watch/1", page.base_url()))
+            .expect("preview");
+
+        assert_eq!(preview.title.as_deref(), Some("Clip title"));
+        assert_eq!(
+            preview.image,
+            Some(format!("{}/thumb.jpg", page.base_url()))
+        );
+        assert_eq!(preview.description.as_deref(), Some("A clip"));
+        let requests = page.requests_to("/watch/1");
+        assert_eq!(requests.len(), 1);
+        assert!(matches!(
+            engine.preview_url("file:///etc/passwd".to_string()),
+            Err(EngineError::InvalidConfig { .. })
+        ));
+    }
+
+    #[test]
+    fn measures_server_latency_without_caching_status() {
+        let server = source_server();
+        let tmp = tempdir().expect("tmpdir");
+        let engine = engine_for(&server, tmp.path());
+        let measured = MockServer::start(vec![(
+            "/api/status",
+            MockResponse::json(r#"{ "id": "figleaf", "name": "Fig Leaf", "apiVersion": "2.1" }"#),
+        )]);
+
+        let latency = engine
+            .measure_server(measured.base_url().to_string())
+            .expect("measure server");
+
+        assert!(latency.reachable);
+        assert!(latency.latency_ms < 5_000);
+        assert_eq!(latency.api_version.as_deref(), Some("2.1"));
+        assert_eq!(measured.requests_to("/api/status").len(), 1);
+        assert!(engine
+            .last_known_status(measured.base_url().to_string())
+            .expect("last known status")
+            .is_none());
+    }
+
+    #[test]
+    fn offline_config_skips_boot_update_check() {
+        let server = source_server();
+        let tmp = tempdir().expect("tmpdir");
+        let db_path = tmp.path().join("offline.sqlite");
+        let mut config = test_config(server.base_url(), db_path.to_str().expect("db path utf8"));
+        config.offline = true;
+
+        let (engine, report) = Engine::new_with_report(config).expect("engine");
+        assert!(engine.is_offline());
+        assert!(!report.update_check_ok);
+        assert_eq!(report.boot_error, None);
+        assert!(server.requests().is_empty());
+    }
+
+    #[test]
+    fn discover_calls_respect_min_interval() {
+        let server = source_server();
+        let tmp = tempdir().expect("tmpdir");
+        let db_path = tmp.path().join("throttle.sqlite");
+        let mut config = test_config(server.base_url(), db_path.to_str().expect("db path utf8"));
+        config.min_discover_interval_ms = Some(300);
+        let engine = Engine::new(config).expect("engine");
+
+        let started = Instant::now();
+        engine
+            .discover_videos("a".to_string(), 1, 10)
+            .expect("first discover");
+        engine
+            .discover_videos("ab".to_string(), 1, 10)
+            .expect("second discover");
+
+        assert!(started.elapsed() >= Duration::from_millis(300));
+        assert_eq!(server.requests_to("/api/videos").len(), 2);
+    }
+
+    #[test]
+    fn normalize_query_collapses_whitespace_and_strips_controls() {
+        assert_eq!(normalize_query("  cats\tand  dogs \n"), "cats and dogs");
+        assert_eq!(normalize_query("a\u{0}b \u{7}  c"), "ab c");
+        assert_eq!(normalize_query(" \t "), "");
+    }
+
+    #[test]
+    fn search_history_records_normalized_query() {
+        let server = source_server();
+        let tmp = tempdir().expect("tmpdir");
+        let engine = engine_for(&server, tmp.path());
+
+        for query in ["  kittens  ", "kittens", "\tkittens\n"] {
+            engine
+                .discover_videos(query.to_string(), 1, 10)
+                .expect("discover");
+        }
+
+        let conn = rusqlite::Connection::open(tmp.path().join("engine.sqlite")).expect("open db");
+        let rows: Vec<(String, i64)> = conn
+            .prepare(r#"SELECT "query", "frequency" FROM "searches""#)
+            .expect("prepare")
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .expect("query searches")
+            .collect::<Result<_, _>>()
+            .expect("collect searches");
+        assert_eq!(rows, vec![("kittens".to_string(), 3)]);
+    }
+
+    #[test]
+    fn sync_status_caches_last_known_status_per_server() {
+        let server = source_server();
+        let tmp = tempdir().expect("tmpdir");
+        let engine = engine_for(&server, tmp.path());
+        assert!(engine
+            .last_known_status(server.base_url().to_string())
+            .expect("empty status cache")
+            .is_none());
+
+        let live = engine.sync_status().expect("sync status");
+        engine.set_offline(true);
+
+        let cached = engine
+            .last_known_status(format!("{}/", server.base_url()))
+            .expect("cached status")
+            .expect("status present");
+        assert_eq!(cached.name, live.name);
+        assert_eq!(cached.channels, live.channels);
+        assert!(engine
+            .last_known_status("https://other.example".to_string())
+            .expect("other server")
+            .is_none());
+    }
+
+    #[test]
+    fn proxy_url_routes_requests_and_rejects_invalid_values() {
+        let proxy = MockServer::start(vec![]);
+        let tmp = tempdir().expect("tmpdir");
+        let db_path = tmp.path().join("proxy.sqlite");
+        let mut config = test_config(
+            "http://upstream.invalid",
+            db_path.to_str().expect("db path utf8"),
+        );
+        config.proxy_url = Some(proxy.base_url().to_string());
+        let engine = Engine::new(config.clone()).expect("engine");
+
+        assert!(engine.sync_status().is_err());
+        let paths: Vec<String> = proxy
+            .requests()
+            .into_iter()
+            .map(|request| request.path)
+            .collect();
+        assert!(paths.contains(&"http://upstream.invalid/releases/latest".to_string()));
+        assert!(paths.contains(&"http://upstream.invalid/api/status".to_string()));
+
+        config.proxy_url = Some("http://[::1".to_string());
+        let err = Engine::new(config).err().expect("invalid proxy rejected");
+        assert!(matches!(err, EngineError::InvalidConfig { .. }));
+    }
+
+    #[test]
+    fn rejects_unsupported_endpoint_methods() {
+        let mut config = test_config("http://127.0.0.1:9", "/tmp/unused.sqlite");
+        config.videos_method = Some("DELETE".to_string());
+        let err = Engine::new(config).err().expect("invalid method rejected");
+        assert!(matches!(err, EngineError::InvalidConfig { .. }));
+    }
+
+    #[test]
+    fn records_search_and_favorite_activity() {
+        let server = source_server();
+        let tmp = tempdir().expect("tmpdir");
+        let engine = engine_for(&server, tmp.path());
+        let videos = engine
+            .discover_videos("clip".to_string(), 1, 10)
+            .expect("discover videos");
+        engine
+            .add_favorite(videos[0].clone())
+            .expect("add favorite");
+
+        let activity = engine.recent_activity(10).expect("recent activity");
+        let entries: Vec<(&str, &str)> = activity
+            .iter()
+            .map(|entry| (entry.kind.as_str(), entry.detail.as_str()))
+            .collect();
+        assert_eq!(entries, [("favorite_added", "clip-1"), ("search", "clip")]);
+
+        assert_eq!(engine.clear_activity_log().expect("clear activity"), 2);
+        assert!(engine.recent_activity(10).expect("cleared").is_empty());
+    }
+
+    #[test]
+    fn falls_back_to_secondary_base_and_remembers_it() {
+        let unavailable = MockResponse {
+            status: 503,
+            headers: Vec::new(),
+            body: "blocked".to_string(),
+        };
+        let primary = MockServer::start(vec![
+            ("/api/status", unavailable.clone()),
+            ("/api/videos", unavailable),
+            (
+                "/releases/latest",
+                MockResponse::json(r#"{ "tag_name": "2025.01.01" }"#),
+            ),
+        ]);
+        let fallback = source_server();
+        let tmp = tempdir().expect("tmpdir");
+        let db_path = tmp.path().join("engine.sqlite");
+        let mut config = test_config(primary.base_url(), db_path.to_str().expect("db path utf8"));
+        config.fallback_api_base_urls = Some(vec![format!("{}/", fallback.base_url())]);
+        let engine = Engine::new(config.clone()).expect("engine");
+
+        let videos = engine
+            .discover_videos("clip".to_string(), 1, 10)
+            .expect("discover via fallback");
+
+        assert_eq!(videos[0].id, "clip-1");
+        assert_eq!(primary.requests_to("/api/status").len(), 1);
+        assert_eq!(primary.requests_to("/api/videos").len(), 0);
+        assert_eq!(fallback.requests_to("/api/videos").len(), 1);
+        assert_eq!(
+            engine
+                .db
+                .get_meta(PREFERRED_API_BASE_KEY)
+                .expect("preferred meta")
+                .as_deref(),
+            Some(fallback.base_url())
+        );
+
+        drop(engine);
+        let restarted = Engine::new(config).expect("restarted engine");
+        restarted.sync_status().expect("status via remembered base");
+        assert_eq!(primary.requests_to("/api/status").len(), 1);
+        assert_eq!(fallback.requests_to("/api/status").len(), 2);
+    }
+
+    #[test]
+    fn typed_preferences_round_trip_and_parse_legacy_values() {
+        let server = source_server();
+        let tmp = tempdir().expect("tmpdir");
+        let engine = engine_for(&server, tmp.path());
+
+        engine
+            .set_bool_preference("player.autoplay".to_string(), true)
+            .expect("set bool");
+        engine
+            .set_int_preference("player.volume".to_string(), -3)
+            .expect("set int");
+        assert!(engine
+            .get_bool_preference("player.autoplay".to_string(), false)
+            .expect("get bool"));
+        assert_eq!(
+            engine
+                .get_int_preference("player.volume".to_string(), 0)
+                .expect("get int"),
+            -3
+        );
+        assert_eq!(
+            engine
+                .get_user_preference("player.autoplay".to_string())
+                .expect("raw bool")
+                .as_deref(),
+            Some("true")
+        );
+
+        for (legacy, expected) in [("YES", true), (" 1 ", true), ("off", false), ("0", false)] {
+            engine
+                .set_user_preference("legacy.flag".to_string(), legacy.to_string())
+                .expect("set legacy");
+            assert_eq!(
+                engine
+                    .get_bool_preference("legacy.flag".to_string(), !expected)
+                    .expect("legacy bool"),
+                expected
+            );
+        }
+        engine
+            .set_user_preference("legacy.count".to_string(), " 42 ".to_string())
+            .expect("set legacy int");
+        assert_eq!(
+            engine
+                .get_int_preference("legacy.count".to_string(), 0)
+                .expect("legacy int"),
+            42
+        );
+
+        engine
+            .set_user_preference("legacy.flag".to_string(), "maybe".to_string())
+            .expect("set garbage");
+        assert!(engine
+            .get_bool_preference("legacy.flag".to_string(), true)
+            .expect("garbage bool"));
+        assert_eq!(
+            engine
+                .get_int_preference("missing.count".to_string(), 7)
+                .expect("missing int"),
+            7
+        );
+    }
+
+    #[test]
+    fn ensure_migrated_after_legacy_import_exposes_favorites() {
+        let server = source_server();
+        let tmp = tempdir().expect("tmpdir");
+        let engine = engine_for(&server, tmp.path());
+        let legacy_path = tmp.path().join("legacy.sqlite");
+        let conn = rusqlite::Connection::open(&legacy_path).expect("create legacy db");
+        conn.execute_batch(
+            r#"
+            CREATE TABLE favorites (
+                video_id TEXT PRIMARY KEY,
+                title TEXT NOT NULL,
+                image_url TEXT,
+                network TEXT,
+                added_at INTEGER NOT NULL
+            );
+            INSERT INTO favorites VALUES ('legacy-1', 'Legacy One', NULL, 'catflix', 1700000000);
+            INSERT INTO favorites VALUES ('legacy-2', 'Legacy Two', NULL, 'catflix', 1700000100);
+            "#,
+        )
+        .expect("seed legacy favorites");
+        drop(conn);
+
+        engine
+            .import_database(legacy_path.to_string_lossy().into_owned())
+            .expect("import legacy db");
+        let stats = engine.ensure_migrated().expect("ensure migrated");
+
+        assert_eq!(stats.total(), 0);
+        assert_eq!(engine.schema_version().expect("schema version"), 1);
+        assert_eq!(
+            engine
+                .last_migration_stats()
+                .expect("last stats")
+                .expect("import migration recorded")
+                .favorites,
+            2
+        );
+        let mut ids: Vec<String> = engine
+            .list_favorites()
+            .expect("list favorites")
+            .into_iter()
+            .map(|favorite| favorite.video_id)
+            .collect();
+        ids.sort();
+        assert_eq!(ids, ["legacy-1", "legacy-2"]);
+    }
+
+    #[test]
+    fn in_memory_engine_keeps_favorites_without_touching_disk() {
+        let server = source_server();
+        let engine =
+            Engine::new(test_config(server.base_url(), ":memory:")).expect("in-memory engine");
+        let videos = engine
+            .discover_videos(String::new(), 1, 10)
+            .expect("discover videos");
+        engine
+            .add_favorite(videos[0].clone())
+            .expect("add favorite");
+        engine.flush().expect("flush is a no-op");
+
+        let exported = engine.export_favorites_json().expect("export favorites");
+        engine.reset_all_data().expect("reset");
+        assert!(engine.list_favorites().expect("list favorites").is_empty());
+        assert_eq!(
+            engine
+                .import_favorites_json(exported)
+                .expect("import favorites"),
+            1
+        );
+        assert_eq!(
+            engine.list_favorites().expect("list favorites")[0].video_id,
+            "clip-1"
+        );
+
+        assert!(engine.bridge_health().expect("health").db_accessible);
+        assert!(matches!(
+            engine.export_database_bytes(),
+            Err(EngineError::InvalidConfig { .. })
+        ));
+        assert!(!std::path::Path::new(":memory:").exists());
+    }
+
+    struct RecordingListener {
+        engine: Mutex<Option<Arc<Engine>>>,
+        changes: Mutex<Vec<(String, String, Option<String>)>>,
+    }
+
+    impl PreferenceListener for RecordingListener {
+        fn on_preference_changed(&self, key: String, value: String) {
+            // Reads back through the engine to prove the callback runs outside engine locks.
+            let stored = self
+                .engine
+                .lock()
+                .expect("engine slot")
+                .as_ref()
+                .and_then(|engine| engine.get_user_preference(key.clone()).ok().flatten());
+            self.changes
+                .lock()
+                .expect("changes")
+                .push((key, value, stored));
+        }
+    }
+
+    #[test]
+    fn preference_listener_fires_on_writes_and_can_reenter() {
+        let server = source_server();
+        let tmp = tempdir().expect("tmpdir");
+        let engine = engine_for(&server, tmp.path());
+        engine
+            .set_user_preference("theme.mode".to_string(), "light".to_string())
+            .expect("write without listener");
+
+        let listener = Arc::new(RecordingListener {
+            engine: Mutex::new(Some(Arc::clone(&engine))),
+            changes: Mutex::new(Vec::new()),
+        });
+        engine.set_preference_listener(Some(listener.clone()));
+        engine
+            .set_user_preference("theme.mode".to_string(), "dark".to_string())
+            .expect("set preference");
+        engine
+            .set_bool_preference("player.autoplay".to_string(), false)
+            .expect("set bool");
+        engine.set_preference_listener(None);
+        engine
+            .set_user_preference("theme.mode".to_string(), "system".to_string())
+            .expect("write after removal");
+
+        let changes = listener.changes.lock().expect("changes").clone();
+        assert_eq!(
+            changes,
+            [
+                (
+                    "theme.mode".to_string(),
+                    "dark".to_string(),
+                    Some("dark".to_string())
+                ),
+                (
+                    "player.autoplay".to_string(),
+                    "false".to_string(),
+                    Some("false".to_string())
+                ),
+            ]
+        );
+        // Break the engine <-> listener cycle.
+        listener.engine.lock().expect("engine slot").take();
+    }
+
+    #[test]
+    fn preference_listener_hears_bulk_preference_writes() {
+        let server = source_server();
+        let tmp = tempdir().expect("tmpdir");
+        let engine = engine_for(&server, tmp.path());
+        engine
+            .set_user_preference("theme.mode".to_string(), "dark".to_string())
+            .expect("set preference");
+
+        let other_dir = tempdir().expect("other tmpdir");
+        let other = engine_for(&server, other_dir.path());
+        other
+            .set_user_preference("theme.accent".to_string(), "teal".to_string())
+            .expect("set other preference");
+        other.flush().expect("flush other");
+
+        let listener = Arc::new(RecordingListener {
+            engine: Mutex::new(None),
+            changes: Mutex::new(Vec::new()),
+        });
+        engine.set_preference_listener(Some(listener.clone()));
+        let take_changes = || {
+            std::mem::take(&mut *listener.changes.lock().expect("changes"))
+                .into_iter()
+                .map(|(key, value, _)| (key, value))
+                .collect::<Vec<_>>()
+        };
+
+        engine
+            .set_active_channel("catflix".to_string())
+            .expect("set active channel");
+        assert_eq!(
+            take_changes(),
+            [(ACTIVE_CHANNEL_KEY.to_string(), "catflix".to_string())]
+        );
+
+        engine
+            .merge_import(
+                other_dir
+                    .path()
+                    .join("engine.sqlite")
+                    .to_string_lossy()
+                    .into_owned(),
+            )
+            .expect("merge import");
+        assert!(take_changes().contains(&("theme.accent".to_string(), "teal".to_string())));
+
+        engine
+            .reset_selective(ResetOptions {
+                preferences: true,
+                ..ResetOptions::default()
+            })
+            .expect("reset preferences");
+        let cleared = take_changes();
+        for key in ["theme.mode", "theme.accent", ACTIVE_CHANNEL_KEY] {
+            assert!(
+                cleared.contains(&(key.to_string(), String::new())),
+                "{key}: {cleared:?}"
+            );
+        }
+
+        engine
+            .reset_selective(ResetOptions {
+                favorites: true,
+                ..ResetOptions::default()
+            })
+            .expect("reset favorites");
+        assert!(take_changes().is_empty());
+
+        // Whole-database swaps report the values they bring in or remove.
+        engine
+            .set_user_preference("theme.mode".to_string(), "dark".to_string())
+            .expect("set preference");
+        other
+            .set_user_preference("theme.mode".to_string(), "light".to_string())
+            .expect("set other theme");
+        other.flush().expect("flush other");
+        take_changes();
+        let theme = |value: &str| ("theme.mode".to_string(), value.to_string());
+
+        engine
+            .import_database(
+                other_dir
+                    .path()
+                    .join("engine.sqlite")
+                    .to_string_lossy()
+                    .into_owned(),
+            )
+            .expect("import database");
+        assert!(take_changes().contains(&theme("light")));
+
+        let backup = engine
+            .list_backups()
+            .expect("list backups")
+            .into_iter()
+            .next()
+            .expect("backup taken before import");
+        engine.restore_backup(backup.path).expect("restore backup");
+        assert!(take_changes().contains(&theme("dark")));
+
+        let bytes = other.export_database_bytes().expect("export other");
+        engine
+            .import_database_bytes(bytes)
+            .expect("import database bytes");
+        assert!(take_changes().contains(&theme("light")));
+
+        engine.reset_all_data().expect("reset all data");
+        assert!(take_changes().contains(&theme("")));
+    }
+
+    #[test]
+    fn favorites_json_roundtrip_survives_reset() {
+        let server = source_server();
+        let tmp = tempdir().expect("tmpdir");
+        let engine = engine_for(&server, tmp.path());
+        let videos = engine
+            .discover_videos(String::new(), 1, 10)
+            .expect("discover videos");
+        engine
+            .add_favorite(videos[0].clone())
+            .expect("add favorite");
+        // Columns can drift from the stored payload; the export follows rawData.
+        let conn = rusqlite::Connection::open(tmp.path().join("engine.sqlite")).expect("open db");
+        conn.execute(
+            r#"UPDATE "video_details" SET "title" = 'Column title' WHERE "id" = 'clip-1'"#,
+            [],
+        )
+        .expect("edit title column");
+
+        let exported = engine.export_favorites_json().expect("export favorites");
+        let exported_videos: Vec<VideoItem> =
+            serde_json::from_str(&exported).expect("exported json");
+        assert_eq!(exported_videos[0].title, videos[0].title);
+        engine.reset_all_data().expect("reset");
+        assert!(engine.list_favorites().expect("list favorites").is_empty());
+
+        assert_eq!(
+            engine
+                .import_favorites_json(exported)
+                .expect("import favorites"),
+            1
+        );
+        let favorites = engine.list_favorite_videos().expect("list favorite videos");
+        assert_eq!(favorites.len(), 1);
+        assert_eq!(favorites[0].id, "clip-1");
+        assert_eq!(favorites[0].page_url, "https://example.com/watch?v=1");
+
+        // One failing item leaves the whole batch unwritten.
+        conn.execute_batch(
+            r#"
+            CREATE TRIGGER "reject_poison" BEFORE INSERT ON "video_details"
+            WHEN NEW."id" = 'poison'
+            BEGIN SELECT RAISE(ABORT, 'rejected'); END;
+            "#,
+        )
+        .expect("install failing trigger");
+        let batch = serde_json::to_string(&[
+            VideoItem {
+                id: "fresh".to_string(),
+                title: "Fresh".to_string(),
+                page_url: "https://example.com/fresh".to_string(),
+                ..VideoItem::default()
+            },
+            VideoItem {
+                id: "poison".to_string(),
+                title: "Poison".to_string(),
+                page_url: "https://example.com/poison".to_string(),
+                ..VideoItem::default()
+            },
+        ])
+        .expect("batch json");
+        engine
+            .import_favorites_json(batch)
+            .expect_err("failing item");
+        let ids: Vec<String> = engine
+            .list_favorite_videos()
+            .expect("list favorite videos")
+            .into_iter()
+            .map(|video| video.id)
+            .collect();
+        assert_eq!(ids, ["clip-1"]);
+
+        let err = engine
+            .import_favorites_json("{not json".to_string())
+            .expect_err("invalid json");
+        assert!(matches!(err, EngineError::Serialization { .. }));
+    }
+}
@@ -0,0 +1,11 @@
+// Implemented by the host to hear about preference writes. The engine calls it after the value is
+// stored and without holding any of its locks, so the callback may call back into the engine;
+// writing a preference from inside the callback fires it again.
+//
+// It fires for `set_user_preference` and the typed setters, and for every key changed by
+// `set_active_channel`, `merge_import`, `reset_selective`, `reset_all_data`, `import_database`,
+// `import_database_bytes` and `restore_backup`; a removed key is reported with an empty value.
+#[uniffi::export(with_foreign)]
+pub trait PreferenceListener: Send + Sync {
+    fn on_preference_changed(&self, key: String, value: String);
+}
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
@@ -8,6 +10,46 @@ pub struct EngineConfig {
     pub python_executable: String,
     pub curl_cffi_script_path: Option<String>,
     pub yt_dlp_repo_api: Option<String>,
+    #[uniffi(default = true)]
+    pub backup_before_import: bool,
+    #[uniffi(default = None)]
+    pub accept_language: Option<String>,
+    #[uniffi(default = false)]
+    pub offline: bool,
+    #[uniffi(default = None)]
+    pub min_discover_interval_ms: Option<u64>,
+    #[uniffi(default = None)]
+    pub yt_dlp_expected_sha256: Option<String>,
+    #[uniffi(default = None)]
+    pub proxy_url: Option<String>,
+    #[uniffi(default = None)]
+    pub status_method: Option<String>,
+    #[uniffi(default = None)]
+    pub videos_method: Option<String>,
+    #[uniffi(default = 5000)]
+    pub db_busy_timeout_ms: u64,
+    #[uniffi(default = None)]
+    pub db_encryption_key: Option<String>,
+    #[uniffi(default = None)]
+    pub yt_dlp_extra_args: Option<Vec<String>>,
+    #[uniffi(default = false)]
+    pub strict_filter_selections: bool,
+    #[uniffi(default = None)]
+    pub max_response_bytes: Option<u64>,
+    #[uniffi(default = None)]
+    pub request_timeout_ms: Option<u64>,
+    #[uniffi(default = None)]
+    pub connect_timeout_ms: Option<u64>,
+    #[uniffi(default = None)]
+    pub read_timeout_ms: Option<u64>,
+    #[uniffi(default = None)]
+    pub fallback_api_base_urls: Option<Vec<String>>,
+    #[uniffi(default = None)]
+    pub curl_cffi_env: Option<HashMap<String, String>>,
+    #[uniffi(default = None)]
+    pub curl_cffi_impersonate: Option<String>,
+    #[uniffi(default = None)]
+    pub allowed_resolve_hosts: Option<Vec<String>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
@@ -22,6 +64,19 @@ pub struct StatusSummary {
     pub sources: Vec<String>,
     pub adblock_required: bool,
     pub source_releases_url: Option<String>,
+    pub subtitle: Option<String>,
+    pub description: Option<String>,
+    pub message: Option<String>,
+    #[serde(default)]
+    #[uniffi(default = [])]
+    pub notices: Vec<StatusNotice>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, uniffi::Record)]
+pub struct StatusNotice {
+    pub level: Option<String>,
+    pub title: Option<String>,
+    pub message: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
@@ -36,6 +91,8 @@ pub struct SourceServer {
     pub title: String,
     pub color: Option<String>,
     pub icon_url: Option<String>,
+    #[uniffi(default = None)]
+    pub headers: Option<HashMap<String, String>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
@@ -46,6 +103,31 @@ pub struct StatusChannel {
     pub favicon_url: Option<String>,
     pub ytdlp_command: Option<String>,
     pub options: Vec<StatusFilterOption>,
+    #[uniffi(default = None)]
+    pub status: Option<ChannelStatus>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, uniffi::Enum)]
+pub enum ChannelStatus {
+    Active,
+    Inactive,
+    Unknown(String),
+}
+
+impl ChannelStatus {
+    // Sources spell these freely, so matching ignores case and padding; anything else is kept
+    // verbatim for display.
+    pub fn parse(raw: &str) -> Option<ChannelStatus> {
+        let trimmed = raw.trim();
+        if trimmed.is_empty() {
+            return None;
+        }
+        Some(match trimmed.to_ascii_lowercase().as_str() {
+            "active" => ChannelStatus::Active,
+            "inactive" => ChannelStatus::Inactive,
+            _ => ChannelStatus::Unknown(trimmed.to_string()),
+        })
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
@@ -69,6 +151,23 @@ pub struct FilterSelection {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
+pub struct DiscoverOptions {
+    pub record_history: bool,
+    pub write_cache: bool,
+}
+
+impl Default for DiscoverOptions {
+    fn default() -> Self {
+        Self {
+            record_history: true,
+            write_cache: true,
+        }
+    }
+}
+
+// Cached `rawData` rows may predate newer fields, so missing ones fall back to defaults.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, uniffi::Record)]
+#[serde(default)]
 pub struct VideoItem {
     pub id: String,
     pub title: String,
@@ -80,6 +179,8 @@ pub struct VideoItem {
     pub extractor: Option<String>,
     pub view_count: Option<u64>,
     pub raw_json: Option<String>,
+    #[uniffi(default = None)]
+    pub aspect_ratio: Option<f64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
@@ -92,6 +193,38 @@ pub struct ResolvedVideo {
     pub author_name: Option<String>,
     pub extractor: Option<String>,
     pub duration_seconds: Option<u32>,
+    #[uniffi(default = None)]
+    pub view_count: Option<u64>,
+    #[uniffi(default = None)]
+    pub upload_date_epoch: Option<i64>,
+    #[uniffi(default = None)]
+    pub description: Option<String>,
+    #[uniffi(default = None)]
+    pub stream_protocol: Option<String>,
+    #[serde(default)]
+    #[uniffi(default = [])]
+    pub thumbnails: Vec<Thumbnail>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, uniffi::Record)]
+pub struct Thumbnail {
+    pub url: String,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, uniffi::Enum)]
+#[allow(clippy::large_enum_variant)]
+pub enum ResolvedOrError {
+    Resolved { video: ResolvedVideo },
+    Failed { page_url: String, error: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
+pub struct DiscoverPage {
+    pub videos: Vec<VideoItem>,
+    pub has_next_page: bool,
+    pub recommendations: Vec<VideoItem>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
@@ -103,6 +236,65 @@ pub struct FavoriteItem {
     pub added_at_epoch: i64,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
+pub struct NetworkCount {
+    pub network: String,
+    pub total: u32,
+    pub favorites: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
+pub struct ImportReport {
+    pub valid: bool,
+    pub integrity_ok: bool,
+    pub missing_tables: Vec<String>,
+    pub video_count: u64,
+    pub favorite_count: u64,
+    pub preference_count: u64,
+    pub server_count: u64,
+    pub issues: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, uniffi::Record)]
+pub struct MergeReport {
+    pub added: u64,
+    pub updated: u64,
+    pub skipped: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, uniffi::Record)]
+pub struct ResetOptions {
+    #[uniffi(default = false)]
+    pub favorites: bool,
+    #[uniffi(default = false)]
+    pub cache: bool,
+    #[uniffi(default = false)]
+    pub history: bool,
+    #[uniffi(default = false)]
+    pub searches: bool,
+    #[uniffi(default = false)]
+    pub servers: bool,
+    #[uniffi(default = false)]
+    pub preferences: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, uniffi::Record)]
+pub struct ResetReport {
+    pub favorites: u64,
+    pub cache: u64,
+    pub history: u64,
+    pub searches: u64,
+    pub servers: u64,
+    pub preferences: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
+pub struct DatabaseBackup {
+    pub path: String,
+    pub created_at_epoch: i64,
+    pub size_bytes: u64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
 pub struct YtDlpUpdateInfo {
     pub current_version: Option<String>,
@@ -111,6 +303,58 @@ pub struct YtDlpUpdateInfo {
     pub checked_at_epoch: i64,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
+pub struct YtDlpUpdateResult {
+    pub updated: bool,
+    pub from_version: Option<String>,
+    pub to_version: Option<String>,
+    pub raw_output: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, uniffi::Record)]
+pub struct Dashboard {
+    pub favorite_count: u64,
+    pub watched_count: u64,
+    pub cached_count: u64,
+    pub last_search: Option<String>,
+    pub yt_dlp_update_available: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
+pub struct ActivityEntry {
+    pub kind: String,
+    pub detail: String,
+    pub recorded_at_epoch: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
+pub struct LinkPreview {
+    pub title: Option<String>,
+    pub image: Option<String>,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
+pub struct ProbeResult {
+    pub ok: bool,
+    pub status: Option<StatusSummary>,
+    pub error: Option<String>,
+    pub latency_ms: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, uniffi::Record)]
+pub struct NetworkStats {
+    pub requests: u64,
+    pub bytes_downloaded: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
+pub struct ServerLatency {
+    pub reachable: bool,
+    pub latency_ms: u64,
+    pub api_version: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
 pub struct BridgeHealth {
     pub engine_ready: bool,
@@ -118,7 +362,38 @@ pub struct BridgeHealth {
     pub last_error: Option<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
+pub struct MigrationStats {
+    pub meta: u64,
+    pub videos: u64,
+    pub favorites: u64,
+    pub resolved: u64,
+}
+
+impl MigrationStats {
+    pub fn total(&self) -> u64 {
+        self.meta + self.videos + self.favorites + self.resolved
+    }
+
+    pub fn merged(self, other: MigrationStats) -> MigrationStats {
+        MigrationStats {
+            meta: self.meta + other.meta,
+            videos: self.videos + other.videos,
+            favorites: self.favorites + other.favorites,
+            resolved: self.resolved + other.resolved,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
+pub struct BootReport {
+    pub db_migrated: bool,
+    pub update_check_ok: bool,
+    pub boot_error: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
+#[allow(dead_code)]
 pub struct ApiStatusResponse {
     pub id: Option<String>,
     pub name: Option<String>,
@@ -142,9 +417,19 @@ pub struct ApiStatusResponse {
     pub adblock_required: Option<bool>,
     #[serde(rename = "sourceReleasesUrl")]
     pub source_releases_url: Option<String>,
+    #[serde(default)]
+    pub notices: Vec<ApiStatusNotice>,
 }
 
 #[derive(Debug, Deserialize)]
+pub struct ApiStatusNotice {
+    pub level: Option<String>,
+    pub title: Option<String>,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
 pub struct ApiVideoEnvelope {
     #[serde(default)]
     pub videos: Vec<ApiVideoRecord>,
@@ -153,6 +438,7 @@ pub struct ApiVideoEnvelope {
 }
 
 #[derive(Debug, Deserialize)]
+#[allow(dead_code)]
 pub struct ApiStatusChannel {
     pub id: String,
     pub name: Option<String>,
@@ -203,6 +489,8 @@ pub struct ApiVideoRecord {
     pub extractor: Option<String>,
     #[serde(alias = "views")]
     pub view_count: Option<u64>,
+    #[serde(rename = "aspectRatio", alias = "aspect_ratio")]
+    pub aspect_ratio: Option<f64>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -216,15 +504,28 @@ pub struct YtDlpResponse {
     pub title: Option<String>,
     pub webpage_url: Option<String>,
     pub url: Option<String>,
+    pub protocol: Option<String>,
     pub thumbnail: Option<String>,
     pub uploader: Option<String>,
     pub extractor: Option<String>,
     pub duration: Option<f64>,
+    pub view_count: Option<u64>,
+    pub upload_date: Option<String>,
+    pub description: Option<String>,
     pub formats: Option<Vec<YtDlpFormat>>,
+    pub thumbnails: Option<Vec<YtDlpThumbnail>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct YtDlpThumbnail {
+    pub url: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct YtDlpFormat {
     pub url: Option<String>,
     pub protocol: Option<String>,
+    pub height: Option<u32>,
 }
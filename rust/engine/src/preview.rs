@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+
+use crate::models::LinkPreview;
+
+// Later keys are only consulted when the earlier ones are missing or blank.
+const TITLE_KEYS: [&str; 2] = ["og:title", "twitter:title"];
+const IMAGE_KEYS: [&str; 4] = [
+    "og:image",
+    "og:image:url",
+    "og:image:secure_url",
+    "twitter:image",
+];
+const DESCRIPTION_KEYS: [&str; 3] = ["og:description", "twitter:description", "description"];
+
+// A deliberately small scanner: previews only need a handful of <meta> tags and <title>, and a
+// page that hides them behind scripts simply yields an empty preview.
+pub fn parse_link_preview(html: &str, page_url: &str) -> LinkPreview {
+    let meta = meta_properties(html);
+    let first = |keys: &[&str]| {
+        keys.iter()
+            .find_map(|key| meta.get(*key).filter(|value| !value.is_empty()).cloned())
+    };
+
+    LinkPreview {
+        title: first(&TITLE_KEYS).or_else(|| title_element(html)),
+        image: first(&IMAGE_KEYS).map(|image| absolute_url(page_url, image)),
+        description: first(&DESCRIPTION_KEYS),
+    }
+}
+
+// Keyed by lowercased `property`/`name`; the first tag for a key wins, matching how crawlers read OG.
+fn meta_properties(html: &str) -> HashMap<String, String> {
+    let lower = html.to_ascii_lowercase();
+    let mut properties = HashMap::new();
+    let mut cursor = 0;
+    while let Some(offset) = lower[cursor..].find("<meta") {
+        let start = cursor + offset;
+        let Some(length) = lower[start..].find('>') else {
+            break;
+        };
+        let attributes = parse_attributes(&html[start + "<meta".len()..start + length]);
+        cursor = start + length + 1;
+
+        let Some(key) = attributes
+            .get("property")
+            .or_else(|| attributes.get("name"))
+            .map(|key| key.trim().to_ascii_lowercase())
+        else {
+            continue;
+        };
+        if let Some(content) = attributes.get("content") {
+            properties
+                .entry(key)
+                .or_insert_with(|| decode_entities(content.trim()));
+        }
+    }
+    properties
+}
+
+fn parse_attributes(tag: &str) -> HashMap<String, String> {
+    let mut attributes = HashMap::new();
+    let mut chars = tag.chars().peekable();
+    loop {
+        while chars
+            .next_if(|ch| ch.is_whitespace() || *ch == '/')
+            .is_some()
+        {}
+        let mut name = String::new();
+        while let Some(ch) = chars.next_if(|ch| !ch.is_whitespace() && *ch != '=' && *ch != '/') {
+            name.push(ch.to_ascii_lowercase());
+        }
+        if name.is_empty() {
+            break;
+        }
+        while chars.next_if(|ch| ch.is_whitespace()).is_some() {}
+        let mut value = String::new();
+        if chars.next_if_eq(&'=').is_some() {
+            while chars.next_if(|ch| ch.is_whitespace()).is_some() {}
+            match chars.next_if(|ch| *ch == '"' || *ch == '\'') {
+                Some(quote) => {
+                    for ch in chars.by_ref() {
+                        if ch == quote {
+                            break;
+                        }
+                        value.push(ch);
+                    }
+                }
+                None => {
+                    while let Some(ch) = chars.next_if(|ch| !ch.is_whitespace()) {
+                        value.push(ch);
+                    }
+                }
+            }
+        }
+        attributes.entry(name).or_insert(value);
+    }
+    attributes
+}
+
+fn title_element(html: &str) -> Option<String> {
+    let lower = html.to_ascii_lowercase();
+    let open = lower.find("<title")?;
+    let content_start = open + lower[open..].find('>')? + 1;
+    let content_end = content_start + lower[content_start..].find("</title")?;
+    let title = decode_entities(
+        &html[content_start..content_end]
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" "),
+    );
+    (!title.is_empty()).then_some(title)
+}
+
+fn decode_entities(value: &str) -> String {
+    let mut decoded = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(amp) = rest.find('&') {
+        decoded.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let entity = rest
+            .find(';')
+            .filter(|end| *end <= 10)
+            .and_then(|end| decode_entity(&rest[1..end]).map(|ch| (ch, end)));
+        match entity {
+            Some((ch, end)) => {
+                decoded.push(ch);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+fn decode_entity(entity: &str) -> Option<char> {
+    match entity {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some(' '),
+        _ => {
+            let code = entity.strip_prefix('#')?;
+            let code = match code.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => code.parse().ok()?,
+            };
+            char::from_u32(code)
+        }
+    }
+}
+
+fn absolute_url(page_url: &str, value: String) -> String {
+    reqwest::Url::parse(page_url)
+        .and_then(|base| base.join(&value))
+        .map(|resolved| resolved.to_string())
+        .unwrap_or(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefers_open_graph_and_falls_back_to_title_element() {
+        let html = r#"<!doctype html><html><head>
+            <TITLE>  Fallback
+                title </TITLE>
+            <meta property="og:title" content="Cats &amp; Dogs">
+            <meta name='description' content='Plain description'>
+            <meta content="/thumbs/1.jpg" property="og:image" />
+            <meta property="og:title" content="Second title is ignored">
+        </head></html>"#;
+
+        let preview = parse_link_preview(html, "https://example.com/watch/1");
+
+        assert_eq!(preview.title.as_deref(), Some("Cats & Dogs"));
+        assert_eq!(
+            preview.image.as_deref(),
+            Some("https://example.com/thumbs/1.jpg")
+        );
+        assert_eq!(preview.description.as_deref(), Some("Plain description"));
+
+        let bare = parse_link_preview(
+            "<html><head><title>Only &#39;title&#x27;</title></head></html>",
+            "https://example.com/",
+        );
+        assert_eq!(bare.title.as_deref(), Some("Only 'title'"));
+        assert_eq!(bare.image, None);
+        assert_eq!(bare.description, None);
+    }
+}
@@ -0,0 +1,65 @@
+use std::future::Future;
+use std::sync::OnceLock;
+use std::thread;
+
+use tokio::runtime::{Builder, Handle, Runtime};
+
+use crate::errors::EngineError;
+
+const WORKER_THREADS: usize = 2;
+
+// One runtime for every blocking HTTP call in the process, built on first use. A build failure is
+// kept too, so each later call reports it instead of retrying.
+static RUNTIME: OnceLock<Result<Runtime, String>> = OnceLock::new();
+
+fn shared_runtime() -> Result<&'static Runtime, EngineError> {
+    RUNTIME
+        .get_or_init(|| {
+            Builder::new_multi_thread()
+                .worker_threads(WORKER_THREADS)
+                .thread_name("whirlpool-http")
+                .enable_all()
+                .build()
+                .map_err(|err| err.to_string())
+        })
+        .as_ref()
+        .map_err(|err| EngineError::Network {
+            detail: format!("failed to build runtime: {err}"),
+        })
+}
+
+// Blocking on a runtime from inside another runtime's thread panics, so a caller that is already
+// async (a host executor, a test) gets the wait moved onto a plain scoped thread.
+pub fn block_on<F>(future: F) -> Result<F::Output, EngineError>
+where
+    F: Future + Send,
+    F::Output: Send,
+{
+    let runtime = shared_runtime()?;
+    if Handle::try_current().is_err() {
+        return Ok(runtime.block_on(future));
+    }
+    thread::scope(|scope| {
+        scope
+            .spawn(|| runtime.block_on(future))
+            .join()
+            .map_err(|_| EngineError::Network {
+                detail: "http worker thread panicked".to_string(),
+            })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reuses_one_runtime_and_nests_safely() {
+        let first = shared_runtime().expect("runtime") as *const Runtime;
+        let second = shared_runtime().expect("runtime") as *const Runtime;
+        assert_eq!(first, second);
+
+        let nested = block_on(async { block_on(async { 21 * 2 }) }).expect("outer block_on");
+        assert_eq!(nested.expect("inner block_on"), 42);
+    }
+}
@@ -0,0 +1,241 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crate::models::EngineConfig;
+
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    pub query: String,
+    pub headers: HashMap<String, String>,
+    pub body: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct MockResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl MockResponse {
+    pub fn json(body: &str) -> Self {
+        Self {
+            status: 200,
+            headers: vec![("Content-Type".to_string(), "application/json".to_string())],
+            body: body.to_string(),
+        }
+    }
+}
+
+pub struct MockServer {
+    base_url: String,
+    requests: Arc<Mutex<Vec<RecordedRequest>>>,
+}
+
+impl MockServer {
+    pub fn start(routes: Vec<(&str, MockResponse)>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind mock server");
+        let base_url = format!("http://{}", listener.local_addr().expect("local addr"));
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        // Repeated paths are served in order; the last response keeps repeating.
+        let mut sequences: HashMap<String, Vec<MockResponse>> = HashMap::new();
+        for (path, response) in routes {
+            sequences
+                .entry(path.to_string())
+                .or_default()
+                .push(response);
+        }
+
+        let recorded = Arc::clone(&requests);
+        thread::spawn(move || {
+            let mut hits: HashMap<String, usize> = HashMap::new();
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else {
+                    continue;
+                };
+                handle_connection(stream, &sequences, &mut hits, &recorded);
+            }
+        });
+
+        Self { base_url, requests }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().expect("requests lock").clone()
+    }
+
+    pub fn requests_to(&self, path: &str) -> Vec<RecordedRequest> {
+        self.requests()
+            .into_iter()
+            .filter(|request| request.path == path)
+            .collect()
+    }
+}
+
+fn handle_connection(
+    stream: TcpStream,
+    routes: &HashMap<String, Vec<MockResponse>>,
+    hits: &mut HashMap<String, usize>,
+    recorded: &Arc<Mutex<Vec<RecordedRequest>>>,
+) {
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).is_err() {
+        return;
+    }
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let target = parts.next().unwrap_or_default().to_string();
+    let (path, query) = match target.split_once('?') {
+        Some((path, query)) => (path.to_string(), query.to_string()),
+        None => (target.clone(), String::new()),
+    };
+
+    let mut headers = HashMap::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).is_err() {
+            return;
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.insert(name.trim().to_ascii_lowercase(), value.trim().to_string());
+        }
+    }
+
+    let content_length = headers
+        .get("content-length")
+        .and_then(|value| value.parse::<usize>().ok())
+        .unwrap_or(0);
+    let mut body = vec![0u8; content_length];
+    if reader.read_exact(&mut body).is_err() {
+        return;
+    }
+
+    recorded
+        .lock()
+        .expect("requests lock")
+        .push(RecordedRequest {
+            method,
+            path: path.clone(),
+            query,
+            headers,
+            body: String::from_utf8_lossy(&body).into_owned(),
+        });
+
+    let hit = hits.entry(path.clone()).or_default();
+    let response = routes
+        .get(&path)
+        .and_then(|responses| responses.get(*hit).or(responses.last()))
+        .cloned();
+    *hit += 1;
+    let response = response.unwrap_or(MockResponse {
+        status: 404,
+        headers: Vec::new(),
+        body: "not found".to_string(),
+    });
+    let mut raw = format!(
+        "HTTP/1.1 {} Mock\r\nContent-Length: {}\r\nConnection: close\r\n",
+        response.status,
+        response.body.len()
+    );
+    for (name, value) in &response.headers {
+        raw.push_str(&format!("{name}: {value}\r\n"));
+    }
+    raw.push_str("\r\n");
+    raw.push_str(&response.body);
+
+    let mut stream = reader.into_inner();
+    let _ = stream.write_all(raw.as_bytes());
+    let _ = stream.flush();
+}
+
+pub fn test_config(api_base_url: &str, db_path: &str) -> EngineConfig {
+    EngineConfig {
+        api_base_url: api_base_url.to_string(),
+        db_path: db_path.to_string(),
+        yt_dlp_path: "/nonexistent/yt-dlp".to_string(),
+        python_executable: "/nonexistent/python3".to_string(),
+        curl_cffi_script_path: None,
+        yt_dlp_repo_api: Some(format!("{api_base_url}/releases/latest")),
+        backup_before_import: true,
+        accept_language: None,
+        offline: false,
+        min_discover_interval_ms: None,
+        yt_dlp_expected_sha256: None,
+        proxy_url: None,
+        status_method: None,
+        videos_method: None,
+        db_busy_timeout_ms: 5000,
+        db_encryption_key: None,
+        yt_dlp_extra_args: None,
+        strict_filter_selections: false,
+        max_response_bytes: None,
+        request_timeout_ms: None,
+        connect_timeout_ms: None,
+        read_timeout_ms: None,
+        fallback_api_base_urls: None,
+        curl_cffi_env: None,
+        curl_cffi_impersonate: None,
+        allowed_resolve_hosts: None,
+    }
+}
+
+pub const STATUS_WITH_TWO_CHANNELS: &str = r#"{
+    "id": "figleaf",
+    "name": "Fig Leaf",
+    "channels": [
+        { "id": "catflix", "name": "Catflix", "default": true },
+        { "id": "dogflix", "name": "Dogflix" }
+    ]
+}"#;
+
+pub const FIGLEAF_STATUS: &str = r##"{
+    "id": "figleaf",
+    "name": "Fig Leaf",
+    "subtitle": "Watch it!",
+    "description": "A source for all your fig leaf needs.",
+    "iconUrl": "https://cdn.hottubapp.io/assets/channels/figleaf.png",
+    "color": "#478003",
+    "status": "normal",
+    "notices": [],
+    "channels": [
+        {
+            "id": "catflix",
+            "name": "Catflix",
+            "description": "All cats, all the time.",
+            "color": "indigo",
+            "status": "active",
+            "default": true,
+            "options": [
+                {
+                    "id": "sort",
+                    "title": "Sort",
+                    "options": [
+                        { "id": "views", "title": "Views" },
+                        { "id": "likes", "title": "Likes" },
+                        { "id": "latest", "title": "Latest" }
+                    ]
+                }
+            ],
+            "categories": ["Funny Cats", "Kittens"],
+            "ytdlpCommand": "--format best[ext=mp4]"
+        }
+    ],
+    "subscription": { "status": "inactive" },
+    "nsfw": false,
+    "categories": ["Cute", "Funny"],
+    "message": "New tutorials available."
+}"##;
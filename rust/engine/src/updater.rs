@@ -1,7 +1,8 @@
-use chrono::Utc;
-
+use crate::api::{http_client, HttpTimeouts};
+use crate::clock::Clock;
 use crate::errors::EngineError;
 use crate::models::{GitHubRelease, YtDlpUpdateInfo};
+use crate::runtime;
 
 const DEFAULT_RELEASES_API: &str = "https://api.github.com/repos/yt-dlp/yt-dlp/releases/latest";
 const GH_USER_AGENT: &str = "whirlpool-engine/0.1 (+android; uniffi)";
@@ -13,8 +14,10 @@ pub fn default_release_api() -> &'static str {
 pub fn check_yt_dlp_update(
     release_api: &str,
     current_version: Option<String>,
+    proxy_url: Option<&str>,
+    clock: &dyn Clock,
 ) -> Result<YtDlpUpdateInfo, EngineError> {
-    let latest_version = fetch_latest_release_tag(release_api)?;
+    let latest_version = fetch_latest_release_tag(release_api, proxy_url)?;
     let update_available = match (&current_version, &latest_version) {
         (Some(current), Some(latest)) => normalize_tag(current) != normalize_tag(latest),
         _ => false,
@@ -24,29 +27,22 @@ pub fn check_yt_dlp_update(
         current_version,
         latest_version,
         update_available,
-        checked_at_epoch: Utc::now().timestamp(),
+        checked_at_epoch: clock.now().timestamp(),
     })
 }
 
-fn fetch_latest_release_tag(release_api: &str) -> Result<Option<String>, EngineError> {
-    let runtime = tokio::runtime::Builder::new_current_thread()
-        .enable_all()
-        .build()
-        .map_err(|err| EngineError::Network {
-            detail: format!("failed to build runtime: {err}"),
-        })?;
-
-    let body = runtime
-        .block_on(async {
-            let client = reqwest::Client::builder()
-                .user_agent(GH_USER_AGENT)
-                .build()?;
-            let response = client.get(release_api).send().await?.error_for_status()?;
-            response.text().await
-        })
-        .map_err(|err| EngineError::Network {
-            detail: format!("failed fetching latest yt-dlp release: {err}"),
-        })?;
+fn fetch_latest_release_tag(
+    release_api: &str,
+    proxy_url: Option<&str>,
+) -> Result<Option<String>, EngineError> {
+    let body = runtime::block_on(async {
+        let client = http_client(GH_USER_AGENT, proxy_url, HttpTimeouts::default())?;
+        let response = client.get(release_api).send().await?.error_for_status()?;
+        response.text().await
+    })?
+    .map_err(|err| EngineError::Network {
+        detail: format!("failed fetching latest yt-dlp release: {err}"),
+    })?;
 
     let parsed = serde_json::from_str::<GitHubRelease>(&body)?;
     Ok(parsed.tag_name)
@@ -61,7 +57,11 @@ fn normalize_tag(tag: &str) -> String {
 
 #[cfg(test)]
 mod tests {
-    use super::normalize_tag;
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+    use crate::clock::FakeClock;
+    use crate::test_support::{MockResponse, MockServer};
 
     #[test]
     fn strips_v_prefix_and_casing() {
@@ -69,4 +69,24 @@ mod tests {
         assert_eq!(normalize_tag("V2025.01.02"), "2025.01.02");
         assert_eq!(normalize_tag(" 2025.01.03 "), "2025.01.03");
     }
+
+    #[test]
+    fn stamps_update_check_with_injected_clock() {
+        let server = MockServer::start(vec![(
+            "/releases/latest",
+            MockResponse::json(r#"{ "tag_name": "2025.02.01" }"#),
+        )]);
+        let clock = FakeClock::new(Utc.timestamp_opt(1_700_000_000, 0).unwrap());
+
+        let info = check_yt_dlp_update(
+            &format!("{}/releases/latest", server.base_url()),
+            Some("2025.01.01".to_string()),
+            None,
+            clock.as_ref(),
+        )
+        .expect("update check");
+
+        assert!(info.update_available);
+        assert_eq!(info.checked_at_epoch, 1_700_000_000);
+    }
 }
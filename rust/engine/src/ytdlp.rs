@@ -1,12 +1,26 @@
+use std::fs;
 use std::process::Command;
 
+use chrono::NaiveDate;
+use ring::digest::{digest, SHA256};
+
+use crate::api::payload_snippet;
+use crate::cancellation::{check_cancelled, CancellationToken};
 use crate::errors::EngineError;
-use crate::models::{ResolvedVideo, YtDlpResponse};
+use crate::models::{
+    ResolvedVideo, Thumbnail, YtDlpFormat, YtDlpResponse, YtDlpThumbnail, YtDlpUpdateResult,
+};
+
+// Progressive downloads play everywhere; HLS and DASH need player support, so they come second.
+const PROGRESSIVE_PROTOCOLS: [&str; 2] = ["http", "https"];
+const STREAMING_PROTOCOLS: [&str; 3] = ["m3u8", "m3u8_native", "http_dash_segments"];
 
 #[derive(Debug, Clone)]
 pub struct YtDlpClient {
     binary_path: String,
     python_executable: String,
+    expected_sha256: Option<String>,
+    extra_args: Vec<String>,
 }
 
 impl YtDlpClient {
@@ -14,11 +28,42 @@ impl YtDlpClient {
         Self {
             binary_path,
             python_executable,
+            expected_sha256: None,
+            extra_args: Vec::new(),
         }
     }
 
-    pub fn extract_stream(&self, page_url: &str) -> Result<ResolvedVideo, EngineError> {
-        let output = self.run_ytdlp(&["-J", "--no-playlist", "--no-warnings", page_url])?;
+    pub fn with_extra_args(mut self, extra_args: Vec<String>) -> Self {
+        self.extra_args = extra_args;
+        self
+    }
+
+    pub fn with_expected_sha256(mut self, expected_sha256: Option<String>) -> Self {
+        self.expected_sha256 = expected_sha256
+            .map(|hash| hash.trim().to_ascii_lowercase())
+            .filter(|hash| !hash.is_empty());
+        self
+    }
+
+    pub fn extract_stream(
+        &self,
+        page_url: &str,
+        cancel: Option<&CancellationToken>,
+    ) -> Result<ResolvedVideo, EngineError> {
+        self.extract_stream_with_height(page_url, None, cancel)
+    }
+
+    pub fn extract_stream_with_height(
+        &self,
+        page_url: &str,
+        max_height: Option<u32>,
+        cancel: Option<&CancellationToken>,
+    ) -> Result<ResolvedVideo, EngineError> {
+        let output = self.run_ytdlp(
+            &self.site_args(&["-J", "--no-playlist", "--no-warnings", page_url]),
+            cancel,
+        )?;
+        check_cancelled(cancel)?;
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
@@ -31,42 +76,41 @@ impl YtDlpClient {
             detail: format!("yt-dlp output is not utf8: {err}"),
         })?;
 
-        let payload = serde_json::from_str::<YtDlpResponse>(&text)?;
+        parse_resolved_video(page_url, &text, max_height)
+    }
 
-        let stream_url = payload
-            .url
-            .or_else(|| {
-                payload.formats.as_ref().and_then(|formats| {
-                    formats
-                        .iter()
-                        .find(|format| {
-                            format
-                                .protocol
-                                .as_ref()
-                                .map(|protocol| protocol.starts_with("http"))
-                                .unwrap_or(false)
-                        })
-                        .and_then(|format| format.url.clone())
-                })
-            })
-            .ok_or_else(|| EngineError::NotFound {
-                detail: "yt-dlp output did not include a stream url".to_string(),
-            })?;
+    // `--simulate` runs extractor matching without downloading; only "Unsupported URL" means no.
+    pub fn supports_url(
+        &self,
+        page_url: &str,
+        cancel: Option<&CancellationToken>,
+    ) -> Result<bool, EngineError> {
+        let output = self.run_ytdlp(
+            &self.site_args(&[
+                "--simulate",
+                "--quiet",
+                "--no-warnings",
+                "--no-playlist",
+                page_url,
+            ]),
+            cancel,
+        )?;
+        check_cancelled(cancel)?;
 
-        Ok(ResolvedVideo {
-            id: payload.id.unwrap_or_else(|| page_url.to_string()),
-            title: payload.title.unwrap_or_else(|| "Untitled".to_string()),
-            page_url: payload.webpage_url.unwrap_or_else(|| page_url.to_string()),
-            stream_url,
-            thumbnail_url: payload.thumbnail,
-            author_name: payload.uploader,
-            extractor: payload.extractor,
-            duration_seconds: payload.duration.map(|value| value as u32),
+        if output.status.success() {
+            return Ok(true);
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if stderr.contains("Unsupported URL") {
+            return Ok(false);
+        }
+        Err(EngineError::Process {
+            detail: format!("yt-dlp support check failed: {stderr}"),
         })
     }
 
     pub fn current_version(&self) -> Result<String, EngineError> {
-        let output = self.run_ytdlp(&["--version"])?;
+        let output = self.run_ytdlp(&["--version"], None)?;
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
@@ -83,7 +127,7 @@ impl YtDlpClient {
     }
 
     pub fn update_binary(&self) -> Result<String, EngineError> {
-        let output = self.run_ytdlp(&["-U"])?;
+        let output = self.run_ytdlp(&["-U"], None)?;
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
@@ -92,15 +136,58 @@ impl YtDlpClient {
             });
         }
 
-        String::from_utf8(output.stdout).map_err(|err| EngineError::Process {
+        let text = String::from_utf8(output.stdout).map_err(|err| EngineError::Process {
             detail: format!("invalid yt-dlp update output: {err}"),
-        })
+        })?;
+        self.verify_binary()?;
+        Ok(text)
     }
 
-    fn run_ytdlp(&self, args: &[&str]) -> Result<std::process::Output, EngineError> {
+    fn verify_binary(&self) -> Result<(), EngineError> {
+        let version = self.current_version().map_err(|err| EngineError::Process {
+            detail: format!("yt-dlp is not runnable after update: {err}"),
+        })?;
+        if !looks_like_version(&version) {
+            return Err(EngineError::Process {
+                detail: format!("yt-dlp reported an unparseable version after update: {version}"),
+            });
+        }
+
+        if let Some(expected) = &self.expected_sha256 {
+            let bytes = fs::read(&self.binary_path).map_err(|err| EngineError::Process {
+                detail: format!("failed to read yt-dlp binary for verification: {err}"),
+            })?;
+            let actual = sha256_hex(&bytes);
+            if &actual != expected {
+                return Err(EngineError::Process {
+                    detail: format!("yt-dlp checksum mismatch: expected {expected}, got {actual}"),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    // Configured extra args only go to invocations that talk to sites, not --version or -U.
+    fn site_args<'a>(&'a self, args: &[&'a str]) -> Vec<&'a str> {
+        self.extra_args
+            .iter()
+            .map(String::as_str)
+            .chain(args.iter().copied())
+            .collect()
+    }
+
+    fn run_ytdlp(
+        &self,
+        args: &[&str],
+        cancel: Option<&CancellationToken>,
+    ) -> Result<std::process::Output, EngineError> {
+        check_cancelled(cancel)?;
         match Command::new(&self.binary_path).args(args).output() {
             Ok(output) => Ok(output),
-            Err(direct_err) => self.run_with_python(args).map_err(|python_err| {
+            Err(direct_err) => self.run_with_python(args, cancel).map_err(|python_err| {
+                if let EngineError::Cancelled { .. } = python_err {
+                    return python_err;
+                }
                 EngineError::Process {
                     detail: format!(
                         "failed to execute yt-dlp directly: {direct_err}; python fallback failed: {python_err}"
@@ -110,7 +197,12 @@ impl YtDlpClient {
         }
     }
 
-    fn run_with_python(&self, args: &[&str]) -> Result<std::process::Output, EngineError> {
+    fn run_with_python(
+        &self,
+        args: &[&str],
+        cancel: Option<&CancellationToken>,
+    ) -> Result<std::process::Output, EngineError> {
+        check_cancelled(cancel)?;
         let module_output = Command::new(&self.python_executable)
             .arg("-m")
             .arg("yt_dlp")
@@ -123,6 +215,7 @@ impl YtDlpClient {
         if module_output.status.success() || !module_missing(&module_output.stderr) {
             return Ok(module_output);
         }
+        check_cancelled(cancel)?;
 
         Command::new(&self.python_executable)
             .arg(&self.binary_path)
@@ -134,7 +227,559 @@ impl YtDlpClient {
     }
 }
 
+// Arguments are passed to the process directly, never through a shell, but reject anything
+// that looks like an attempt to smuggle shell syntax or command execution through config.
+pub fn validate_extra_args(args: &[String]) -> Result<(), EngineError> {
+    const FORBIDDEN_TOKENS: [&str; 7] = [";", "|", "&", "`", "$(", ">", "<"];
+    for arg in args {
+        let invalid = arg.trim().is_empty()
+            || arg.chars().any(char::is_control)
+            || FORBIDDEN_TOKENS.iter().any(|token| arg.contains(token))
+            || arg == "--exec"
+            || arg.starts_with("--exec=")
+            || arg.starts_with("--exec-before-download");
+        if invalid {
+            return Err(EngineError::InvalidConfig {
+                detail: format!("unsafe yt_dlp_extra_args entry: {arg:?}"),
+            });
+        }
+    }
+    Ok(())
+}
+
+pub fn parse_update_output(output: &str) -> YtDlpUpdateResult {
+    let mut result = YtDlpUpdateResult {
+        updated: false,
+        from_version: None,
+        to_version: None,
+        raw_output: output.to_string(),
+    };
+    for line in output.lines().map(str::trim) {
+        if let Some(rest) = line.strip_prefix("Current version:") {
+            result.from_version = first_version(rest);
+        } else if let Some(rest) = line.strip_prefix("Updated yt-dlp to") {
+            result.updated = true;
+            result.to_version = first_version(rest);
+        } else if let Some(rest) = line.strip_prefix("yt-dlp is up to date") {
+            result.to_version = first_version(rest.trim_start_matches([' ', '(']));
+        }
+    }
+    if !result.updated && result.to_version.is_some() && result.from_version.is_none() {
+        result.from_version = result.to_version.clone();
+    }
+    result
+}
+
+// Versions are printed as `channel@YYYY.MM.DD from repo`; keep only the bare version.
+fn first_version(text: &str) -> Option<String> {
+    let token = text.split_whitespace().next()?.trim_end_matches([')', ',']);
+    let version = token.rsplit('@').next().unwrap_or(token);
+    (!version.is_empty()).then(|| version.to_string())
+}
+
+fn looks_like_version(version: &str) -> bool {
+    let mut parts = version.split('.');
+    let year = parts.next().unwrap_or_default();
+    !year.is_empty()
+        && year.chars().all(|ch| ch.is_ascii_digit())
+        && parts
+            .next()
+            .is_some_and(|part| part.starts_with(|ch: char| ch.is_ascii_digit()))
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    digest(&SHA256, bytes)
+        .as_ref()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
 fn module_missing(stderr: &[u8]) -> bool {
     let text = String::from_utf8_lossy(stderr).to_ascii_lowercase();
     text.contains("no module named") && text.contains("yt_dlp")
 }
+
+// Older binaries print warnings to stdout ahead of the JSON document, so fall back to
+// the first line that parses, then to the outermost braces.
+fn parse_ytdlp_json(text: &str) -> Result<YtDlpResponse, EngineError> {
+    if let Ok(payload) = serde_json::from_str::<YtDlpResponse>(text) {
+        return Ok(payload);
+    }
+    let from_line = text
+        .lines()
+        .filter(|line| line.trim_start().starts_with('{'))
+        .find_map(|line| serde_json::from_str::<YtDlpResponse>(line).ok());
+    let from_braces = || {
+        let start = text.find('{')?;
+        let end = text.rfind('}')?;
+        serde_json::from_str::<YtDlpResponse>(text.get(start..=end)?).ok()
+    };
+    from_line
+        .or_else(from_braces)
+        .ok_or_else(|| EngineError::Process {
+            detail: format!(
+                "yt-dlp output did not contain a JSON object: {}",
+                payload_snippet(text)
+            ),
+        })
+}
+
+fn parse_resolved_video(
+    page_url: &str,
+    text: &str,
+    max_height: Option<u32>,
+) -> Result<ResolvedVideo, EngineError> {
+    let payload = parse_ytdlp_json(text)?;
+
+    let formats = payload.formats.as_deref().unwrap_or_default();
+    let preferred = max_height
+        .and_then(|max_height| select_format_by_height(formats, max_height))
+        .map(format_stream);
+    let top_level = payload.url.map(|url| (url, payload.protocol.clone()));
+    let (stream_url, stream_protocol) = preferred
+        .or(top_level)
+        .or_else(|| {
+            formats
+                .iter()
+                .find(|format| format.url.is_some() && is_progressive(format))
+                .or_else(|| {
+                    formats
+                        .iter()
+                        .find(|format| format.url.is_some() && is_playable(format))
+                })
+                .map(format_stream)
+        })
+        .ok_or_else(|| EngineError::NotFound {
+            detail: "yt-dlp output did not include a stream url".to_string(),
+        })?;
+
+    let thumbnails = collect_thumbnails(payload.thumbnails.unwrap_or_default());
+    let thumbnail_url = largest_thumbnail(&thumbnails)
+        .map(|thumbnail| thumbnail.url.clone())
+        .or(payload.thumbnail);
+
+    Ok(ResolvedVideo {
+        id: payload.id.unwrap_or_else(|| page_url.to_string()),
+        title: payload.title.unwrap_or_else(|| "Untitled".to_string()),
+        page_url: payload.webpage_url.unwrap_or_else(|| page_url.to_string()),
+        stream_url,
+        thumbnail_url,
+        author_name: payload.uploader,
+        extractor: payload.extractor,
+        duration_seconds: payload.duration.map(|value| value as u32),
+        view_count: payload.view_count,
+        upload_date_epoch: payload.upload_date.as_deref().and_then(parse_upload_date),
+        description: payload
+            .description
+            .filter(|description| !description.trim().is_empty()),
+        stream_protocol,
+        thumbnails,
+    })
+}
+
+fn collect_thumbnails(thumbnails: Vec<YtDlpThumbnail>) -> Vec<Thumbnail> {
+    thumbnails
+        .into_iter()
+        .filter_map(|thumbnail| {
+            let url = thumbnail.url.filter(|url| !url.trim().is_empty())?;
+            Some(Thumbnail {
+                url,
+                width: thumbnail.width,
+                height: thumbnail.height,
+            })
+        })
+        .collect()
+}
+
+// yt-dlp lists thumbnails worst to best, so among equal (or unknown) sizes the last one wins.
+fn largest_thumbnail(thumbnails: &[Thumbnail]) -> Option<&Thumbnail> {
+    thumbnails.iter().max_by_key(|thumbnail| {
+        (
+            thumbnail.height.unwrap_or_default(),
+            thumbnail.width.unwrap_or_default(),
+        )
+    })
+}
+
+fn is_progressive(format: &YtDlpFormat) -> bool {
+    format
+        .protocol
+        .as_deref()
+        .is_some_and(|protocol| PROGRESSIVE_PROTOCOLS.contains(&protocol))
+}
+
+fn is_playable(format: &YtDlpFormat) -> bool {
+    is_progressive(format)
+        || format
+            .protocol
+            .as_deref()
+            .is_some_and(|protocol| STREAMING_PROTOCOLS.contains(&protocol))
+}
+
+fn format_stream(format: &YtDlpFormat) -> (String, Option<String>) {
+    (
+        format.url.clone().unwrap_or_default(),
+        format.protocol.clone(),
+    )
+}
+
+// Best format at or below `max_height`, else the tallest one available; progressive wins over streaming.
+fn select_format_by_height(formats: &[YtDlpFormat], max_height: u32) -> Option<&YtDlpFormat> {
+    let pick = |accept: fn(&YtDlpFormat) -> bool| {
+        let candidates = formats.iter().filter_map(|format| {
+            match (accept(format), format.height, &format.url) {
+                (true, Some(height), Some(_)) => Some((height, format)),
+                _ => None,
+            }
+        });
+        let (fitting, too_tall): (Vec<_>, Vec<_>) =
+            candidates.partition(|(height, _)| *height <= max_height);
+        fitting
+            .into_iter()
+            .max_by_key(|(height, _)| *height)
+            .or_else(|| too_tall.into_iter().max_by_key(|(height, _)| *height))
+            .map(|(_, format)| format)
+    };
+    pick(is_progressive).or_else(|| pick(is_playable))
+}
+
+// yt-dlp reports upload dates as YYYYMMDD; treat them as midnight UTC.
+fn parse_upload_date(value: &str) -> Option<i64> {
+    let date = NaiveDate::parse_from_str(value.trim(), "%Y%m%d").ok()?;
+    Some(date.and_hms_opt(0, 0, 0)?.and_utc().timestamp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_extra_metadata_and_upload_date() {
+        let fixture = r#"{
+            "id": "abc",
+            "title": "Clip",
+            "webpage_url": "https://example.com/watch?v=abc",
+            "url": "https://cdn.example.com/abc.mp4",
+            "extractor": "generic",
+            "view_count": 1234,
+            "like_count": 56,
+            "upload_date": "20240115",
+            "description": "A short clip"
+        }"#;
+
+        let resolved = parse_resolved_video("https://example.com/watch?v=abc", fixture, None)
+            .expect("parse resolved video");
+
+        assert_eq!(resolved.view_count, Some(1234));
+        assert_eq!(resolved.upload_date_epoch, Some(1_705_276_800));
+        assert_eq!(resolved.description.as_deref(), Some("A short clip"));
+        assert_eq!(parse_upload_date("2024-01-15"), None);
+        assert_eq!(parse_upload_date("20241350"), None);
+    }
+
+    #[test]
+    fn picks_largest_thumbnail_and_lists_all() {
+        let fixture = r#"{
+            "id": "abc",
+            "url": "https://cdn.example.com/abc.mp4",
+            "thumbnail": "https://img.example.com/default.jpg",
+            "thumbnails": [
+                { "url": "https://img.example.com/small.jpg", "width": 120, "height": 90 },
+                { "url": "https://img.example.com/large.jpg", "width": 1280, "height": 720 },
+                { "url": "https://img.example.com/medium.jpg", "width": 480, "height": 360 },
+                { "id": "no-url" }
+            ]
+        }"#;
+
+        let resolved = parse_resolved_video("https://example.com/watch?v=abc", fixture, None)
+            .expect("parse resolved video");
+
+        assert_eq!(
+            resolved.thumbnail_url.as_deref(),
+            Some("https://img.example.com/large.jpg")
+        );
+        let urls: Vec<&str> = resolved
+            .thumbnails
+            .iter()
+            .map(|thumbnail| thumbnail.url.as_str())
+            .collect();
+        assert_eq!(
+            urls,
+            [
+                "https://img.example.com/small.jpg",
+                "https://img.example.com/large.jpg",
+                "https://img.example.com/medium.jpg",
+            ]
+        );
+        assert_eq!(resolved.thumbnails[1].width, Some(1280));
+
+        let single = parse_resolved_video(
+            "https://example.com/watch?v=abc",
+            r#"{ "url": "https://cdn.example.com/abc.mp4", "thumbnail": "https://img.example.com/only.jpg" }"#,
+            None,
+        )
+        .expect("parse single thumbnail");
+        assert_eq!(
+            single.thumbnail_url.as_deref(),
+            Some("https://img.example.com/only.jpg")
+        );
+        assert!(single.thumbnails.is_empty());
+    }
+
+    #[test]
+    fn skips_warning_lines_before_json_output() {
+        let stdout = "WARNING: Your yt-dlp version is older than 90 days\n\
+            {\"id\": \"abc\", \"title\": \"Clip\", \"url\": \"https://cdn.example.com/abc.mp4\"}\n";
+
+        let resolved = parse_resolved_video("https://example.com/watch?v=abc", stdout, None)
+            .expect("parse after warning");
+
+        assert_eq!(resolved.id, "abc");
+        assert_eq!(resolved.stream_url, "https://cdn.example.com/abc.mp4");
+
+        let err = parse_resolved_video(
+            "https://example.com/watch?v=abc",
+            "Deprecated feature\n",
+            None,
+        )
+        .expect_err("no json");
+        assert!(
+            matches!(err, EngineError::Process { ref detail } if detail.contains("Deprecated feature"))
+        );
+    }
+
+    #[test]
+    fn picks_best_format_at_or_below_max_height() {
+        let fixture = r#"{
+            "id": "abc",
+            "url": "https://cdn.example.com/default.mp4",
+            "formats": [
+                { "url": "https://cdn.example.com/360.mp4", "protocol": "https", "height": 360 },
+                { "url": "https://cdn.example.com/1080.mp4", "protocol": "https", "height": 1080 },
+                { "url": "https://cdn.example.com/720.mp4", "protocol": "https", "height": 720 },
+                { "url": "https://cdn.example.com/720.m3u8", "protocol": "m3u8_native", "height": 720 }
+            ]
+        }"#;
+        let page_url = "https://example.com/watch?v=abc";
+
+        let resolved = parse_resolved_video(page_url, fixture, Some(720)).expect("720p");
+        assert_eq!(resolved.stream_url, "https://cdn.example.com/720.mp4");
+
+        let resolved = parse_resolved_video(page_url, fixture, Some(240)).expect("240p");
+        assert_eq!(resolved.stream_url, "https://cdn.example.com/1080.mp4");
+
+        let resolved = parse_resolved_video(page_url, fixture, None).expect("default");
+        assert_eq!(resolved.stream_url, "https://cdn.example.com/default.mp4");
+    }
+
+    #[test]
+    fn selects_m3u8_native_when_it_is_the_only_format() {
+        let fixture = r#"{
+            "id": "hls",
+            "formats": [
+                { "url": "https://cdn.example.com/hls/master.m3u8", "protocol": "m3u8_native", "height": 720 },
+                { "url": "rtmp://cdn.example.com/live", "protocol": "rtmp", "height": 1080 }
+            ]
+        }"#;
+
+        let resolved = parse_resolved_video("https://example.com/watch?v=hls", fixture, None)
+            .expect("parse hls video");
+
+        assert_eq!(
+            resolved.stream_url,
+            "https://cdn.example.com/hls/master.m3u8"
+        );
+        assert_eq!(resolved.stream_protocol.as_deref(), Some("m3u8_native"));
+    }
+
+    #[test]
+    fn prefers_progressive_formats_over_streaming() {
+        let fixture = r#"{
+            "id": "mixed",
+            "formats": [
+                { "url": "https://cdn.example.com/mixed.mpd", "protocol": "http_dash_segments", "height": 720 },
+                { "url": "https://cdn.example.com/mixed.mp4", "protocol": "https", "height": 480 }
+            ]
+        }"#;
+        let page_url = "https://example.com/watch?v=mixed";
+
+        for max_height in [None, Some(720)] {
+            let resolved = parse_resolved_video(page_url, fixture, max_height).expect("mixed");
+            assert_eq!(resolved.stream_url, "https://cdn.example.com/mixed.mp4");
+            assert_eq!(resolved.stream_protocol.as_deref(), Some("https"));
+        }
+    }
+
+    #[test]
+    fn parses_update_output_for_updated_binary() {
+        let output = "Current version: stable@2024.07.25 from yt-dlp/yt-dlp\n\
+            Latest version: stable@2024.08.06 from yt-dlp/yt-dlp\n\
+            Current Build Hash: 0123456789abcdef\n\
+            Updating to stable@2024.08.06 from yt-dlp/yt-dlp ...\n\
+            Updated yt-dlp to stable@2024.08.06 from yt-dlp/yt-dlp\n";
+
+        let result = parse_update_output(output);
+
+        assert!(result.updated);
+        assert_eq!(result.from_version.as_deref(), Some("2024.07.25"));
+        assert_eq!(result.to_version.as_deref(), Some("2024.08.06"));
+        assert_eq!(result.raw_output, output);
+    }
+
+    #[test]
+    fn parses_update_output_when_already_current() {
+        let output = "Latest version: stable@2024.08.06 from yt-dlp/yt-dlp\n\
+            yt-dlp is up to date (stable@2024.08.06 from yt-dlp/yt-dlp)\n";
+
+        let result = parse_update_output(output);
+
+        assert!(!result.updated);
+        assert_eq!(result.from_version.as_deref(), Some("2024.08.06"));
+        assert_eq!(result.to_version.as_deref(), Some("2024.08.06"));
+    }
+
+    #[cfg(unix)]
+    fn fake_binary(dir: &std::path::Path, version_output: &str) -> String {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = dir.join("yt-dlp");
+        let script = format!(
+            "#!/bin/sh\nif [ \"$1\" = \"-U\" ]; then echo 'Updated yt-dlp to 2024.08.06'; exit 0; fi\necho '{version_output}'\n"
+        );
+        fs::write(&path, script).expect("write fake yt-dlp");
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).expect("chmod fake yt-dlp");
+        path.to_string_lossy().into_owned()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn update_fails_when_new_binary_reports_garbage_version() {
+        let tmp = tempfile::tempdir().expect("tmpdir");
+        let client = YtDlpClient::new(
+            fake_binary(tmp.path(), "Traceback (most recent call last)"),
+            "/nonexistent/python3".to_string(),
+        );
+
+        let err = client.update_binary().expect_err("broken binary");
+        assert!(
+            matches!(err, EngineError::Process { ref detail } if detail.contains("unparseable version"))
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn update_verifies_expected_checksum() {
+        let tmp = tempfile::tempdir().expect("tmpdir");
+        let binary = fake_binary(tmp.path(), "2024.08.06");
+        let actual = sha256_hex(&fs::read(&binary).expect("read fake binary"));
+
+        let verified = YtDlpClient::new(binary.clone(), "/nonexistent/python3".to_string())
+            .with_expected_sha256(Some(actual.to_ascii_uppercase()));
+        assert!(verified.update_binary().is_ok());
+
+        let mismatched = YtDlpClient::new(binary, "/nonexistent/python3".to_string())
+            .with_expected_sha256(Some("00".repeat(32)));
+        let err = mismatched.update_binary().expect_err("checksum mismatch");
+        assert!(
+            matches!(err, EngineError::Process { ref detail } if detail.contains("checksum mismatch"))
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn supports_url_follows_simulate_exit_status() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmp = tempfile::tempdir().expect("tmpdir");
+        let path = tmp.path().join("yt-dlp");
+        fs::write(
+            &path,
+            "#!/bin/sh
+for arg; do last=$arg; done
+case \"$last\" in
+  https://videos.example/*) exit 0 ;;
+  https://broken.example/*) echo 'ERROR: Unable to download webpage' >&2; exit 1 ;;
+esac
+echo \"ERROR: Unsupported URL: $last\" >&2
+exit 1
+",
+        )
+        .expect("write fake yt-dlp");
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).expect("chmod fake yt-dlp");
+        let client = YtDlpClient::new(
+            path.to_string_lossy().into_owned(),
+            "/nonexistent/python3".to_string(),
+        );
+
+        assert!(client
+            .supports_url("https://videos.example/watch/1", None)
+            .expect("supported url"));
+        assert!(!client
+            .supports_url("https://blog.example/post/1", None)
+            .expect("unsupported url"));
+        let err = client
+            .supports_url("https://broken.example/watch/1", None)
+            .expect_err("extractor failure");
+        assert!(
+            matches!(err, EngineError::Process { ref detail } if detail.contains("Unable to download"))
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn extra_args_apply_to_extraction_but_not_version() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmp = tempfile::tempdir().expect("tmpdir");
+        let log = tmp.path().join("argv.log");
+        let path = tmp.path().join("yt-dlp");
+        fs::write(
+            &path,
+            format!(
+                "#!/bin/sh\necho \"$*\" >> '{}'\nif [ \"$1\" = \"--version\" ]; then echo 2025.01.01; exit 0; fi\necho '{{\"id\": \"abc\", \"url\": \"https://cdn.example.com/abc.mp4\"}}'\n",
+                log.display()
+            ),
+        )
+        .expect("write fake yt-dlp");
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).expect("chmod fake yt-dlp");
+        let client = YtDlpClient::new(
+            path.to_string_lossy().into_owned(),
+            "/nonexistent/python3".to_string(),
+        )
+        .with_extra_args(vec![
+            "--geo-bypass".to_string(),
+            "--source-address=10.0.0.2".to_string(),
+        ]);
+
+        client
+            .extract_stream("https://example.com/watch?v=abc", None)
+            .expect("extract");
+        client.current_version().expect("version");
+
+        let argv = fs::read_to_string(&log).expect("read argv log");
+        let lines: Vec<&str> = argv.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("--geo-bypass --source-address=10.0.0.2 -J"));
+        assert_eq!(lines[1], "--version");
+    }
+
+    #[test]
+    fn rejects_shell_like_extra_args() {
+        let ok = ["--geo-bypass", "--limit-rate", "2M"].map(String::from);
+        assert!(validate_extra_args(&ok).is_ok());
+
+        for bad in [
+            "--proxy=x; rm -rf /",
+            "$(id)",
+            "--exec",
+            "--exec=curl",
+            "a|b",
+            "line\nbreak",
+            " ",
+        ] {
+            let err = validate_extra_args(&[bad.to_string()]).expect_err(bad);
+            assert!(matches!(err, EngineError::InvalidConfig { .. }));
+        }
+    }
+}
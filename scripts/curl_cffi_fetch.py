@@ -2,21 +2,30 @@
 """Fetch a URL with curl-cffi impersonation.
 
 Usage:
-  python curl_cffi_fetch.py <method> <url> <payload_json>
+  python curl_cffi_fetch.py <method> <url> <payload_json> [headers_json] [impersonate]
+
+Set WHIRLPOOL_PROXY_URL to route the request through a proxy. The impersonation
+profile defaults to chrome124.
 """
 
 import json
+import os
 import sys
 
 
 def main() -> int:
-    if len(sys.argv) != 4:
-        print("expected arguments: <method> <url> <payload_json>", file=sys.stderr)
+    if len(sys.argv) not in (4, 5, 6):
+        print(
+            "expected arguments: <method> <url> <payload_json> [headers_json] [impersonate]",
+            file=sys.stderr,
+        )
         return 2
 
     method = sys.argv[1].upper()
     url = sys.argv[2]
     payload = json.loads(sys.argv[3])
+    headers = json.loads(sys.argv[4]) if len(sys.argv) >= 5 else {}
+    impersonate = sys.argv[5] if len(sys.argv) == 6 else "chrome124"
 
     try:
         from curl_cffi import requests
@@ -27,9 +36,14 @@ def main() -> int:
     try:
         kwargs = {
             "url": url,
-            "impersonate": "chrome124",
+            "impersonate": impersonate,
             "timeout": 20,
         }
+        if headers:
+            kwargs["headers"] = headers
+        proxy_url = os.environ.get("WHIRLPOOL_PROXY_URL")
+        if proxy_url:
+            kwargs["proxies"] = {"http": proxy_url, "https": proxy_url}
         if method == "GET":
             kwargs["params"] = payload
         else: